@@ -0,0 +1,126 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use serde_json::{Map, json};
+use std::hint::black_box;
+
+use cloudmaid::prelude::*;
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+// A CDK-shaped stack: each service is a queue, a table, a function configured
+// with both and a mapping triggering it from the queue, plus a role that
+// references nothing drawn. References run across the whole template, which is
+// what made the old per-resource search quadratic.
+fn template(resources: usize) -> String {
+  let mut map = Map::new();
+
+  for i in 0..resources.div_ceil(5) {
+    map.insert(format!("Queue{}", i), json!({ "Type": "AWS::SQS::Queue", "Properties": { "QueueName": format!("queue-{}", i) } }));
+    map.insert(
+      format!("Table{}", i),
+      json!({ "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": format!("table-{}", i) } }),
+    );
+    map.insert(
+      format!("Role{}", i),
+      json!({
+        "Type": "AWS::IAM::Role",
+        "Properties": { "AssumeRolePolicyDocument": { "Statement": [{ "Action": "sts:AssumeRole", "Effect": "Allow" }] } },
+        "Metadata": { "aws:cdk:path": format!("App/Service{}/Role/Resource", i) }
+      }),
+    );
+    map.insert(
+      format!("Function{}", i),
+      json!({
+        "Type": "AWS::Lambda::Function",
+        "Properties": {
+          "FunctionName": format!("function-{}", i),
+          "Architectures": ["arm64"],
+          "Role": { "Fn::GetAtt": [format!("Role{}", i), "Arn"] },
+          "Environment": {
+            "Variables": { "QUEUE_URL": { "Ref": format!("Queue{}", i) }, "TABLE": { "Ref": format!("Table{}", i) } }
+          }
+        },
+        "Metadata": { "aws:cdk:path": format!("App/Service{}/Function/Resource", i) }
+      }),
+    );
+    map.insert(
+      format!("Mapping{}", i),
+      json!({
+        "Type": "AWS::Lambda::EventSourceMapping",
+        "Properties": {
+          "EventSourceArn": { "Fn::GetAtt": [format!("Queue{}", i), "Arn"] },
+          "FunctionName": { "Ref": format!("Function{}", i) }
+        }
+      }),
+    );
+  }
+
+  json!({ "Resources": map }).to_string()
+}
+
+fn parse(c: &mut Criterion) {
+  let mut group = c.benchmark_group("parse");
+  group.sample_size(10);
+
+  for size in SIZES {
+    let json = template(size);
+    group.throughput(Throughput::Bytes(json.len() as u64));
+    group.bench_with_input(BenchmarkId::from_parameter(size), &json, |b, json| {
+      b.iter(|| serde_json::from_str::<Template>(black_box(json)).unwrap())
+    });
+  }
+  group.finish();
+}
+
+fn graph(c: &mut Criterion) {
+  let mut group = c.benchmark_group("graph");
+  group.sample_size(10);
+
+  for size in SIZES {
+    let template: Template = serde_json::from_str(&template(size)).unwrap();
+    group.throughput(Throughput::Elements(size as u64));
+    group.bench_with_input(BenchmarkId::from_parameter(size), &template, |b, template| {
+      b.iter(|| AST::from(black_box(template.clone())))
+    });
+  }
+  group.finish();
+}
+
+fn render(c: &mut Criterion) {
+  let mut group = c.benchmark_group("render");
+  group.sample_size(10);
+
+  for size in SIZES {
+    let template: Template = serde_json::from_str(&template(size)).unwrap();
+    let ast = AST::from(template);
+    for format in [Format::Mermaid, Format::PlantUml] {
+      let renderer = format.renderer(RenderOptions::default());
+      group.throughput(Throughput::Elements(size as u64));
+      group.bench_with_input(BenchmarkId::new(format!("{:?}", format), size), &ast, |b, ast| {
+        b.iter(|| renderer.render(black_box(ast)))
+      });
+    }
+  }
+  group.finish();
+}
+
+// What every render asks of the graph: its nodes, their IDs and a filtered
+// copy. Each used to scan all nodes again per node.
+fn query(c: &mut Criterion) {
+  let mut group = c.benchmark_group("query");
+  group.sample_size(10);
+
+  for size in SIZES {
+    let template: Template = serde_json::from_str(&template(size)).unwrap();
+    let ast = AST::from(template);
+    group.throughput(Throughput::Elements(size as u64));
+    group.bench_with_input(BenchmarkId::new("nodes", size), &ast, |b, ast| b.iter(|| black_box(ast).nodes().len()));
+    group.bench_with_input(BenchmarkId::new("ids", size), &ast, |b, ast| b.iter(|| black_box(ast).ids()));
+    group.bench_with_input(BenchmarkId::new("retain", size), &ast, |b, ast| {
+      b.iter(|| black_box(ast.clone()).retain(|node| node.typ != ResourceType::DynamoDb))
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, parse, graph, render, query);
+criterion_main!(benches);
@@ -1,2 +1,31 @@
-pub mod ast;
-pub mod node;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub(crate) mod ast;
+pub mod node;
+pub mod subgraph;
+pub mod edge;
+pub mod graph;
+pub mod ids;
+pub mod cache;
+pub(crate) mod cross_stack;
+pub(crate) mod imports;
+pub(crate) mod construct;
+pub(crate) mod layers;
+pub(crate) mod aliases;
+pub(crate) mod depends_on;
+pub(crate) mod missing;
+pub(crate) mod event_rule;
+pub(crate) mod state_machine;
+pub(crate) mod glue;
+pub(crate) mod firehose;
+pub(crate) mod efs;
+pub(crate) mod pipeline;
+pub(crate) mod iot;
+pub(crate) mod ses;
+pub(crate) mod parameters;
+pub(crate) mod permissions;
+pub(crate) mod api;
+pub mod diff;
+pub mod query;
+pub mod analysis;
+pub mod split;
+pub use ast::AST;
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use crate::ast::ast::AST;
+use crate::ast::node::Node;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::stack::Stack;
+
+// Versions and aliases are folded into their function when the template is
+// read; this only appends the alias names, `worker:live,canary`.
+pub fn suffix(ast: &mut AST, stacks: &[Stack]) {
+  let aliased: Vec<(Node, String)> = stacks
+    .iter()
+    .flat_map(|stack| stack.template.resources.iter())
+    .filter(|resource| !resource.aliases.is_empty())
+    .map(|resource| (Node::from(resource), resource.aliases.join(",")))
+    .collect();
+
+  ast.for_each_node(|node| {
+    let Some((_, aliases)) = aliased.iter().find(|(function, _)| function == node) else {
+      return;
+    };
+    let name = node.get_name().to_string();
+    if let Property::Lambda { function_name, .. } = Arc::make_mut(&mut node.properties) {
+      *function_name = format!("{}:{}", name, aliases).into();
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_versions_and_aliases_collapse_into_function() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "WorkerVersion": { "Type": "AWS::Lambda::Version", "Properties": { "FunctionName": { "Ref": "Worker" } } },
+        "Live": {
+          "Type": "AWS::Lambda::Alias",
+          "Properties": {
+            "FunctionName": { "Ref": "Worker" },
+            "FunctionVersion": { "Fn::GetAtt": ["WorkerVersion", "Version"] },
+            "Name": "live"
+          }
+        },
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Live" } }
+        },
+        "Api": {
+          "Type": "AWS::ApiGateway::Method",
+          "Properties": {
+            "HttpMethod": "GET",
+            "Integration": { "Uri": { "Fn::Sub": "arn:aws:apigateway:${AWS::Region}:lambda:path/2015-03-31/functions/${Live}/invocations" } }
+          }
+        }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template: template.clone(),
+    }];
+
+    let mut ast = AST::from(template.clone());
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Api[[Api]] -->|invokes| Worker([\"worker\"])\n",
+      "Queue((\"jobs\")) -.->|triggers| Worker([\"worker\"])\n",
+      "```",
+    );
+    assert_eq!(template.resources.len(), 4);
+    assert_eq!(ast.to_mermaid(), expected_output);
+
+    suffix(&mut ast, &stacks);
+    assert!(ast.to_mermaid().contains("Api[[Api]] -->|invokes| Worker([\"worker:live\"])\n"));
+  }
+
+  #[test]
+  fn test_untyped_resources_are_only_rewritten_when_naming_an_alias() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker" } },
+        "Live": { "Type": "AWS::Lambda::Alias", "Properties": { "FunctionName": { "Ref": "Worker" }, "Name": "live" } },
+        "Invoke": {
+          "Type": "AWS::Lambda::Permission",
+          "Properties": { "FunctionName": { "Ref": "Live" }, "Action": "lambda:InvokeFunction" }
+        },
+        "Logs": { "Type": "AWS::Logs::LogGroup", "Properties": { "RetentionInDays": 7 } }
+      }
+    }))
+    .unwrap();
+
+    let untyped = |index: usize| match &*template.resources[index].properties {
+      Property::Unparsed(properties) => properties.text().to_string(),
+      other => panic!("{:?} was parsed", other),
+    };
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&untyped(1)).unwrap()["FunctionName"], json!({ "Ref": "Worker" }));
+    assert_eq!(untyped(2), r#"{"RetentionInDays":7}"#);
+  }
+}
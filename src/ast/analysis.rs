@@ -0,0 +1,386 @@
+use std::collections::BTreeMap;
+
+use petgraph::graph::NodeIndex;
+use serde::Serialize;
+
+use crate::ast::ast::AST;
+use crate::ast::edge::Edge;
+use crate::ast::graph::{Graph, neighbours};
+use crate::ast::node::Node;
+use crate::cloudformation::resource::ResourceType;
+use crate::cloudformation::stack::Stack;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Orphan {
+  pub stack: String,
+  pub node: Node,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Analysis {
+  pub orphans: Vec<Orphan>,
+  pub cycles: Vec<Vec<Node>>,
+}
+
+impl Analysis {
+  pub fn new(ast: &AST, stacks: &[Stack]) -> Self {
+    Analysis {
+      orphans: orphans(ast, stacks),
+      cycles: cycles(ast),
+    }
+  }
+
+  pub fn to_text(&self) -> String {
+    let mut result = format!("Orphaned resources ({}):\n", self.orphans.len());
+
+    for orphan in &self.orphans {
+      result.push_str(&format!(
+        "  {}  {}  {}\n",
+        orphan.stack, orphan.node.name.0, orphan.node.typ
+      ));
+    }
+
+    result.push_str(&format!("Cycles ({}):\n", self.cycles.len()));
+
+    for cycle in &self.cycles {
+      result.push_str(&format!("  {}\n", describe(cycle)));
+    }
+
+    result
+  }
+}
+
+const TOP_NODES: usize = 5;
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct Stats {
+  pub resources: BTreeMap<String, usize>,
+  pub nodes: usize,
+  pub edges: usize,
+  pub cross_stack_edges: usize,
+  pub fan_in: Vec<(String, usize)>,
+  pub fan_out: Vec<(String, usize)>,
+  pub components: usize,
+}
+
+impl Stats {
+  pub fn new(ast: &AST, stacks: &[Stack]) -> Self {
+    let mut resources = BTreeMap::new();
+    for resource in stacks.iter().flat_map(|stack| stack.template.resources.iter()) {
+      *resources.entry(resource.typ.to_string()).or_insert(0) += 1;
+    }
+
+    let nodes = ast.nodes();
+    let edges: Vec<&Edge> = ast.edges.iter().chain(ast.cross_stack_edges.iter()).collect();
+    let degree = |endpoint: fn(&Edge) -> &Node| {
+      let mut counts: Vec<(String, usize)> = nodes
+        .iter()
+        .map(|node| (node.name.0.to_string(), edges.iter().filter(|edge| endpoint(edge) == *node).count()))
+        .filter(|(_, count)| *count > 0)
+        .collect();
+      counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+      counts.truncate(TOP_NODES);
+      counts
+    };
+
+    Stats {
+      resources,
+      nodes: nodes.len(),
+      edges: ast.edges.len(),
+      cross_stack_edges: ast.cross_stack_edges.len(),
+      fan_in: degree(|edge| &edge.to),
+      fan_out: degree(|edge| &edge.from),
+      components: components(ast),
+    }
+  }
+
+  pub fn to_text(&self) -> String {
+    let mut result = String::from("Resources by type:\n");
+    for (typ, count) in &self.resources {
+      result.push_str(&format!("  {:<20}{}\n", typ, count));
+    }
+
+    result.push_str(&format!("Nodes: {}\n", self.nodes));
+    result.push_str(&format!("Edges: {} ({} cross-stack)\n", self.edges + self.cross_stack_edges, self.cross_stack_edges));
+    result.push_str(&format!("Connected components: {}\n", self.components));
+
+    for (heading, counts) in [("Highest fan-in:", &self.fan_in), ("Highest fan-out:", &self.fan_out)] {
+      result.push_str(heading);
+      result.push('\n');
+      for (name, count) in counts {
+        result.push_str(&format!("  {:<20}{}\n", name, count));
+      }
+    }
+
+    result
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap_or_default()
+  }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Listed {
+  pub stack: String,
+  pub id: String,
+  #[serde(rename = "type")]
+  pub typ: String,
+  pub name: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct Listing {
+  pub resources: Vec<Listed>,
+}
+
+impl Listing {
+  pub fn new(stacks: &[Stack], keep: impl Fn(&Node) -> bool) -> Self {
+    let resources = stacks
+      .iter()
+      .flat_map(|stack| stack.template.resources.iter().map(move |resource| (stack, resource)))
+      .filter(|(_, resource)| resource.typ.is_supported())
+      .map(|(stack, resource)| (stack, Node::from(resource)))
+      .filter(|(_, node)| keep(node))
+      .map(|(stack, node)| Listed {
+        stack: stack.name.clone(),
+        id: node.name.0.to_string(),
+        typ: node.typ.to_string(),
+        name: node.get_name().to_string(),
+      })
+      .collect();
+
+    Listing { resources }
+  }
+
+  // Columns are as wide as their longest value, as CDK logical IDs run long.
+  pub fn to_text(&self) -> String {
+    let width = |column: fn(&Listed) -> &str| self.resources.iter().map(|r| column(r).len()).max().unwrap_or(0) + 2;
+    let (stack, id, typ) = (width(|r| &r.stack), width(|r| &r.id), width(|r| &r.typ));
+
+    let mut result = format!("Resources ({}):\n", self.resources.len());
+    for r in &self.resources {
+      result.push_str(&format!("  {:<stack$}{:<id$}{:<typ$}{}\n", r.stack, r.id, r.typ, r.name));
+    }
+    result
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap_or_default()
+  }
+}
+
+// Weakly connected components: edge direction is ignored.
+fn components(ast: &AST) -> usize {
+  petgraph::algo::connected_components(&ast.graph())
+}
+
+pub fn describe(cycle: &[Node]) -> String {
+  let closed: Vec<Node> = cycle.iter().chain(cycle.first()).cloned().collect();
+  describe_path(&closed)
+}
+
+pub fn describe_path(path: &[Node]) -> String {
+  let names: Vec<&str> = path.iter().map(|node| &*node.name.0).collect();
+  names.join(" -> ")
+}
+
+// Each strongly connected component with more than one node (or a node with an
+// edge to itself) contains at least one loop; one representative loop is
+// reported per component so a tangle of functions is one finding, not dozens.
+pub fn cycles(ast: &AST) -> Vec<Vec<Node>> {
+  let graph = ast.graph();
+
+  let mut components: Vec<Vec<NodeIndex>> = petgraph::algo::tarjan_scc(&graph)
+    .into_iter()
+    .map(|mut component| {
+      component.sort();
+      component
+    })
+    .collect();
+  components.sort();
+
+  components
+    .into_iter()
+    .filter(|component| component.len() > 1 || graph.contains_edge(component[0], component[0]))
+    .filter_map(|component| find_loop(&graph, &component))
+    .map(|cycle| cycle.into_iter().map(|index| graph[index].clone()).collect())
+    .collect()
+}
+
+// Breadth-first search inside the component gives the shortest loop back to
+// its first node.
+fn find_loop(graph: &Graph, component: &[NodeIndex]) -> Option<Vec<NodeIndex>> {
+  let start = component[0];
+  let mut previous: Vec<Option<NodeIndex>> = vec![None; graph.node_count()];
+  let mut queue = std::collections::VecDeque::from([start]);
+
+  while let Some(node) = queue.pop_front() {
+    for successor in neighbours(graph, node, petgraph::Direction::Outgoing) {
+      if !component.contains(&successor) {
+        continue;
+      }
+
+      if successor == start {
+        let mut cycle = vec![node];
+        let mut current = node;
+        while let Some(prior) = previous[current.index()] {
+          cycle.push(prior);
+          current = prior;
+        }
+        cycle.reverse();
+        return Some(cycle);
+      }
+
+      if previous[successor.index()].is_none() {
+        previous[successor.index()] = Some(node);
+        queue.push_back(successor);
+      }
+    }
+  }
+
+  None
+}
+
+// Event source mappings are drawn as the edge they create rather than as a
+// node, so they are never reported; unsupported types are never drawn at all.
+fn orphans(ast: &AST, stacks: &[Stack]) -> Vec<Orphan> {
+  let nodes = ast.nodes();
+  let mut orphans = Vec::new();
+
+  for stack in stacks {
+    for resource in &stack.template.resources {
+      if !resource.typ.is_supported() || resource.typ == ResourceType::EventSourceMapping {
+        continue;
+      }
+
+      let node = Node::from(resource);
+      if !nodes.contains(&&node) {
+        orphans.push(Orphan {
+          stack: stack.name.clone(),
+          node,
+        });
+      }
+    }
+  }
+
+  orphans
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::Name;
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_orphaned_resources() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "Table": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "unused" } },
+        "Bucket": { "Type": "AWS::S3::Bucket", "Properties": {} },
+        "Role": { "Type": "AWS::IAM::Role", "Properties": {} }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template: template.clone(),
+    }];
+
+    let analysis = Analysis::new(&AST::from(template), &stacks);
+
+    assert_eq!(analysis.to_text(), "Orphaned resources (2):\n  app  Table  dynamodb\n  app  Bucket  bucket\nCycles (0):\n");
+  }
+
+  #[test]
+  fn test_cycles() {
+    let queue = Node {
+      name: Name::from("Queue"),
+      typ: ResourceType::Sqs,
+      properties: Property::Sqs {
+        queue_name: "jobs".into(),
+      }.into(),
+    };
+    let worker = Node {
+      name: Name::from("Worker"),
+      typ: ResourceType::Lambda,
+      properties: Property::lambda("worker").into(),
+    };
+    let ast = AST {
+      edges: vec![Edge::new(queue.clone(), worker.clone()), Edge::new(worker, queue)],
+      ..Default::default()
+    };
+
+    let cycles = cycles(&ast);
+
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(describe(&cycles[0]), "Queue -> Worker -> Queue");
+  }
+
+  #[test]
+  fn test_stats() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Api": { "Type": "AWS::ApiGateway::Method", "Properties": { "HttpMethod": "GET", "Integration": { "Uri": { "Ref": "Worker" } } } },
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "OtherApi": { "Type": "AWS::ApiGateway::Method", "Properties": { "HttpMethod": "GET", "Integration": { "Uri": { "Ref": "Other" } } } },
+        "Other": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "other", "Architectures": ["arm64"] } }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template: template.clone(),
+    }];
+
+    let stats = Stats::new(&AST::from(template), &stacks);
+
+    assert_eq!(stats.resources.get("lambda"), Some(&2));
+    assert_eq!(stats.resources.get("eventsourcemapping"), Some(&1));
+    assert_eq!(stats.nodes, 5);
+    assert_eq!(stats.edges, 3);
+    assert_eq!(stats.components, 2);
+    assert_eq!(stats.fan_in[0], ("Worker".to_string(), 2));
+    assert_eq!(stats.fan_out.len(), 3);
+  }
+
+  #[test]
+  fn test_listing() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "Bucket": { "Type": "AWS::S3::Bucket", "Properties": {} },
+        "Role": { "Type": "AWS::IAM::Role", "Properties": {} }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template,
+    }];
+
+    let listing = Listing::new(&stacks, |_| true);
+    let lambdas = Listing::new(&stacks, |node| node.typ == ResourceType::Lambda);
+
+    assert_eq!(listing.to_text(), "Resources (3):\n  app  Queue   sqs     jobs\n  app  Worker  lambda  worker\n  app  Bucket  bucket  Bucket\n");
+    assert_eq!(lambdas.resources.len(), 1);
+  }
+}
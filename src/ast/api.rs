@@ -0,0 +1,321 @@
+use serde_json::{Map, Value};
+
+use crate::ast::ast::{AST, canonical, named_resource, referenced_resource};
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::ast::subgraph::Subgraph;
+use crate::cloudformation::arn::Arn;
+use crate::cloudformation::intrinsic::{references, splice, spliced_references};
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Name, Resource, ResourceType};
+use crate::cloudformation::stack::Stack;
+use crate::cloudformation::template::Template;
+
+const LAMBDA_INVOCATION: &str = "/functions/";
+
+// A Lambda proxy integration's `Uri` is an
+// `arn:aws:apigateway:{region}:lambda:path/2015-03-31/functions/{arn}/invocations`
+// ARN, so the target is whatever sits in the `{arn}` slot. Other service
+// integrations (SQS, Step Functions, ...) point at everything the URI names.
+pub(crate) fn integration_targets(integration: &Value, template: &Template) -> Vec<String> {
+  let Some(uri) = integration.get("Uri").and_then(|uri| splice(uri, &Map::new())) else {
+    return Vec::new();
+  };
+
+  let Some((_, function)) = uri.split_once(LAMBDA_INVOCATION) else {
+    return spliced_references(&uri);
+  };
+  let function = function.strip_suffix("/invocations").unwrap_or(function);
+
+  match spliced_references(function).first() {
+    Some(name) => vec![name.clone()],
+    None => function_named(function, template).into_iter().collect(),
+  }
+}
+
+fn function_named(arn: &str, template: &Template) -> Option<String> {
+  named_resource(&Arn::parse(arn)?, template).map(|resource| resource.name.0.to_string())
+}
+
+// A usage plan configures each API in its `ApiStages`, labelled with the
+// throttling and quota it sets, and the keys attached to it through
+// `AWS::ApiGateway::UsagePlanKey`s lead into it. With `--collapse-apis` both
+// hang off the one API node.
+pub(crate) fn usage_plan_edges(plan: &Resource, template: &Template) -> Vec<Edge> {
+  let Property::Other(properties) = &*plan.properties else {
+    return Vec::new();
+  };
+  if plan.typ != ResourceType::UsagePlan {
+    return Vec::new();
+  }
+
+  let limits = limits(properties);
+  let mut edges = Vec::new();
+
+  for stage in properties.get("ApiStages").and_then(Value::as_array).into_iter().flatten() {
+    let Some(api) = stage.get("ApiId").and_then(|api| referenced_resource(api, template)) else {
+      continue;
+    };
+    let mut edge = Edge::with(Node::from(plan), Node::from(api), Relationship::Configures);
+    edge.detail = limits.clone();
+    edges.push(edge);
+  }
+
+  // Usage plan keys are never drawn, so they are told apart by their
+  // `UsagePlanId` and `KeyId`.
+  let plan_keys = template.resources.iter().filter_map(|resource| resource.properties.untyped());
+  for key in plan_keys {
+    let uses = |field: &str| key.get(field).and_then(|value| referenced_resource(value, template));
+    if let (Some(used), Some(key)) = (uses("UsagePlanId"), uses("KeyId"))
+      && used.name == plan.name
+      && key.typ == ResourceType::ApiKey
+    {
+      edges.push(Edge::new(Node::from(key), Node::from(plan)));
+    }
+  }
+
+  edges
+}
+
+// `(100/s, burst 200, 10000/month)`
+fn limits(plan: &Value) -> Option<String> {
+  let number = |pointer: &str| plan.pointer(pointer).filter(|value| value.is_number()).map(Value::to_string);
+
+  let mut limits = Vec::new();
+  limits.extend(number("/Throttle/RateLimit").map(|rate| format!("{}/s", rate)));
+  limits.extend(number("/Throttle/BurstLimit").map(|burst| format!("burst {}", burst)));
+  if let Some(quota) = number("/Quota/Limit") {
+    let period = plan.pointer("/Quota/Period").and_then(Value::as_str).unwrap_or("period");
+    limits.push(format!("{}/{}", quota, period.to_lowercase()));
+  }
+
+  (!limits.is_empty()).then(|| format!("({})", limits.join(", ")))
+}
+
+// CDK and SAM emit a `Method` per verb and path, plus `Resource`, `Deployment`
+// and `Stage` plumbing, which buries the functions behind dozens of API nodes.
+// Each method is folded into its REST API, and the edges leaving it are
+// labelled with the verb and path instead.
+pub fn consolidate(ast: &mut AST, stacks: &[Stack]) {
+  let mut replacements: Vec<(Node, Node, String)> = Vec::new();
+
+  for stack in stacks {
+    let template = &stack.template;
+
+    for method in &template.resources {
+      let Property::ApiGateway { http_method, rest_api_id, resource_id, resource_path, .. } = &*method.properties else {
+        continue;
+      };
+      let Some(api_name) = references(rest_api_id).into_iter().next() else {
+        continue;
+      };
+
+      let api = match template.resource(&api_name) {
+        Some(resource) => Node::from(resource),
+        // SAM's implicit `ServerlessRestApi` only exists after the transform.
+        None => Node {
+          name: Name::from(api_name),
+          typ: ResourceType::ApiGateway,
+          properties: Property::default().into(),
+        },
+      };
+      let path = resource_path.clone().unwrap_or_else(|| path_of(resource_id, template));
+
+      replacements.push((Node::from(method), api, format!("{} {}", http_method, path)));
+    }
+  }
+
+  if replacements.is_empty() {
+    return;
+  }
+
+  for edges in [&mut ast.edges, &mut ast.cross_stack_edges] {
+    for edge in edges.iter_mut() {
+      if let Some((_, api, route)) = replacements.iter().find(|(method, _, _)| *method == edge.from) {
+        edge.from = api.clone();
+        edge.detail = Some(route.clone());
+      }
+      if let Some((_, api, _)) = replacements.iter().find(|(method, _, _)| *method == edge.to) {
+        edge.to = api.clone();
+      }
+    }
+    *edges = canonical(std::mem::take(edges));
+  }
+
+  for subgraph in &mut ast.subgraphs {
+    replace_nodes(subgraph, &replacements);
+  }
+}
+
+// `ResourceId` points at the deepest `AWS::ApiGateway::Resource`, whose
+// `ParentId` chain ends at the API's `RootResourceId`.
+fn path_of(resource_id: &Value, template: &Template) -> String {
+  let mut parts = Vec::new();
+  let mut current = resource_id.clone();
+
+  while let Some(resource) = references(&current).first().and_then(|name| find_path_part(name, template))
+    && parts.len() < 64
+  {
+    let Some(properties) = resource.properties.untyped() else {
+      break;
+    };
+    parts.push(properties.get("PathPart").and_then(Value::as_str).unwrap_or_default().to_string());
+    current = properties.get("ParentId").cloned().unwrap_or(Value::Null);
+  }
+
+  parts.reverse();
+  format!("/{}", parts.join("/"))
+}
+
+fn find_path_part<'a>(name: &str, template: &'a Template) -> Option<&'a Resource> {
+  template.resources.iter().find(|resource| {
+    &*resource.name.0 == name && resource.properties.untyped().is_some_and(|properties| properties.get("PathPart").is_some())
+  })
+}
+
+fn replace_nodes(subgraph: &mut Subgraph, replacements: &[(Node, Node, String)]) {
+  let mut nodes = Vec::new();
+
+  for node in std::mem::take(&mut subgraph.nodes) {
+    let node = match replacements.iter().find(|(method, _, _)| *method == node) {
+      Some((_, api, _)) => api.clone(),
+      None => node,
+    };
+    if !nodes.contains(&node) {
+      nodes.push(node);
+    }
+  }
+
+  subgraph.nodes = nodes;
+  for child in &mut subgraph.subgraphs {
+    replace_nodes(child, replacements);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn test_integration_targets() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "Other": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "other", "Architectures": ["arm64"] } }
+      }
+    }))
+    .unwrap();
+
+    let sub = json!({
+      "Uri": { "Fn::Sub": "arn:aws:apigateway:${AWS::Region}:lambda:path/2015-03-31/functions/${Worker.Arn}/invocations" },
+      "Credentials": { "Fn::GetAtt": ["Other", "Arn"] }
+    });
+    let join = json!({
+      "Uri": {
+        "Fn::Join": ["", [
+          "arn:", { "Ref": "AWS::Partition" }, ":apigateway:", { "Ref": "AWS::Region" }, ":lambda:path/2015-03-31/functions/",
+          { "Fn::GetAtt": ["Worker", "Arn"] }, "/invocations"
+        ]]
+      }
+    });
+    let literal = json!({
+      "Uri": "arn:aws:apigateway:eu-west-2:lambda:path/2015-03-31/functions/arn:aws:lambda:eu-west-2:123:function:other/invocations"
+    });
+
+    assert_eq!(integration_targets(&sub, &template), vec!["Worker"]);
+    assert_eq!(integration_targets(&join, &template), vec!["Worker"]);
+    assert_eq!(integration_targets(&literal, &template), vec!["Other"]);
+    assert!(integration_targets(&json!({ "Type": "MOCK" }), &template).is_empty());
+  }
+
+  #[test]
+  fn test_consolidate_methods_into_api() {
+    let uri = |function: &str| json!({ "Uri": { "Fn::Sub": format!("arn:aws:apigateway:${{AWS::Region}}:lambda:path/2015-03-31/functions/${{{}.Arn}}/invocations", function) } });
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Api": { "Type": "AWS::ApiGateway::RestApi", "Properties": { "Name": "orders" } },
+        "Orders": {
+          "Type": "AWS::ApiGateway::Resource",
+          "Properties": { "ParentId": { "Fn::GetAtt": ["Api", "RootResourceId"] }, "PathPart": "orders", "RestApiId": { "Ref": "Api" } }
+        },
+        "Order": {
+          "Type": "AWS::ApiGateway::Resource",
+          "Properties": { "ParentId": { "Ref": "Orders" }, "PathPart": "{id}", "RestApiId": { "Ref": "Api" } }
+        },
+        "ListOrders": {
+          "Type": "AWS::ApiGateway::Method",
+          "Properties": { "HttpMethod": "GET", "RestApiId": { "Ref": "Api" }, "ResourceId": { "Ref": "Orders" }, "Integration": uri("List") }
+        },
+        "GetOrder": {
+          "Type": "AWS::ApiGateway::Method",
+          "Properties": { "HttpMethod": "GET", "RestApiId": { "Ref": "Api" }, "ResourceId": { "Ref": "Order" }, "Integration": uri("Get") }
+        },
+        "Health": {
+          "Type": "AWS::ApiGateway::Method",
+          "Properties": {
+            "HttpMethod": "GET",
+            "RestApiId": { "Ref": "Api" },
+            "ResourceId": { "Fn::GetAtt": ["Api", "RootResourceId"] },
+            "Integration": uri("Get")
+          }
+        },
+        "List": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "list", "Architectures": ["arm64"] } },
+        "Get": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "get", "Architectures": ["arm64"] } }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template: template.clone(),
+    }];
+
+    let mut ast = AST::from(template);
+    consolidate(&mut ast, &stacks);
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Api[[Api]] -->|invokes GET /| Get([\"get\"])\n",
+      "Api[[Api]] -->|invokes GET /orders/{id}| Get([\"get\"])\n",
+      "Api[[Api]] -->|invokes GET /orders| List([\"list\"])\n",
+      "```",
+    );
+
+    assert_eq!(ast.to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_usage_plans_and_keys() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Api": { "Type": "AWS::ApiGateway::RestApi", "Properties": { "Name": "orders" } },
+        "Partners": {
+          "Type": "AWS::ApiGateway::UsagePlan",
+          "Properties": {
+            "UsagePlanName": "partners",
+            "ApiStages": [{ "ApiId": { "Ref": "Api" }, "Stage": { "Ref": "Prod" } }],
+            "Throttle": { "RateLimit": 100, "BurstLimit": 200 },
+            "Quota": { "Limit": 10000, "Period": "MONTH" }
+          }
+        },
+        "AcmeKey": { "Type": "AWS::ApiGateway::ApiKey", "Properties": { "Name": "acme", "Enabled": true } },
+        "AcmePlanKey": {
+          "Type": "AWS::ApiGateway::UsagePlanKey",
+          "Properties": { "KeyId": { "Ref": "AcmeKey" }, "KeyType": "API_KEY", "UsagePlanId": { "Ref": "Partners" } }
+        },
+        "Prod": { "Type": "AWS::ApiGateway::Stage", "Properties": { "RestApiId": { "Ref": "Api" }, "StageName": "prod" } }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "AcmeKey>\"acme\"] --> Partners[/\"partners\"/]\n",
+      "Partners[/\"partners\"/] -->|configures (100/s, burst 200, 10000/month)| Api[[Api]]\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+}
@@ -1,114 +1,524 @@
-use crate::ast::node::Node;
+use crate::ast::edge::{Edge, EdgeDirection, Relationship};
+use crate::ast::api::{self, integration_targets};
+use crate::ast::cache::ReferenceCache;
+use crate::ast::{efs, event_rule, firehose, glue, iot, missing, pipeline, ses, state_machine};
+use crate::ast::node::{DEFAULT_LABEL, Node};
+use crate::ast::subgraph::Subgraph;
+use crate::cloudformation::arn::Arn;
+use crate::cloudformation::dynamic::{DynamicReference, dynamic_references};
+use crate::cloudformation::intrinsic::references;
 use crate::cloudformation::property::Property;
 use crate::cloudformation::resource::{Name, Resource, ResourceType};
 use crate::cloudformation::template::Template;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tracing::{debug, trace, warn};
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
 pub struct AST {
-  pub edges: Vec<(Node, Node)>,
+  pub edges: Vec<Edge>,
+  pub subgraphs: Vec<Subgraph>,
+  pub cross_stack_edges: Vec<Edge>,
 }
 
 impl AST {
+  // Properties kept as JSON text are parsed on first use, which clippy takes
+  // for a key that can change; the hash only ever depends on the text.
+  #[allow(clippy::mutable_key_type)]
+  pub fn nodes(&self) -> Vec<&Node> {
+    let mut seen = HashSet::new();
+    self
+      .edges
+      .iter()
+      .chain(self.cross_stack_edges.iter())
+      .flat_map(|edge| [&edge.from, &edge.to])
+      .filter(|node| seen.insert(*node))
+      .collect()
+  }
+
+  // Resources nothing connects to are only in the subgraphs, which diagrams of
+  // a single resource's insides still need to find.
+  #[allow(clippy::mutable_key_type)]
+  pub fn all_nodes(&self) -> Vec<&Node> {
+    let mut nodes = self.nodes();
+    let mut seen: HashSet<&Node> = nodes.iter().copied().collect();
+    nodes.extend(self.subgraphs.iter().flat_map(Subgraph::all_nodes).filter(|node| seen.insert(*node)));
+    nodes
+  }
+
+  pub fn group_of(&self, node: &Node) -> Option<&str> {
+    self
+      .subgraphs
+      .iter()
+      .find(|subgraph| subgraph.contains(node))
+      .map(|subgraph| subgraph.name.as_str())
+  }
+
+  // Edges are dropped along with either endpoint, so a filtered diagram never
+  // points at a node that is no longer drawn.
+  #[allow(clippy::mutable_key_type)]
+  pub fn retain(&mut self, keep: impl Fn(&Node) -> bool) {
+    self.edges.retain(|edge| keep(&edge.from) && keep(&edge.to));
+    self.cross_stack_edges.retain(|edge| keep(&edge.from) && keep(&edge.to));
+
+    let nodes: HashSet<Node> = self.nodes().into_iter().cloned().collect();
+    for subgraph in &mut self.subgraphs {
+      subgraph.retain(&|node| nodes.contains(node));
+    }
+    self.subgraphs.retain(|subgraph| !subgraph.is_empty());
+  }
+
+  // Every copy of a node is updated, so edges and subgraphs keep agreeing on
+  // which nodes are the same.
+  pub fn for_each_node(&mut self, f: impl Fn(&mut Node)) {
+    for edge in self.edges.iter_mut().chain(self.cross_stack_edges.iter_mut()) {
+      f(&mut edge.from);
+      f(&mut edge.to);
+    }
+    for subgraph in &mut self.subgraphs {
+      subgraph.for_each_node(&f);
+    }
+  }
+
+  pub fn orient(&mut self, direction: EdgeDirection) {
+    for edges in [&mut self.edges, &mut self.cross_stack_edges] {
+      let oriented = std::mem::take(edges)
+        .into_iter()
+        .map(|edge| if edge.points(direction) { edge } else { edge.reversed() })
+        .collect();
+      *edges = canonical(oriented);
+    }
+  }
+
   pub fn to_mermaid(&self) -> String {
-    let mut result = String::from("```mermaid\nflowchart LR\n");
-    
-    for (from, to) in &self.edges {
-      result.push_str(&format!("{} --> {}\n", from, to));
+    format!("```mermaid\n{}```", self.to_flowchart())
+  }
+
+  pub fn to_flowchart(&self) -> String {
+    self.to_flowchart_with(DEFAULT_LABEL)
+  }
+
+  pub fn to_flowchart_with(&self, label_format: &str) -> String {
+    let ids = self.ids();
+    let mut result = String::from("flowchart LR\n");
+
+    for subgraph in &self.subgraphs {
+      result.push_str(&subgraph.to_mermaid_within(None, label_format, &ids));
     }
     
-    result.push_str("```");
+    for edge in &self.edges {
+      result.push_str(&format!(
+        "{} {} {}\n",
+        edge.from.to_mermaid_as(&ids.of(&edge.from), label_format),
+        edge.flowchart_arrow(),
+        edge.to.to_mermaid_as(&ids.of(&edge.to), label_format)
+      ));
+    }
+
+    for edge in &self.cross_stack_edges {
+      result.push_str(&format!(
+        "{} {} {}\n",
+        edge.from.to_mermaid_as(&ids.of(&edge.from), label_format),
+        edge.arrow("==>"),
+        edge.to.to_mermaid_as(&ids.of(&edge.to), label_format)
+      ));
+    }
+
+    // Mermaid only has one dotted line, so permissions get a finer dash than
+    // async edges to tell them apart.
+    for (relationship, style) in [
+      (Relationship::DependsOn, "stroke:#999,stroke-dasharray:3"),
+      (Relationship::Grants, "stroke-dasharray:1 3"),
+    ] {
+      let indexes: Vec<String> = self
+        .edges
+        .iter()
+        .enumerate()
+        .filter(|(_, edge)| edge.relationship == relationship)
+        .map(|(index, _)| index.to_string())
+        .collect();
+      if !indexes.is_empty() {
+        result.push_str(&format!("linkStyle {} {}\n", indexes.join(","), style));
+      }
+    }
+
     result
   }
 }
 
 impl From<Template> for AST {
   fn from(template: Template) -> Self {
-    let mut edges = Vec::new();
-
-    for resource in &template.resources {
-      if should_keep(resource.typ.clone()) {
-        match resource.typ {
-          ResourceType::EventSourceMapping => {
-            if let Some((source_queue, target_lambda)) = extract_event_source_mapping_refs(resource, &template) {
-              edges.push((source_queue, target_lambda));
-            }
-          },
-          _ => {
-            let referenced_node = Node::from(resource.clone());
-            let references = find_references(template.clone(), resource.name.clone());
-
-            for ref_resource in references {
-              if should_keep(ref_resource.typ.clone()) {
-                let referencing_node = Node::from(ref_resource);
-                edges.push((referencing_node, referenced_node.clone()));
-              }
-            }
-          }
+    build(&template, None, &|_| true)
+  }
+}
+
+// Resources `keep` rejects make no edges, and edges to them are dropped, so a
+// resource drawn as an edge rather than a node, such as an event source
+// mapping, can be left out as well.
+pub(crate) fn build(template: &Template, cache: Option<&ReferenceCache>, keep: &(dyn Fn(&Node) -> bool + Sync)) -> AST {
+  let kept: Vec<bool> = template
+    .resources
+    .iter()
+    .map(|resource| resource.typ.is_supported() && keep(&Node::from(resource)))
+    .collect();
+  let edges = match cache {
+    Some(cache) => cache.edges(template, &kept, || edges(template, Some(cache), &kept)),
+    None => edges(template, None, &kept),
+  };
+
+  let edges: Vec<Edge> = edges.into_iter().filter(|edge| keep(&edge.from) && keep(&edge.to)).collect();
+  for edge in &edges {
+    trace!("edge {} -> {} ({})", edge.from.name.0, edge.to.name.0, edge.relationship);
+  }
+
+  AST {
+    edges,
+    ..Default::default()
+  }
+}
+
+fn edges(template: &Template, cache: Option<&ReferenceCache>, kept: &[bool]) -> Vec<Edge> {
+  let index = ReferenceIndex::new(template, cache);
+  report_references(template, &index);
+  // Resources are drawn independently of each other, so large templates are
+  // split across threads; `canonical` puts the edges back in a fixed order.
+  let edges: Vec<Edge> = template
+    .resources
+    .par_iter()
+    .zip(kept)
+    .filter(|(_, kept)| **kept)
+    .flat_map_iter(|(resource, _)| resource_edges(resource, template, &index))
+    .collect();
+
+  canonical(edges)
+}
+
+fn resource_edges(resource: &Resource, template: &Template, index: &ReferenceIndex) -> Vec<Edge> {
+  let mut edges = Vec::new();
+  edges.extend(event_rule::target_edges(resource, template));
+  edges.extend(state_machine::task_edges(resource, template));
+  edges.extend(glue::edges(resource, template));
+  edges.extend(firehose::destination_edges(resource, template));
+  edges.extend(efs::mount_edges(resource, template));
+  edges.extend(pipeline::stage_edges(resource, template));
+  edges.extend(iot::action_edges(resource, template));
+  edges.extend(ses::action_edges(resource, template));
+  edges.extend(api::usage_plan_edges(resource, template));
+
+  match resource.typ {
+    ResourceType::EventSourceMapping => match extract_event_source_mapping_refs(resource, template) {
+      Some((source_queue, target_lambda)) => edges.push(Edge::new(source_queue, target_lambda)),
+      None => debug!("{}: event source or function could not be resolved", resource.name.0),
+    },
+    _ => {
+      let referenced_node = Node::from(resource);
+
+      for dynamic in index.dynamic(&resource.name) {
+        edges.push(Edge::new(external_node(dynamic.clone()), referenced_node.clone()));
+      }
+
+      for &ref_resource in index.referencing(&resource.name) {
+        if ref_resource.typ.is_supported() {
+          let referencing_node = Node::from(ref_resource);
+          edges.push(Edge::new(referencing_node, referenced_node.clone()));
+        }
+      }
+
+      for &function in index.configuring(&resource.name) {
+        edges.push(Edge::with(Node::from(function), referenced_node.clone(), Relationship::Configures));
+      }
+    }
+  }
+
+  edges
+}
+
+// An unresolved reference is the usual reason an expected edge is missing, so
+// it is logged. A `Ref` or `Fn::GetAtt` to an undefined logical ID is a broken
+// template and is warned about; anything else may be a variable of its own.
+fn report_references(template: &Template, index: &ReferenceIndex) {
+  for resource in template.resources.iter().filter(|r| r.typ.is_supported()) {
+    let mut unresolved = Vec::new();
+    for name in index.mentioned(&resource.name) {
+      match template.resource(name) {
+        Some(target) if !target.typ.is_supported() => {
+          trace!("{}: reference to {} not drawn, its type is unsupported", resource.name.0, name)
         }
+        Some(_) => {}
+        None if template.parameters.contains_key(name) => {}
+        None => unresolved.push(name),
       }
     }
+    if unresolved.is_empty() {
+      continue;
+    }
 
-    AST { edges }
+    let dangling = missing::dangling(resource, template);
+    for name in unresolved {
+      match dangling.contains(name) {
+        true => warn!("{}: references {}, which is not defined in the template", resource.name.0, name),
+        false => debug!("{}: reference to {} could not be resolved", resource.name.0, name),
+      }
+    }
   }
 }
 
-fn find_references(template: Template, resource_name: Name) -> Vec<Resource> {
-  template
-    .resources
-    .into_iter()
-    .filter(|resource| match &resource.properties {
-      Property::Other(properties) => properties.to_string().contains(&resource_name.0),
-      Property::ApiGateway { integration, .. } => {
-        integration.to_string().contains(&resource_name.0)
+fn external_node(reference: DynamicReference) -> Node {
+  let (name, typ) = match reference {
+    DynamicReference::Ssm(name) => (name, ResourceType::SsmParameter),
+    DynamicReference::Secret(name) => (name, ResourceType::Secret),
+  };
+
+  Node {
+    name: Name::from(name),
+    typ,
+    properties: Property::default().into(),
+  }
+}
+
+// Edge order otherwise follows resource order in the template, so reordering a
+// template (or a JSON map serializing differently) would churn the diagram.
+pub(crate) fn canonical(mut edges: Vec<Edge>) -> Vec<Edge> {
+  edges.sort_by(|a, b| {
+    (&a.from.name.0, &a.to.name.0, a.relationship, &a.detail).cmp(&(&b.from.name.0, &b.to.name.0, b.relationship, &b.detail))
+  });
+  edges.dedup();
+  edges
+}
+
+// Who refers to whom is worked out in one pass over the template, rather than
+// walking every resource's properties again for each resource drawn.
+struct ReferenceIndex<'a> {
+  referencing: HashMap<String, Vec<&'a Resource>>,
+  // Queue URLs, table and bus names are mostly handed to functions through
+  // environment variables, which is where the runtime wiring actually happens.
+  configuring: HashMap<String, Vec<&'a Resource>>,
+  // What each drawn resource names anywhere in its properties.
+  mentions: HashMap<&'a str, (BTreeSet<String>, Vec<DynamicReference>)>,
+}
+
+// Everything the one walk over a resource's properties finds.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct Found {
+  pub referenced: Vec<String>,
+  pub configured: Vec<String>,
+  pub mentioned: BTreeSet<String>,
+  pub dynamic: Vec<DynamicReference>,
+}
+
+impl<'a> ReferenceIndex<'a> {
+  fn new(template: &'a Template, cache: Option<&ReferenceCache>) -> Self {
+    let mut index = ReferenceIndex {
+      referencing: HashMap::new(),
+      configuring: HashMap::new(),
+      mentions: HashMap::new(),
+    };
+
+    // Walking the properties is the expensive part, so it runs in parallel and
+    // only the inserts, which keep template order, run one at a time.
+    let extract = |resource: &Resource| find_references(resource, template);
+    let found = match cache {
+      Some(cache) => cache.find(&template.resources, extract),
+      None => template.resources.par_iter().map(extract).collect(),
+    };
+
+    for (resource, found) in template.resources.iter().zip(found) {
+      insert(&mut index.referencing, found.referenced, resource);
+      insert(&mut index.configuring, found.configured, resource);
+      index.mentions.insert(&resource.name.0, (found.mentioned, found.dynamic));
+    }
+
+    index
+  }
+
+  fn referencing(&self, name: &Name) -> &[&'a Resource] {
+    self.referencing.get(&*name.0).map(Vec::as_slice).unwrap_or_default()
+  }
+
+  fn configuring(&self, name: &Name) -> &[&'a Resource] {
+    self.configuring.get(&*name.0).map(Vec::as_slice).unwrap_or_default()
+  }
+
+  fn mentioned(&self, name: &Name) -> impl Iterator<Item = &String> {
+    self.mentions.get(&*name.0).into_iter().flat_map(|(mentioned, _)| mentioned)
+  }
+
+  fn dynamic(&self, name: &Name) -> &[DynamicReference] {
+    self.mentions.get(&*name.0).map(|(_, dynamic)| dynamic.as_slice()).unwrap_or_default()
+  }
+}
+
+fn find_references(resource: &Resource, template: &Template) -> Found {
+  // Only drawn resources make edges, so the rest are not read at all; their
+  // properties may not even be parsed yet.
+  if !resource.typ.is_supported() {
+    return Found::default();
+  }
+
+  let mut found = Found::default();
+  for json in resource.properties.json() {
+    found.mentioned.extend(references(&json));
+    for dynamic in dynamic_references(&json) {
+      if !found.dynamic.contains(&dynamic) {
+        found.dynamic.push(dynamic);
       }
-      _ => false,
-    })
-    .collect()
+    }
+  }
+
+  found.referenced = match &*resource.properties {
+    Property::Other(_) if wires_itself(&resource.typ) => Vec::new(),
+    Property::Other(_) => found.mentioned.iter().cloned().collect(),
+    Property::ApiGateway { integration, .. } => integration_targets(integration, template),
+    _ => Vec::new(),
+  };
+  found.configured = match &*resource.properties {
+    Property::Lambda { environment, .. } => references(environment),
+    _ => Vec::new(),
+  };
+  found
+}
+
+// Types whose properties name more than where they send things, so their edges
+// come from a module of their own rather than from every reference: state
+// machines only use what their task states call (`state_machine`), Glue
+// triggers name jobs both to start and to wait on (`glue`), pipelines are drawn
+// stage by stage (`pipeline`), IoT and SES rules route to their actions, not
+// to the roles they act as (`iot`, `ses`), and usage plans name stages as well
+// as APIs (`api`).
+fn wires_itself(typ: &ResourceType) -> bool {
+  matches!(
+    typ,
+    ResourceType::StateMachine
+      | ResourceType::GlueTrigger
+      | ResourceType::Pipeline
+      | ResourceType::IotRule
+      | ResourceType::SesReceiptRule
+      | ResourceType::UsagePlan
+  )
+}
+
+// A resource naming another twice is still one reference.
+fn insert<'a>(index: &mut HashMap<String, Vec<&'a Resource>>, mut names: Vec<String>, resource: &'a Resource) {
+  names.sort();
+  names.dedup();
+  for name in names {
+    index.entry(name).or_default().push(resource);
+  }
 }
 
 fn extract_event_source_mapping_refs(resource: &Resource, template: &Template) -> Option<(Node, Node)> {
-  if let Property::EventSourceMapping { event_source_arn, function_name } = &resource.properties {
-    let queue_name = extract_ref_from_getatt(event_source_arn)?;
-    
-    let lambda_name = extract_ref_from_ref(function_name)?;
-    
-    let queue_resource = template.resources.iter().find(|r| r.name.0 == queue_name)?;
-    let lambda_resource = template.resources.iter().find(|r| r.name.0 == lambda_name)?;
-    
-    Some((Node::from(queue_resource.clone()), Node::from(lambda_resource.clone())))
+  if let Property::EventSourceMapping { event_source_arn, function_name } = &*resource.properties {
+    let source = match referenced_resource(event_source_arn, template) {
+      Some(resource) => Node::from(resource),
+      None => literal_node(&Arn::parse(event_source_arn.as_str()?)?, template),
+    };
+    let function = match referenced_resource(function_name, template) {
+      Some(resource) => Node::from(resource),
+      // `FunctionName` may be a bare name as well as an ARN.
+      None => {
+        let name = function_name.as_str()?;
+        let arn = Arn::parse(name).unwrap_or_else(|| Arn {
+          service: "lambda".to_string(),
+          region: String::new(),
+          account: String::new(),
+          resource: format!("function:{}", name),
+        });
+        literal_node(&arn, template)
+      }
+    };
+
+    Some((source, function))
   } else {
     None
   }
 }
 
-fn extract_ref_from_getatt(value: &serde_json::Value) -> Option<String> {
-  if let Some(get_att) = value.get("Fn::GetAtt") {
-    if let Some(array) = get_att.as_array() {
-      if let Some(resource_name) = array.get(0) {
-        return resource_name.as_str().map(|s| s.to_string());
-      }
+// ARNs are as often assembled with `Fn::Join` or `Fn::Sub` as fetched with
+// `Fn::GetAtt`, so the first in-template resource referenced anywhere in the
+// value is taken.
+pub(crate) fn referenced_resource<'a>(value: &serde_json::Value, template: &'a Template) -> Option<&'a Resource> {
+  references(value)
+    .iter()
+    .find_map(|name| template.resource(name))
+}
+
+// Hand-written templates often carry the literal ARN of a queue, stream or
+// function. It is matched back to the resource created with that name, or
+// drawn as an external node when the template does not define it.
+pub(crate) fn literal_node(arn: &Arn, template: &Template) -> Node {
+  match named_resource(arn, template) {
+    Some(resource) => Node::from(resource),
+    None => Node {
+      name: Name::from(arn.name()),
+      typ: ResourceType::External,
+      properties: Property::default().into(),
+    },
+  }
+}
+
+// What a rule action sends to: the resource it references, or the one created
+// with the literal ARN or name it gives. A name is taken as the last part of a
+// path, since queues are given by URL.
+pub(crate) fn target_node(value: &serde_json::Value, service: &str, template: &Template) -> Option<Node> {
+  if let Some(resource) = referenced_resource(value, template) {
+    return resource.typ.is_supported().then(|| Node::from(resource));
+  }
+
+  let literal = value.as_str()?;
+  let arn = Arn::parse(literal).unwrap_or_else(|| Arn {
+    service: service.to_string(),
+    region: String::new(),
+    account: String::new(),
+    resource: literal.rsplit('/').next().unwrap_or(literal).to_string(),
+  });
+  Some(literal_node(&arn, template))
+}
+
+// The types kept as plain JSON that can be named by a literal ARN, keyed by the
+// service in it.
+fn untyped_service(service: &str) -> Option<&'static ResourceType> {
+  match service {
+    "s3" => Some(&ResourceType::Bucket),
+    "redshift" => Some(&ResourceType::Redshift),
+    "kinesis" => Some(&ResourceType::Kinesis),
+    "firehose" => Some(&ResourceType::Firehose),
+    "sns" => Some(&ResourceType::Sns),
+    _ => None,
+  }
+}
+
+pub(crate) fn named_resource<'a>(arn: &Arn, template: &'a Template) -> Option<&'a Resource> {
+  let name = arn.name();
+
+  template.resources.iter().find(|resource| match (arn.service.as_str(), &*resource.properties) {
+    ("sqs", Property::Sqs { queue_name }) => queue_name.as_str() == Some(name),
+    ("dynamodb", Property::DynamoDb { table_name, .. }) => table_name.as_str() == Some(name),
+    ("lambda", Property::Lambda { function_name, .. }) => function_name.as_str() == Some(name),
+    (service, Property::Other(_)) => {
+      untyped_service(service) == Some(&resource.typ) && Node::from(resource).get_name() == name
     }
+    _ => false,
+  })
+}
+
+pub(crate) fn extract_ref_from_getatt(value: &serde_json::Value) -> Option<String> {
+  if let Some(get_att) = value.get("Fn::GetAtt")
+    && let Some(array) = get_att.as_array()
+    && let Some(resource_name) = array.first()
+  {
+    return resource_name.as_str().map(|s| s.to_string());
   }
   None
 }
 
-fn extract_ref_from_ref(value: &serde_json::Value) -> Option<String> {
+pub(crate) fn extract_ref_from_ref(value: &serde_json::Value) -> Option<String> {
   if let Some(ref_value) = value.get("Ref") {
     return ref_value.as_str().map(|s| s.to_string());
   }
   None
 }
 
-fn should_keep(typ: ResourceType) -> bool {
-  match typ {
-    ResourceType::Other => false,
-    ResourceType::Lambda => true,
-    ResourceType::Sqs => true,
-    ResourceType::ApiGateway => true,
-    ResourceType::EventSourceMapping => true,
-  }
-}
-
 #[cfg(test)]
 mod tests {
   use serde_json::json;
@@ -118,104 +528,99 @@ mod tests {
   #[test]
   fn test_ast_construction_single_edge() {
     let node1 = Node {
-      name: Name("name1".to_string()),
+      name: Name::from("name1"),
       typ: ResourceType::Sqs,
       properties: Property::Sqs {
-        queue_name: "queue1".to_string(),
-      },
+        queue_name: "queue1".into(),
+      }.into(),
     };
     let node2 = Node {
-      name: Name("name2".to_string()),
+      name: Name::from("name2"),
       typ: ResourceType::Lambda,
-      properties: Property::Lambda {
-        function_name: "lambda1".to_string(),
-        architectures: vec!["arm64".to_string()],
-      },
+      properties: Property::lambda("lambda1").into(),
     };
-    let ast = AST { edges: vec![(node1.clone(), node2.clone())] };
+    let ast = AST { edges: vec![Edge::new(node1.clone(), node2.clone())], ..Default::default() };
 
-    assert_eq!(ast, AST { edges: vec![(node1, node2)] });
+    assert_eq!(ast, AST { edges: vec![Edge::new(node1, node2)], ..Default::default() });
   }
 
   #[test]
   fn test_ast_construction_multiple_edges() {
     let sqs_node = Node {
-      name: Name("queue1".to_string()),
+      name: Name::from("queue1"),
       typ: ResourceType::Sqs,
       properties: Property::Sqs {
-        queue_name: "queue1".to_string(),
-      },
+        queue_name: "queue1".into(),
+      }.into(),
     };
     let lambda_node1 = Node {
-      name: Name("lambda1".to_string()),
+      name: Name::from("lambda1"),
       typ: ResourceType::Lambda,
-      properties: Property::Lambda {
-        function_name: "lambda1".to_string(),
-        architectures: vec!["arm64".to_string()],
-      },
+      properties: Property::lambda("lambda1").into(),
     };
     let lambda_node2 = Node {
-      name: Name("lambda2".to_string()),
+      name: Name::from("lambda2"),
       typ: ResourceType::Lambda,
-      properties: Property::Lambda {
-        function_name: "lambda2".to_string(),
-        architectures: vec!["arm64".to_string()],
-      },
+      properties: Property::lambda("lambda2").into(),
     };
     
     let ast = AST { 
       edges: vec![
-        (sqs_node.clone(), lambda_node1.clone()),
-        (sqs_node.clone(), lambda_node2.clone())
-      ] 
+        Edge::new(sqs_node.clone(), lambda_node1.clone()),
+        Edge::new(sqs_node.clone(), lambda_node2.clone())
+      ],
+      ..Default::default()
     };
 
     assert_eq!(ast, AST { 
       edges: vec![
-        (sqs_node.clone(), lambda_node1),
-        (sqs_node, lambda_node2)
-      ] 
+        Edge::new(sqs_node.clone(), lambda_node1),
+        Edge::new(sqs_node, lambda_node2)
+      ],
+      ..Default::default()
     });
   }
 
   #[test]
   fn test_ast_construction_chain_edges() {
     let api_node = Node {
-      name: Name("api".to_string()),
+      name: Name::from("api"),
       typ: ResourceType::ApiGateway,
       properties: Property::ApiGateway {
         http_method: "POST".to_string(),
         integration: serde_json::json!({}),
-      },
+        rest_api_id: serde_json::Value::Null,
+        resource_id: serde_json::Value::Null,
+        resource_path: None,
+      }.into(),
     };
     let lambda_node = Node {
-      name: Name("lambda".to_string()),
+      name: Name::from("lambda"),
       typ: ResourceType::Lambda,
-      properties: Property::Lambda {
-        function_name: "lambda".to_string(),
-        architectures: vec!["arm64".to_string()],
-      },
+      properties: Property::lambda("lambda").into(),
     };
     let sqs_node = Node {
-      name: Name("queue".to_string()),
+      name: Name::from("queue"),
       typ: ResourceType::Sqs,
       properties: Property::Sqs {
-        queue_name: "queue".to_string(),
-      },
+        queue_name: "queue".into(),
+      }.into(),
     };
     
     let ast = AST { 
       edges: vec![
-        (api_node.clone(), lambda_node.clone()),
-        (lambda_node.clone(), sqs_node.clone())
-      ] 
+        Edge::new(api_node.clone(), lambda_node.clone()),
+        Edge::new(lambda_node.clone(), sqs_node.clone())
+      ],
+      ..Default::default()
     };
 
     assert_eq!(ast, AST { 
       edges: vec![
-        (api_node, lambda_node.clone()),
-        (lambda_node, sqs_node)
-      ] 
+        Edge::new(api_node, lambda_node.clone()),
+        Edge::new(lambda_node, sqs_node)
+      ],
+      ..Default::default()
     });
   }
 
@@ -225,47 +630,51 @@ mod tests {
     let template = Template {
       resources: vec![
         Resource {
-          name: Name("mylambda".to_string()),
+          name: Name::from("mylambda"),
           typ: ResourceType::Lambda,
-          properties: Property::Lambda {
-            function_name: "mylambda".to_string(),
-            architectures: vec!["arm64".to_string()],
-          },
+          properties: Property::lambda("mylambda").into(),
+          ..Default::default()
         },
         Resource {
-          name: Name("mygateway".to_string()),
+          name: Name::from("mygateway"),
           typ: ResourceType::ApiGateway,
           properties: Property::ApiGateway {
             http_method: "POST".to_string(),
-            integration: json!("mylambda"),
-          },
+            integration: json!({ "Uri": { "Fn::GetAtt": ["mylambda", "Arn"] } }),
+            rest_api_id: serde_json::Value::Null,
+            resource_id: serde_json::Value::Null,
+            resource_path: None,
+          }.into(),
+          ..Default::default()
         },
       ],
+      ..Default::default()
     };
 
     let ast = AST::from(template);
 
     let expected_gateway_node = Node {
-      name: Name("mygateway".to_string()),
+      name: Name::from("mygateway"),
       typ: ResourceType::ApiGateway,
       properties: Property::ApiGateway {
         http_method: "POST".to_string(),
-        integration: json!("mylambda"),
-      },
+        integration: json!({ "Uri": { "Fn::GetAtt": ["mylambda", "Arn"] } }),
+        rest_api_id: serde_json::Value::Null,
+        resource_id: serde_json::Value::Null,
+        resource_path: None,
+      }.into(),
     };
     let expected_lambda_node = Node {
-      name: Name("mylambda".to_string()),
+      name: Name::from("mylambda"),
       typ: ResourceType::Lambda,
-      properties: Property::Lambda {
-        function_name: "mylambda".to_string(),
-        architectures: vec!["arm64".to_string()],
-      },
+      properties: Property::lambda("mylambda").into(),
     };
 
     assert_eq!(
       ast,
       AST {
-        edges: vec![(expected_gateway_node, expected_lambda_node)]
+        edges: vec![Edge::new(expected_gateway_node, expected_lambda_node)],
+        ..Default::default()
       }
     );
   }
@@ -273,27 +682,25 @@ mod tests {
   #[test]
   fn test_to_mermaid_with_single_edge() {
     let sqs_node = Node {
-      name: Name("myqueue".to_string()),
+      name: Name::from("myqueue"),
       typ: ResourceType::Sqs,
       properties: Property::Sqs {
-        queue_name: "myqueue".to_string(),
-      },
+        queue_name: "myqueue".into(),
+      }.into(),
     };
     let lambda_node = Node {
-      name: Name("mylambda".to_string()),
+      name: Name::from("mylambda"),
       typ: ResourceType::Lambda,
-      properties: Property::Lambda {
-        function_name: "mylambda".to_string(),
-        architectures: vec!["arm64".to_string()],
-      },
+      properties: Property::lambda("mylambda").into(),
     };
     
     let ast = AST {
-      edges: vec![(sqs_node, lambda_node)]
+      edges: vec![Edge::new(sqs_node, lambda_node)],
+      ..Default::default()
     };
 
     let mermaid_output = ast.to_mermaid();
-    let expected_output = "```mermaid\nflowchart LR\nmyqueue((myqueue)) --> mylambda([mylambda])\n```";
+    let expected_output = "```mermaid\nflowchart LR\nmyqueue((myqueue)) -.->|triggers| mylambda([mylambda])\n```";
 
     assert_eq!(mermaid_output, expected_output);
   }
@@ -302,11 +709,12 @@ mod tests {
   fn test_empty_template() {
     let template = Template {
       resources: vec![],
+      ..Default::default()
     };
 
     let ast = AST::from(template);
 
-    assert_eq!(ast, AST { edges: vec![] });
+    assert_eq!(ast, AST { edges: vec![], ..Default::default() });
 
     let mermaid_output = ast.to_mermaid();
     let expected_output = "```mermaid\nflowchart LR\n```";
@@ -319,59 +727,64 @@ mod tests {
     let template = Template {
       resources: vec![
         Resource {
-          name: Name("mylambda".to_string()),
+          name: Name::from("mylambda"),
           typ: ResourceType::Lambda,
-          properties: Property::Lambda {
-            function_name: "mylambda".to_string(),
-            architectures: vec!["arm64".to_string()],
-          },
+          properties: Property::lambda("mylambda").into(),
+          ..Default::default()
         },
         Resource {
-          name: Name("myapi".to_string()),
+          name: Name::from("myapi"),
           typ: ResourceType::ApiGateway,
           properties: Property::ApiGateway {
             http_method: "POST".to_string(),
-            integration: json!("mylambda"),
-          },
+            integration: json!({ "Uri": { "Fn::GetAtt": ["mylambda", "Arn"] } }),
+            rest_api_id: serde_json::Value::Null,
+            resource_id: serde_json::Value::Null,
+            resource_path: None,
+          }.into(),
+          ..Default::default()
         },
         Resource {
-          name: Name("myqueue".to_string()),
+          name: Name::from("myqueue"),
           typ: ResourceType::Sqs,
           properties: Property::Sqs {
-            queue_name: "myqueue".to_string(),
-          },
+            queue_name: "myqueue".into(),
+          }.into(),
+          ..Default::default()
         },
       ],
+      ..Default::default()
     };
 
     let ast = AST::from(template);
 
     let expected_lambda_node = Node {
-      name: Name("mylambda".to_string()),
+      name: Name::from("mylambda"),
       typ: ResourceType::Lambda,
-      properties: Property::Lambda {
-        function_name: "mylambda".to_string(),
-        architectures: vec!["arm64".to_string()],
-      },
+      properties: Property::lambda("mylambda").into(),
     };
     let expected_api_node = Node {
-      name: Name("myapi".to_string()),
+      name: Name::from("myapi"),
       typ: ResourceType::ApiGateway,
       properties: Property::ApiGateway {
         http_method: "POST".to_string(),
-        integration: json!("mylambda"),
-      },
+        integration: json!({ "Uri": { "Fn::GetAtt": ["mylambda", "Arn"] } }),
+        rest_api_id: serde_json::Value::Null,
+        resource_id: serde_json::Value::Null,
+        resource_path: None,
+      }.into(),
     };
 
     assert_eq!(
       ast,
       AST {
-        edges: vec![(expected_api_node, expected_lambda_node)]
+        edges: vec![Edge::new(expected_api_node, expected_lambda_node)],
+        ..Default::default()
       }
     );
 
     let mermaid_output = ast.to_mermaid();
-    let expected_output = "```mermaid\nflowchart LR\nmyapi[[myapi]] --> mylambda([mylambda])\n```";
+    let expected_output = "```mermaid\nflowchart LR\nmyapi[[myapi]] -->|invokes| mylambda([mylambda])\n```";
 
     assert_eq!(mermaid_output, expected_output);
   }
@@ -381,57 +794,62 @@ mod tests {
     let template = Template {
       resources: vec![
         Resource {
-          name: Name("mylambda".to_string()),
+          name: Name::from("mylambda"),
           typ: ResourceType::Lambda,
-          properties: Property::Lambda {
-            function_name: "mylambda".to_string(),
-            architectures: vec!["arm64".to_string()],
-          },
+          properties: Property::lambda("mylambda").into(),
+          ..Default::default()
         },
         Resource {
-          name: Name("myapi".to_string()),
+          name: Name::from("myapi"),
           typ: ResourceType::ApiGateway,
           properties: Property::ApiGateway {
             http_method: "POST".to_string(),
-            integration: json!("mylambda"),
-          },
+            integration: json!({ "Uri": { "Fn::GetAtt": ["mylambda", "Arn"] } }),
+            rest_api_id: serde_json::Value::Null,
+            resource_id: serde_json::Value::Null,
+            resource_path: None,
+          }.into(),
+          ..Default::default()
         },
         Resource {
-          name: Name("unsupported".to_string()),
+          name: Name::from("unsupported"),
           typ: ResourceType::Other,
-          properties: Property::Other(json!("some value")),
+          properties: Property::Other(json!("some value")).into(),
+          ..Default::default()
         },
       ],
+      ..Default::default()
     };
 
     let ast = AST::from(template);
 
     let expected_lambda_node = Node {
-      name: Name("mylambda".to_string()),
+      name: Name::from("mylambda"),
       typ: ResourceType::Lambda,
-      properties: Property::Lambda {
-        function_name: "mylambda".to_string(),
-        architectures: vec!["arm64".to_string()],
-      },
+      properties: Property::lambda("mylambda").into(),
     };
     let expected_api_node = Node {
-      name: Name("myapi".to_string()),
+      name: Name::from("myapi"),
       typ: ResourceType::ApiGateway,
       properties: Property::ApiGateway {
         http_method: "POST".to_string(),
-        integration: json!("mylambda"),
-      },
+        integration: json!({ "Uri": { "Fn::GetAtt": ["mylambda", "Arn"] } }),
+        rest_api_id: serde_json::Value::Null,
+        resource_id: serde_json::Value::Null,
+        resource_path: None,
+      }.into(),
     };
 
     assert_eq!(
       ast,
       AST {
-        edges: vec![(expected_api_node, expected_lambda_node)]
+        edges: vec![Edge::new(expected_api_node, expected_lambda_node)],
+        ..Default::default()
       }
     );
 
     let mermaid_output = ast.to_mermaid();
-    let expected_output = "```mermaid\nflowchart LR\nmyapi[[myapi]] --> mylambda([mylambda])\n```";
+    let expected_output = "```mermaid\nflowchart LR\nmyapi[[myapi]] -->|invokes| mylambda([mylambda])\n```";
 
     assert_eq!(mermaid_output, expected_output);
   }
@@ -441,34 +859,32 @@ mod tests {
     let template = Template {
       resources: vec![
         Resource {
-          name: Name("lambda1".to_string()),
+          name: Name::from("lambda1"),
           typ: ResourceType::Lambda,
-          properties: Property::Lambda {
-            function_name: "lambda1".to_string(),
-            architectures: vec!["arm64".to_string()],
-          },
+          properties: Property::lambda("lambda1").into(),
+          ..Default::default()
         },
         Resource {
-          name: Name("lambda2".to_string()),
+          name: Name::from("lambda2"),
           typ: ResourceType::Lambda,
-          properties: Property::Lambda {
-            function_name: "lambda2".to_string(),
-            architectures: vec!["arm64".to_string()],
-          },
+          properties: Property::lambda("lambda2").into(),
+          ..Default::default()
         },
         Resource {
-          name: Name("queue1".to_string()),
+          name: Name::from("queue1"),
           typ: ResourceType::Sqs,
           properties: Property::Sqs {
-            queue_name: "queue1".to_string(),
-          },
+            queue_name: "queue1".into(),
+          }.into(),
+          ..Default::default()
         },
       ],
+      ..Default::default()
     };
 
     let ast = AST::from(template);
 
-    assert_eq!(ast, AST { edges: vec![] });
+    assert_eq!(ast, AST { edges: vec![], ..Default::default() });
 
     let mermaid_output = ast.to_mermaid();
     let expected_output = "```mermaid\nflowchart LR\n```";
@@ -481,22 +897,21 @@ mod tests {
     let template = Template {
       resources: vec![
         Resource {
-          name: Name("MyQueue".to_string()),
+          name: Name::from("MyQueue"),
           typ: ResourceType::Sqs,
           properties: Property::Sqs {
-            queue_name: "MyQueue".to_string(),
-          },
+            queue_name: "MyQueue".into(),
+          }.into(),
+          ..Default::default()
         },
         Resource {
-          name: Name("MyLambda".to_string()),
+          name: Name::from("MyLambda"),
           typ: ResourceType::Lambda,
-          properties: Property::Lambda {
-            function_name: "MyLambda".to_string(),
-            architectures: vec!["arm64".to_string()],
-          },
+          properties: Property::lambda("MyLambda").into(),
+          ..Default::default()
         },
         Resource {
-          name: Name("MyEventSourceMapping".to_string()),
+          name: Name::from("MyEventSourceMapping"),
           typ: ResourceType::EventSourceMapping,
           properties: Property::EventSourceMapping {
             event_source_arn: json!({
@@ -505,39 +920,39 @@ mod tests {
             function_name: json!({
               "Ref": "MyLambda"
             }),
-          },
+          }.into(),
+          ..Default::default()
         },
       ],
+      ..Default::default()
     };
 
     let ast = AST::from(template);
 
     let expected_queue_node = Node {
-      name: Name("MyQueue".to_string()),
+      name: Name::from("MyQueue"),
       typ: ResourceType::Sqs,
       properties: Property::Sqs {
-        queue_name: "MyQueue".to_string(),
-      },
+        queue_name: "MyQueue".into(),
+      }.into(),
     };
     let expected_lambda_node = Node {
-      name: Name("MyLambda".to_string()),
+      name: Name::from("MyLambda"),
       typ: ResourceType::Lambda,
-      properties: Property::Lambda {
-        function_name: "MyLambda".to_string(),
-        architectures: vec!["arm64".to_string()],
-      },
+      properties: Property::lambda("MyLambda").into(),
     };
 
     // Should create SQS -> Lambda edge from EventSourceMapping
     assert_eq!(
       ast,
       AST {
-        edges: vec![(expected_queue_node, expected_lambda_node)]
+        edges: vec![Edge::new(expected_queue_node, expected_lambda_node)],
+        ..Default::default()
       }
     );
 
     let mermaid_output = ast.to_mermaid();
-    let expected_output = "```mermaid\nflowchart LR\nMyQueue((MyQueue)) --> MyLambda([MyLambda])\n```";
+    let expected_output = "```mermaid\nflowchart LR\nMyQueue((MyQueue)) -.->|triggers| MyLambda([MyLambda])\n```";
 
     assert_eq!(mermaid_output, expected_output);
   }
@@ -545,39 +960,408 @@ mod tests {
   #[test]
   fn test_to_mermaid_with_multiple_edges() {
     let api_node = Node {
-      name: Name("myapi".to_string()),
+      name: Name::from("myapi"),
       typ: ResourceType::ApiGateway,
       properties: Property::ApiGateway {
         http_method: "POST".to_string(),
         integration: serde_json::json!({}),
-      },
+        rest_api_id: serde_json::Value::Null,
+        resource_id: serde_json::Value::Null,
+        resource_path: None,
+      }.into(),
     };
     let lambda_node = Node {
-      name: Name("mylambda".to_string()),
+      name: Name::from("mylambda"),
       typ: ResourceType::Lambda,
-      properties: Property::Lambda {
-        function_name: "mylambda".to_string(),
-        architectures: vec!["arm64".to_string()],
-      },
+      properties: Property::lambda("mylambda").into(),
     };
     let sqs_node = Node {
-      name: Name("myqueue".to_string()),
+      name: Name::from("myqueue"),
       typ: ResourceType::Sqs,
       properties: Property::Sqs {
-        queue_name: "myqueue".to_string(),
-      },
+        queue_name: "myqueue".into(),
+      }.into(),
     };
     
     let ast = AST {
       edges: vec![
-        (api_node, lambda_node.clone()),
-        (lambda_node, sqs_node)
-      ]
+        Edge::new(api_node, lambda_node.clone()),
+        Edge::new(lambda_node, sqs_node)
+      ],
+      ..Default::default()
     };
 
     let mermaid_output = ast.to_mermaid();
-    let expected_output = "```mermaid\nflowchart LR\nmyapi[[myapi]] --> mylambda([mylambda])\nmylambda([mylambda]) --> myqueue((myqueue))\n```";
+    let expected_output = "```mermaid\nflowchart LR\nmyapi[[myapi]] -->|invokes| mylambda([mylambda])\nmylambda([mylambda]) -->|writes| myqueue((myqueue))\n```";
 
     assert_eq!(mermaid_output, expected_output);
   }
+
+  #[test]
+  fn test_edges_are_sorted_and_deduplicated() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "SecondMapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": {
+            "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] },
+            "FunctionName": { "Ref": "Worker" }
+          }
+        },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] }
+        },
+        "Api": {
+          "Type": "AWS::ApiGateway::Method",
+          "Properties": { "HttpMethod": "GET", "Integration": { "Uri": { "Fn::GetAtt": ["Worker", "Arn"] } } }
+        },
+        "FirstMapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": {
+            "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] },
+            "FunctionName": { "Ref": "Worker" }
+          }
+        },
+        "Queue": {
+          "Type": "AWS::SQS::Queue",
+          "Properties": { "QueueName": "jobs" }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = "```mermaid\nflowchart LR\nApi[[Api]] -->|invokes| Worker([\"worker\"])\nQueue((\"jobs\")) -.->|triggers| Worker([\"worker\"])\n```";
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_retain_prunes_edges_and_subgraphs() {
+    let api_node = Node {
+      name: Name::from("api"),
+      typ: ResourceType::ApiGateway,
+      properties: Property::ApiGateway {
+        http_method: "POST".to_string(),
+        integration: serde_json::json!({}),
+        rest_api_id: serde_json::Value::Null,
+        resource_id: serde_json::Value::Null,
+        resource_path: None,
+      }.into(),
+    };
+    let lambda_node = Node {
+      name: Name::from("lambda"),
+      typ: ResourceType::Lambda,
+      properties: Property::lambda("lambda").into(),
+    };
+    let sqs_node = Node {
+      name: Name::from("queue"),
+      typ: ResourceType::Sqs,
+      properties: Property::Sqs {
+        queue_name: "queue".into(),
+      }.into(),
+    };
+
+    let mut ast = AST {
+      edges: vec![
+        Edge::new(api_node.clone(), lambda_node.clone()),
+        Edge::new(lambda_node.clone(), sqs_node.clone()),
+      ],
+      subgraphs: vec![Subgraph {
+        name: "api".to_string(),
+        nodes: vec![api_node],
+        ..Default::default()
+      }],
+      ..Default::default()
+    };
+    ast.retain(|node| node.typ != ResourceType::ApiGateway);
+
+    assert_eq!(ast, AST { edges: vec![Edge::new(lambda_node, sqs_node)], ..Default::default() });
+  }
+
+  #[test]
+  fn test_references_require_exact_logical_ids() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "QueueDlq": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs-dlq" } },
+        "Api": {
+          "Type": "AWS::ApiGateway::Method",
+          "Properties": {
+            "HttpMethod": "POST",
+            "Integration": {
+              "Uri": { "Fn::Sub": "arn:aws:apigateway:${AWS::Region}:sqs:path/${AWS::AccountId}/${QueueDlq.QueueName}" },
+              "Description": "Sends to Queue"
+            }
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = "```mermaid\nflowchart LR\nApi[[Api]] --> QueueDlq((\"jobs-dlq\"))\n```";
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_event_source_mapping_with_joined_arn() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": {
+            "EventSourceArn": {
+              "Fn::Join": ["", ["arn:aws:sqs:", { "Ref": "AWS::Region" }, ":", { "Ref": "AWS::AccountId" }, ":", { "Fn::GetAtt": ["Queue", "QueueName"] }]]
+            },
+            "FunctionName": { "Fn::Join": [":", [{ "Fn::GetAtt": ["Worker", "Arn"] }, "live"]] }
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = "```mermaid\nflowchart LR\nQueue((\"jobs\")) -.->|triggers| Worker([\"worker\"])\n```";
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_dynamic_references_become_external_nodes() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Api": {
+          "Type": "AWS::ApiGateway::Method",
+          "Properties": {
+            "HttpMethod": "POST",
+            "Integration": {
+              "Credentials": "{{resolve:ssm:/app/api-role:2}}",
+              "RequestParameters": { "integration.request.header.x-api-key": "'{{resolve:secretsmanager:partner:SecretString:key}}'" }
+            }
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "_app_api_role[/\"/app/api-role\"/] --> Api[[Api]]\n",
+      "partner>partner] --> Api[[Api]]\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_unnamed_resources_keep_their_type() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": {} },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": { "Environment": { "Variables": { "QUEUE_URL": { "Ref": "Queue" } } } }
+        }
+      }
+    }))
+    .unwrap();
+
+    assert!(matches!(*template.resources[0].properties, Property::Sqs { queue_name: serde_json::Value::Null }));
+    assert_eq!(
+      serde_json::to_value(&*template.resources[1].properties).unwrap(),
+      json!({ "Environment": { "Variables": { "QUEUE_URL": { "Ref": "Queue" } } } })
+    );
+    assert_eq!(
+      AST::from(template).to_mermaid(),
+      "```mermaid\nflowchart LR\nWorker([Worker]) -->|configures| Queue((Queue))\n```"
+    );
+  }
+
+  #[test]
+  fn test_names_built_by_intrinsics_keep_their_type() {
+    let template = crate::cloudformation::yaml::from_str(
+      "
+Resources:
+  Queue:
+    Type: AWS::SQS::Queue
+    Properties:
+      QueueName: !Sub \"${AWS::StackName}-jobs\"
+  Worker:
+    Type: AWS::Lambda::Function
+    Properties:
+      FunctionName: !Sub \"${AWS::StackName}-worker\"
+      Environment:
+        Variables:
+          QUEUE_URL: !Ref Queue
+",
+    )
+    .unwrap();
+
+    assert!(matches!(&*template.resources[0].properties, Property::Sqs { queue_name } if *queue_name == json!({ "Fn::Sub": "${AWS::StackName}-jobs" })));
+    assert_eq!(
+      AST::from(template).to_mermaid(),
+      "```mermaid\nflowchart LR\nWorker([Worker]) -->|configures| Queue((Queue))\n```"
+    );
+  }
+
+  #[test]
+  fn test_environment_variables_configure_functions() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Table": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "orders" } },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": {
+            "FunctionName": "worker",
+            "Architectures": ["arm64"],
+            "Environment": {
+              "Variables": {
+                "QUEUE_URL": { "Ref": "Queue" },
+                "TABLE_NAME": { "Fn::GetAtt": ["Table", "Arn"] },
+                "STAGE": "prod"
+              }
+            }
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Worker([\"worker\"]) -->|configures| Queue((\"jobs\"))\n",
+      "Worker([\"worker\"]) -->|configures| Table[(\"orders\")]\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_event_source_mapping_with_literal_arns() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": "arn:aws:sqs:eu-west-2:123456789012:jobs", "FunctionName": "worker" }
+        },
+        "StreamMapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": {
+            "EventSourceArn": "arn:aws:kinesis:eu-west-2:123456789012:stream/clicks",
+            "FunctionName": "arn:aws:lambda:eu-west-2:123456789012:function:worker"
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Queue((\"jobs\")) -.->|triggers| Worker([\"worker\"])\n",
+      "clicks[clicks] -.->|triggers| Worker([\"worker\"])\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_references_across_a_large_template() {
+    let mut resources = serde_json::Map::new();
+    resources.insert("Jobs".to_string(), json!({ "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } }));
+    for i in 0..500 {
+      resources.insert(
+        format!("Worker{}", i),
+        json!({
+          "Type": "AWS::Lambda::Function",
+          "Properties": {
+            "FunctionName": format!("worker-{}", i),
+            "Architectures": ["arm64"],
+            "Environment": { "Variables": { "QUEUE": { "Ref": "Jobs" }, "QUEUE_ARN": { "Fn::GetAtt": ["Jobs", "Arn"] } } }
+          }
+        }),
+      );
+    }
+    let template: Template = serde_json::from_value(json!({ "Resources": resources })).unwrap();
+
+    let ast = AST::from(template);
+
+    assert_eq!(ast.edges.len(), 500);
+    assert!(ast.edges.iter().all(|edge| &*edge.to.name.0 == "Jobs" && edge.relationship == Relationship::Configures));
+  }
+
+  #[test]
+  fn test_orient_edges() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Table": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "orders" } },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        }
+      }
+    }))
+    .unwrap();
+    let node = |name: &str| Node::from(template.resources.iter().find(|r| &*r.name.0 == name).unwrap());
+    let mut ast = AST::from(template.clone());
+    ast.edges.push(Edge::new(node("Worker"), node("Table")));
+
+    let mut flow = ast.clone();
+    flow.orient(EdgeDirection::Flow);
+    let mut dependency = ast.clone();
+    dependency.orient(EdgeDirection::Dependency);
+
+    assert_eq!(
+      flow.to_mermaid(),
+      "```mermaid\nflowchart LR\nQueue((\"jobs\")) -.->|triggers| Worker([\"worker\"])\nTable[(\"orders\")] -->|reads| Worker([\"worker\"])\n```"
+    );
+    assert_eq!(
+      dependency.to_mermaid(),
+      "```mermaid\nflowchart LR\nWorker([\"worker\"]) -.->|triggers| Queue((\"jobs\"))\nWorker([\"worker\"]) -->|reads| Table[(\"orders\")]\n```"
+    );
+  }
+
+  #[test]
+  fn test_serialize_model() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Jobs": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": { "FunctionName": "worker", "Architectures": ["arm64"], "Environment": { "Variables": { "QUEUE": { "Ref": "Jobs" } } } }
+        }
+      }
+    }))
+    .unwrap();
+
+    let resource = serde_json::to_value(&template.resources[0]).unwrap();
+    assert_eq!(resource["name"], "Jobs");
+    assert_eq!(resource["typ"], "sqs");
+    assert_eq!(resource["properties"], json!({ "QueueName": "jobs" }));
+
+    let ast = serde_json::to_value(AST::from(template)).unwrap();
+    assert_eq!(
+      ast["edges"],
+      json!([{
+        "from": {
+          "name": "Worker",
+          "typ": "lambda",
+          "properties": { "FunctionName": "worker", "Architectures": ["arm64"], "Environment": { "Variables": { "QUEUE": { "Ref": "Jobs" } } } }
+        },
+        "to": { "name": "Jobs", "typ": "sqs", "properties": { "QueueName": "jobs" } },
+        "relationship": "configures",
+        "detail": null
+      }])
+    );
+    assert_eq!(ast["cross_stack_edges"], json!([]));
+  }
 }
@@ -0,0 +1,250 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use rayon::prelude::*;
+use tracing::debug;
+
+use crate::ast::ast::Found;
+use crate::ast::edge::Edge;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Resource, ResourceType};
+use crate::cloudformation::template::Template;
+
+// Resources whose references are remembered, the least recently used going
+// first, so a long watch session over changing templates stays bounded.
+const REFERENCES: usize = 100_000;
+// Graphs remembered, the least recently used going first: enough for every
+// stack of a large CDK app.
+const GRAPHS: usize = 64;
+
+/// Remembers the graph of each template and what each resource refers to
+/// between renders, so that in watch mode an unchanged stack is not built
+/// again, and in a changed one only the resources changed since the last save
+/// have their properties walked. Clones share the same entries.
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceCache {
+  entries: Arc<Mutex<Entries>>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+  // Keyed by the resource's type and properties rather than its logical ID, so
+  // stacks reusing an ID do not evict each other. Keys are compared in full,
+  // so two resources never share an entry through their hashes alone.
+  references: HashMap<Key, (Found, u64)>,
+  // Most recently used first.
+  graphs: VecDeque<Built>,
+  // Advanced on every lookup, to tell which entries were used last.
+  clock: u64,
+}
+
+type Key = (ResourceType, Arc<Property>);
+
+// A template's edges before any to resources left out are dropped, along with
+// which of its resources were kept, since only those made edges.
+#[derive(Debug)]
+struct Built {
+  template: Template,
+  kept: Vec<bool>,
+  edges: Vec<Edge>,
+}
+
+impl ReferenceCache {
+  // Properties kept as JSON text are parsed on first use, which clippy takes
+  // for a key that can change; they are never used as keys.
+  #[allow(clippy::mutable_key_type)]
+  pub(crate) fn find(&self, resources: &[Resource], extract: impl Fn(&Resource) -> Found + Sync) -> Vec<Found> {
+    let keys: Vec<Option<Key>> = resources.iter().map(key).collect();
+    // The lock is not held while walking: rayon may run another stack's
+    // lookup on this thread in the meantime.
+    let cached: Vec<Option<Found>> = {
+      let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+      entries.clock += 1;
+      let clock = entries.clock;
+      keys
+        .iter()
+        .map(|key| {
+          let (found, used) = entries.references.get_mut(key.as_ref()?)?;
+          *used = clock;
+          Some(found.clone())
+        })
+        .collect()
+    };
+    debug!("references of {} of {} resources reused", cached.iter().flatten().count(), resources.len());
+
+    let found: Vec<(Found, bool)> = resources
+      .par_iter()
+      .zip(cached)
+      .map(|(resource, cached)| match cached {
+        Some(found) => (found, true),
+        None => (extract(resource), false),
+      })
+      .collect();
+
+    let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    let clock = entries.clock;
+    let found = found
+      .into_iter()
+      .zip(keys)
+      .map(|((found, reused), key)| {
+        if let (Some(key), false) = (key, reused) {
+          entries.references.insert(key, (found.clone(), clock));
+        }
+        found
+      })
+      .collect();
+    entries.evict(REFERENCES);
+    found
+  }
+
+  // The edges of a template built with the same resources kept before, or
+  // those `build` makes, which are then remembered.
+  pub(crate) fn edges(&self, template: &Template, kept: &[bool], build: impl FnOnce() -> Vec<Edge>) -> Vec<Edge> {
+    {
+      let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+      let position = entries.graphs.iter().position(|built| built.kept == kept && built.template == *template);
+      if let Some(built) = position.and_then(|position| entries.graphs.remove(position)) {
+        debug!("graph of {} resources reused", template.resources.len());
+        let edges = built.edges.clone();
+        entries.graphs.push_front(built);
+        return edges;
+      }
+    }
+
+    let edges = build();
+    let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    entries.graphs.push_front(Built {
+      template: template.clone(),
+      kept: kept.to_vec(),
+      edges: edges.clone(),
+    });
+    entries.graphs.truncate(GRAPHS);
+    edges
+  }
+}
+
+impl Entries {
+  // Drops the least recently used references beyond `limit`; ties on the
+  // oldest lookup all go.
+  fn evict(&mut self, limit: usize) {
+    if self.references.len() <= limit {
+      return;
+    }
+    let excess = self.references.len() - limit;
+    let mut used: Vec<u64> = self.references.values().map(|(_, used)| *used).collect();
+    let oldest = *used.select_nth_unstable(excess - 1).1;
+    self.references.retain(|_, (_, used)| *used > oldest);
+  }
+}
+
+// API Gateway integrations may name a function by ARN, which is matched
+// against the rest of the template, so they are always worked out again.
+// Unparsed properties are never walked, and hashing them would parse them.
+fn key(resource: &Resource) -> Option<Key> {
+  if let Property::ApiGateway { .. } | Property::Unparsed(_) = &*resource.properties {
+    return None;
+  }
+  Some((resource.typ.clone(), resource.properties.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  use serde_json::json;
+
+  use super::*;
+  use crate::ast::ast::{self, AST};
+  use crate::cloudformation::resource::{Name, ResourceType};
+  use crate::cloudformation::template::Template;
+
+  fn queue(name: &str, properties: serde_json::Value) -> Resource {
+    Resource {
+      name: Name::from(name),
+      typ: ResourceType::Other,
+      properties: Property::Other(properties).into(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_only_changed_resources_are_walked() {
+    let cache = ReferenceCache::default();
+    let walked = AtomicUsize::new(0);
+    let extract = |resource: &Resource| {
+      walked.fetch_add(1, Ordering::Relaxed);
+      Found {
+        referenced: vec![resource.name.0.to_string()],
+        ..Default::default()
+      }
+    };
+
+    let resources = vec![queue("A", json!({ "Ref": "B" })), queue("B", json!({}))];
+    cache.find(&resources, extract);
+    assert_eq!(walked.load(Ordering::Relaxed), 2);
+
+    let changed = vec![queue("A", json!({ "Ref": "C" })), queue("B", json!({}))];
+    let found = cache.find(&changed, extract);
+    assert_eq!(walked.load(Ordering::Relaxed), 3);
+    let referenced: Vec<&Vec<String>> = found.iter().map(|found| &found.referenced).collect();
+    assert_eq!(referenced, [&vec!["A".to_string()], &vec!["B".to_string()]]);
+  }
+
+  #[test]
+  fn test_cached_graph_follows_edits() {
+    let template = |queue: &str| -> Template {
+      serde_json::from_value(json!({
+        "Resources": {
+          "Jobs": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+          "Retries": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "retries" } },
+          "Worker": {
+            "Type": "AWS::Lambda::Function",
+            "Properties": { "FunctionName": "worker", "Environment": { "Variables": { "QUEUE": { "Ref": queue } } } }
+          }
+        }
+      }))
+      .unwrap()
+    };
+    let cache = ReferenceCache::default();
+
+    for queue in ["Jobs", "Retries", "Jobs"] {
+      assert_eq!(ast::build(&template(queue), Some(&cache), &|_| true), AST::from(template(queue)));
+    }
+  }
+
+  #[test]
+  fn test_unchanged_graph_is_reused() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": { "Jobs": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } } }
+    }))
+    .unwrap();
+    let cache = ReferenceCache::default();
+    let built = AtomicUsize::new(0);
+    let build = || {
+      built.fetch_add(1, Ordering::Relaxed);
+      Vec::new()
+    };
+
+    cache.edges(&template, &[true], build);
+    cache.edges(&template, &[true], build);
+    assert_eq!(built.load(Ordering::Relaxed), 1);
+    cache.edges(&template, &[false], build);
+    assert_eq!(built.load(Ordering::Relaxed), 2);
+  }
+
+  #[test]
+  fn test_least_recently_used_references_are_evicted() {
+    let cache = ReferenceCache::default();
+    let extract = |_: &Resource| Found::default();
+    for name in ["A", "B", "C"] {
+      cache.find(&[queue(name, json!({ "Name": name }))], extract);
+    }
+    cache.find(&[queue("A", json!({ "Name": "A" }))], extract);
+
+    let mut entries = cache.entries.lock().unwrap();
+    entries.evict(2);
+    let mut names: Vec<String> = entries.references.keys().map(|(_, properties)| properties.json()[0]["Name"].to_string()).collect();
+    names.sort();
+    assert_eq!(names, ["\"A\"", "\"C\""]);
+  }
+}
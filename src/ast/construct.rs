@@ -0,0 +1,132 @@
+use crate::ast::ast::AST;
+use crate::ast::node::Node;
+use crate::ast::subgraph::Subgraph;
+use crate::cloudformation::stack::Stack;
+
+// CDK records each resource's construct path (`Stack/Api/Handler/Resource`) in
+// `aws:cdk:path` metadata; the path minus the resource itself becomes its chain
+// of nested subgraphs.
+pub fn group(ast: &mut AST, stacks: &[Stack]) {
+  let has_paths = stacks
+    .iter()
+    .flat_map(|stack| stack.template.resources.iter())
+    .any(|resource| resource.cdk_path().is_some());
+
+  if !has_paths {
+    return;
+  }
+
+  let mut subgraphs = Vec::new();
+
+  for node in ast.nodes() {
+    let path = stacks.iter().find_map(|stack| {
+      let resource = stack
+        .template
+        .resources
+        .iter()
+        .find(|resource| Node::from(resource) == *node)?;
+
+      match resource.cdk_path() {
+        Some(path) => Some(construct_path(path)),
+        None if stacks.len() > 1 => Some(vec![stack.name.clone()]),
+        None => None,
+      }
+    });
+
+    if let Some(path) = path.filter(|path| !path.is_empty()) {
+      insert(&mut subgraphs, &path, node.clone());
+    }
+  }
+
+  ast.subgraphs = subgraphs;
+}
+
+fn construct_path(path: &str) -> Vec<String> {
+  let mut segments: Vec<String> = path.split('/').map(str::to_string).collect();
+
+  if segments.last().is_some_and(|segment| segment == "Resource") {
+    segments.pop();
+  }
+  segments.pop();
+
+  segments
+}
+
+fn insert(subgraphs: &mut Vec<Subgraph>, path: &[String], node: Node) {
+  let Some((name, rest)) = path.split_first() else {
+    return;
+  };
+
+  let index = match subgraphs.iter().position(|subgraph| subgraph.name == *name) {
+    Some(index) => index,
+    None => {
+      subgraphs.push(Subgraph {
+        name: name.clone(),
+        ..Default::default()
+      });
+      subgraphs.len() - 1
+    }
+  };
+
+  match rest.is_empty() {
+    true => subgraphs[index].nodes.push(node),
+    false => insert(&mut subgraphs[index].subgraphs, rest, node),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_group_by_construct_path() {
+    let template: Template = serde_json::from_str(
+      r#"{
+        "Resources": {
+          "JobsQueue": {
+            "Type": "AWS::SQS::Queue",
+            "Properties": { "QueueName": "jobs" },
+            "Metadata": { "aws:cdk:path": "App/Jobs/Queue/Resource" }
+          },
+          "WorkerFunction": {
+            "Type": "AWS::Lambda::Function",
+            "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] },
+            "Metadata": { "aws:cdk:path": "App/Jobs/Worker/Resource" }
+          },
+          "WorkerMapping": {
+            "Type": "AWS::Lambda::EventSourceMapping",
+            "Properties": {
+              "EventSourceArn": { "Fn::GetAtt": ["JobsQueue", "Arn"] },
+              "FunctionName": { "Ref": "WorkerFunction" }
+            },
+            "Metadata": { "aws:cdk:path": "App/Jobs/Worker/SqsEventSource/Resource" }
+          }
+        }
+      }"#,
+    )
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "App".to_string(),
+      template: template.clone(),
+    }];
+
+    let mut ast = AST::from(template);
+    group(&mut ast, &stacks);
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "subgraph App\n",
+      "subgraph App_Jobs[Jobs]\n",
+      "JobsQueue((\"jobs\"))\n",
+      "WorkerFunction([\"worker\"])\n",
+      "end\n",
+      "end\n",
+      "JobsQueue((\"jobs\")) -.->|triggers| WorkerFunction([\"worker\"])\n",
+      "```",
+    );
+
+    assert_eq!(ast.to_mermaid(), expected_output);
+  }
+}
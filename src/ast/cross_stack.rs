@@ -0,0 +1,55 @@
+use rayon::prelude::*;
+
+use crate::ast::ast::{self, AST, canonical};
+use crate::ast::cache::ReferenceCache;
+use crate::ast::edge::Edge;
+use crate::ast::imports;
+use crate::ast::node::Node;
+use crate::ast::subgraph::Subgraph;
+use crate::cloudformation::stack::Stack;
+
+impl From<Vec<Stack>> for AST {
+  fn from(stacks: Vec<Stack>) -> Self {
+    build(&stacks, None, &|_| true)
+  }
+}
+
+pub(crate) fn build(stacks: &[Stack], cache: Option<&ReferenceCache>, keep: &(dyn Fn(&Node) -> bool + Sync)) -> AST {
+  let edges: Vec<Edge> = stacks
+    .par_iter()
+    .flat_map_iter(|stack| ast::build(&stack.template, cache, keep).edges)
+    .collect();
+  let cross_stack_edges = imports::edges(stacks, keep);
+
+  let subgraphs = stacks
+    .iter()
+    .map(|stack| {
+      let mut nodes: Vec<Node> = stack
+        .template
+        .resources
+        .iter()
+        .map(Node::from)
+        .filter(|node| {
+          edges
+            .iter()
+            .chain(cross_stack_edges.iter())
+            .any(|edge| edge.from == *node || edge.to == *node)
+        })
+        .collect();
+      nodes.sort_by(|a, b| a.name.0.cmp(&b.name.0));
+
+      Subgraph {
+        name: stack.name.clone(),
+        nodes,
+        ..Default::default()
+      }
+    })
+    .filter(|subgraph| !subgraph.nodes.is_empty())
+    .collect();
+
+  AST {
+    edges: canonical(edges),
+    subgraphs,
+    cross_stack_edges: canonical(cross_stack_edges),
+  }
+}
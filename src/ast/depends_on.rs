@@ -0,0 +1,65 @@
+use crate::ast::ast::{AST, canonical};
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::stack::Stack;
+
+// `DependsOn` only orders deployment, so these edges are opt-in and drawn
+// dotted grey rather than mixed in with runtime relationships.
+pub fn add(ast: &mut AST, stacks: &[Stack]) {
+  for stack in stacks {
+    let template = &stack.template;
+
+    for resource in template.resources.iter().filter(|r| r.typ.is_supported()) {
+      for dependency in &resource.depends_on {
+        let Some(target) = template.resource(dependency).filter(|r| r.typ.is_supported()) else {
+          continue;
+        };
+
+        ast.edges.push(Edge::with(Node::from(resource), Node::from(target), Relationship::DependsOn));
+      }
+    }
+  }
+
+  ast.edges = canonical(std::mem::take(&mut ast.edges));
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_depends_on_edges() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "DependsOn": ["Queue", "Role"],
+          "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] }
+        },
+        "Role": { "Type": "AWS::IAM::Role", "Properties": {} }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template: template.clone(),
+    }];
+
+    let mut ast = AST::from(template);
+    add(&mut ast, &stacks);
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Worker([\"worker\"]) -.-> Queue((\"jobs\"))\n",
+      "linkStyle 0 stroke:#999,stroke-dasharray:3\n",
+      "```",
+    );
+
+    assert_eq!(ast.to_mermaid(), expected_output);
+  }
+}
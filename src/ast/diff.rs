@@ -0,0 +1,166 @@
+use crate::ast::ast::AST;
+use crate::ast::edge::Edge;
+use crate::ast::node::Node;
+
+// Nodes are matched across the two graphs by logical ID, so a resource whose
+// properties changed is reported as changed rather than removed and re-added.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Diff {
+  pub added_nodes: Vec<Node>,
+  pub removed_nodes: Vec<Node>,
+  pub changed_nodes: Vec<Node>,
+  pub unchanged_nodes: Vec<Node>,
+  pub added_edges: Vec<Edge>,
+  pub removed_edges: Vec<Edge>,
+  pub unchanged_edges: Vec<Edge>,
+}
+
+impl Diff {
+  pub fn new(old: &AST, new: &AST) -> Self {
+    let mut diff = Diff::default();
+    let old_nodes = old.nodes();
+    let new_nodes = new.nodes();
+
+    for node in &new_nodes {
+      match old_nodes.iter().find(|old| old.name == node.name) {
+        None => diff.added_nodes.push((*node).clone()),
+        Some(old) if old != node => diff.changed_nodes.push((*node).clone()),
+        Some(_) => diff.unchanged_nodes.push((*node).clone()),
+      }
+    }
+
+    for node in &old_nodes {
+      if !new_nodes.iter().any(|new| new.name == node.name) {
+        diff.removed_nodes.push((*node).clone());
+      }
+    }
+
+    let old_edges: Vec<&Edge> = old.edges.iter().chain(old.cross_stack_edges.iter()).collect();
+    let new_edges: Vec<&Edge> = new.edges.iter().chain(new.cross_stack_edges.iter()).collect();
+
+    for edge in &new_edges {
+      match old_edges.iter().any(|old| same_edge(old, edge)) {
+        true => diff.unchanged_edges.push((*edge).clone()),
+        false => diff.added_edges.push((*edge).clone()),
+      }
+    }
+
+    for edge in &old_edges {
+      if !new_edges.iter().any(|new| same_edge(new, edge)) {
+        diff.removed_edges.push((*edge).clone());
+      }
+    }
+
+    diff
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.added_nodes.is_empty()
+      && self.removed_nodes.is_empty()
+      && self.changed_nodes.is_empty()
+      && self.added_edges.is_empty()
+      && self.removed_edges.is_empty()
+  }
+
+  pub fn to_flowchart(&self) -> String {
+    let mut result = String::from("flowchart LR\n");
+
+    let edges = self
+      .unchanged_edges
+      .iter()
+      .map(|edge| (edge, None))
+      .chain(self.added_edges.iter().map(|edge| (edge, Some("stroke:#28a745"))))
+      .chain(self.removed_edges.iter().map(|edge| (edge, Some("stroke:#dc3545,stroke-dasharray:4"))));
+
+    let mut link_styles = Vec::new();
+    for (index, (edge, style)) in edges.enumerate() {
+      result.push_str(&format!("{} {} {}\n", edge.from, edge.flowchart_arrow(), edge.to));
+      if let Some(style) = style {
+        link_styles.push(format!("linkStyle {} {}\n", index, style));
+      }
+    }
+
+    for node in self.added_nodes.iter().chain(self.removed_nodes.iter()).chain(self.changed_nodes.iter()) {
+      result.push_str(&format!("{}\n", node));
+    }
+
+    for link_style in link_styles {
+      result.push_str(&link_style);
+    }
+
+    result.push_str("classDef added fill:#d4edda,stroke:#28a745\n");
+    result.push_str("classDef removed fill:#f8d7da,stroke:#dc3545\n");
+    result.push_str("classDef changed fill:#fff3cd,stroke:#ffc107\n");
+
+    for (class, nodes) in [
+      ("added", &self.added_nodes),
+      ("removed", &self.removed_nodes),
+      ("changed", &self.changed_nodes),
+    ] {
+      if !nodes.is_empty() {
+        let ids: Vec<String> = nodes.iter().map(|node| node.id().into_owned()).collect();
+        result.push_str(&format!("class {} {}\n", ids.join(","), class));
+      }
+    }
+
+    result
+  }
+}
+
+fn same_edge(a: &Edge, b: &Edge) -> bool {
+  a.from.name == b.from.name && a.to.name == b.to.name && a.relationship == b.relationship
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  fn ast(value: serde_json::Value) -> AST {
+    AST::from(serde_json::from_value::<Template>(value).unwrap())
+  }
+
+  #[test]
+  fn test_diff_templates() {
+    let old = ast(json!({
+      "Resources": {
+        "Api": { "Type": "AWS::ApiGateway::Method", "Properties": { "HttpMethod": "GET", "Integration": { "Uri": { "Ref": "Worker" } } } },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["x86_64"] } }
+      }
+    }));
+    let new = ast(json!({
+      "Resources": {
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        }
+      }
+    }));
+
+    let diff = Diff::new(&old, &new);
+    let expected_output = concat!(
+      "flowchart LR\n",
+      "Queue((\"jobs\")) -.->|triggers| Worker([\"worker\"])\n",
+      "Api[[Api]] -->|invokes| Worker([\"worker\"])\n",
+      "Queue((\"jobs\"))\n",
+      "Api[[Api]]\n",
+      "Worker([\"worker\"])\n",
+      "linkStyle 0 stroke:#28a745\n",
+      "linkStyle 1 stroke:#dc3545,stroke-dasharray:4\n",
+      "classDef added fill:#d4edda,stroke:#28a745\n",
+      "classDef removed fill:#f8d7da,stroke:#dc3545\n",
+      "classDef changed fill:#fff3cd,stroke:#ffc107\n",
+      "class Queue added\n",
+      "class Api removed\n",
+      "class Worker changed\n",
+    );
+
+    assert!(!diff.is_empty());
+    assert_eq!(diff.to_flowchart(), expected_output);
+  }
+}
@@ -0,0 +1,164 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::ast::node::Node;
+use crate::cloudformation::resource::ResourceType;
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Edge {
+  pub from: Node,
+  pub to: Node,
+  pub relationship: Relationship,
+  pub detail: Option<String>,
+}
+
+// In alphabetical order, which is how edges between the same two resources
+// are sorted.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Relationship {
+  Configures,
+  DependsOn,
+  Grants,
+  Invokes,
+  Mounts,
+  Reads,
+  References,
+  Routes,
+  Subscribes,
+  Triggers,
+  Writes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum EdgeDirection {
+  Flow,
+  Dependency,
+}
+
+impl std::fmt::Display for Relationship {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Relationship::Invokes => write!(f, "invokes"),
+      Relationship::Triggers => write!(f, "triggers"),
+      Relationship::Reads => write!(f, "reads"),
+      Relationship::Writes => write!(f, "writes"),
+      Relationship::Subscribes => write!(f, "subscribes"),
+      Relationship::References => write!(f, "references"),
+      Relationship::Configures => write!(f, "configures"),
+      Relationship::Routes => write!(f, "routes"),
+      Relationship::Grants => write!(f, "grants"),
+      Relationship::DependsOn => write!(f, "depends on"),
+      Relationship::Mounts => write!(f, "mounts"),
+    }
+  }
+}
+
+impl Relationship {
+  // Templates rarely say what a reference is for, so the relationship is
+  // inferred from the pair of resource types at either end.
+  pub fn between(from: &Node, to: &Node) -> Self {
+    match (&from.typ, &to.typ) {
+      (ResourceType::ApiGateway | ResourceType::FunctionUrl, ResourceType::Lambda) => Relationship::Invokes,
+      // External sources only come from event source mappings with literal ARNs.
+      // A bucket notifies functions of changes to its objects.
+      (
+        ResourceType::Sqs | ResourceType::DynamoDb | ResourceType::Kinesis | ResourceType::Bucket | ResourceType::External,
+        ResourceType::Lambda,
+      ) => Relationship::Triggers,
+      (ResourceType::Lambda, ResourceType::Sqs) => Relationship::Writes,
+      (ResourceType::Lambda, ResourceType::DynamoDb) => Relationship::Reads,
+      // Crawlers read a bucket's objects and write the tables they find.
+      (ResourceType::GlueCrawler, ResourceType::Bucket) => Relationship::Reads,
+      (ResourceType::GlueCrawler, ResourceType::GlueDatabase) => Relationship::Writes,
+      // Delivery streams load into their destination and only call a processor.
+      (ResourceType::Firehose, ResourceType::Bucket | ResourceType::Redshift) => Relationship::Writes,
+      (ResourceType::Kinesis, ResourceType::Firehose) => Relationship::Triggers,
+      // A topic's inline `Subscription`s name the endpoints it delivers to.
+      // Anything else it mentions, such as its KMS key, is only referenced.
+      (ResourceType::Sns, ResourceType::Lambda | ResourceType::Sqs | ResourceType::Firehose) => Relationship::Subscribes,
+      _ => Relationship::References,
+    }
+  }
+
+  // Event sources and subscriptions deliver through a queue or poller, so the
+  // producer never waits on the consumer.
+  pub fn is_async(&self) -> bool {
+    matches!(self, Relationship::Triggers | Relationship::Subscribes | Relationship::Routes)
+  }
+
+  pub fn label(&self) -> Option<String> {
+    match self {
+      // A grant is labelled by its actions alone, carried in the detail.
+      Relationship::References | Relationship::Grants | Relationship::DependsOn => None,
+      relationship => Some(relationship.to_string()),
+    }
+  }
+}
+
+impl Edge {
+  pub fn new(from: Node, to: Node) -> Self {
+    let relationship = Relationship::between(&from, &to);
+    Edge::with(from, to, relationship)
+  }
+
+  pub fn with(from: Node, to: Node, relationship: Relationship) -> Self {
+    Edge {
+      from,
+      to,
+      relationship,
+      detail: None,
+    }
+  }
+
+  // The detail narrows the relationship down for this one edge, such as the
+  // part of an event a rule passes on to its target.
+  pub fn label(&self) -> Option<String> {
+    match (self.relationship.label(), &self.detail) {
+      (Some(label), Some(detail)) => Some(format!("{} {}", label, detail)),
+      (None, Some(detail)) => Some(detail.clone()),
+      (label, None) => label,
+    }
+  }
+
+  pub fn is_async(&self) -> bool {
+    self.relationship.is_async()
+  }
+
+  // Edges are built from whichever end the template wires them at: most point
+  // from the referencing resource to the referenced one, but event sources and
+  // inputs (parameters, secrets, external sources) already follow the data.
+  pub fn points(&self, direction: EdgeDirection) -> bool {
+    let input = matches!(
+      self.from.typ,
+      ResourceType::Parameter | ResourceType::SsmParameter | ResourceType::Secret | ResourceType::External
+    );
+
+    match direction {
+      EdgeDirection::Flow => self.relationship != Relationship::Reads,
+      EdgeDirection::Dependency => {
+        !(input || matches!(self.relationship, Relationship::Triggers | Relationship::Subscribes))
+      }
+    }
+  }
+
+  pub fn reversed(self) -> Self {
+    Edge {
+      from: self.to,
+      to: self.from,
+      ..self
+    }
+  }
+
+  pub fn flowchart_arrow(&self) -> String {
+    let dotted = matches!(self.relationship, Relationship::Grants | Relationship::DependsOn);
+    self.arrow(if self.is_async() || dotted { "-.->" } else { "-->" })
+  }
+
+  pub fn arrow(&self, arrow: &str) -> String {
+    match self.label() {
+      Some(label) => format!("{}|{}|", arrow, label.replace('"', "#quot;").replace('|', "#124;")),
+      None => arrow.to_string(),
+    }
+  }
+}
@@ -0,0 +1,77 @@
+use serde_json::Value;
+
+use crate::ast::ast::{literal_node, referenced_resource};
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::arn::Arn;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::Resource;
+use crate::cloudformation::template::Template;
+
+// Each entry in a function's `FileSystemConfigs` mounts an access point, by
+// reference or literal ARN, at the local path the edge is labelled with.
+pub(crate) fn mount_edges(function: &Resource, template: &Template) -> Vec<Edge> {
+  let Property::Lambda { file_system_configs, .. } = &*function.properties else {
+    return Vec::new();
+  };
+
+  file_system_configs
+    .as_array()
+    .into_iter()
+    .flatten()
+    .filter_map(|config| {
+      let arn = config.get("Arn")?;
+      let access_point = match referenced_resource(arn, template) {
+        Some(resource) => Node::from(resource),
+        None => literal_node(&Arn::parse(arn.as_str()?)?, template),
+      };
+
+      let mut edge = Edge::with(Node::from(function), access_point, Relationship::Mounts);
+      edge.detail = config.get("LocalMountPath").and_then(Value::as_str).map(|path| format!("({})", path));
+      Some(edge)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::ast::AST;
+
+  use super::*;
+
+  #[test]
+  fn test_mounted_file_systems() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Models": { "Type": "AWS::EFS::FileSystem", "Properties": { "Encrypted": true } },
+        "ModelsAccess": {
+          "Type": "AWS::EFS::AccessPoint",
+          "Properties": { "FileSystemId": { "Ref": "Models" }, "RootDirectory": { "Path": "/models" } }
+        },
+        "Inference": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": {
+            "FunctionName": "inference",
+            "FileSystemConfigs": [
+              { "Arn": { "Fn::GetAtt": ["ModelsAccess", "Arn"] }, "LocalMountPath": "/mnt/models" },
+              { "Arn": "arn:aws:elasticfilesystem:eu-west-2:123:access-point/fsap-0abc", "LocalMountPath": "/mnt/shared" }
+            ]
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Inference([\"inference\"]) -->|mounts (/mnt/models)| ModelsAccess>ModelsAccess]\n",
+      "Inference([\"inference\"]) -->|mounts (/mnt/shared)| fsap_0abc[\"fsap-0abc\"]\n",
+      "ModelsAccess>ModelsAccess] --> Models[(Models)]\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+}
@@ -0,0 +1,142 @@
+use serde_json::Value;
+
+use crate::ast::ast::{referenced_resource};
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Resource, ResourceType};
+use crate::cloudformation::template::Template;
+
+pub(crate) fn target_edges(rule: &Resource, template: &Template) -> Vec<Edge> {
+  let Property::EventRule { targets } = &*rule.properties else {
+    return Vec::new();
+  };
+
+  let mut edges = Vec::new();
+  for target in targets {
+    let Some(resource) = target.get("Arn").and_then(|arn| referenced_resource(arn, template)) else {
+      continue;
+    };
+    if !resource.typ.is_supported() {
+      continue;
+    }
+
+    let mut edge = Edge::with(Node::from(rule), Node::from(resource), Relationship::Routes);
+    edge.detail = input_summary(target).map(|summary| format!("({})", summary));
+    edges.push(edge);
+    edges.extend(batch_job(resource, target, template));
+  }
+  edges
+}
+
+// A Batch job queue target submits a job of the definition in its
+// `BatchParameters`, which the queue then runs.
+fn batch_job(queue: &Resource, target: &Value, template: &Template) -> Option<Edge> {
+  let definition = referenced_resource(target.pointer("/BatchParameters/JobDefinition")?, template)?;
+  if queue.typ != ResourceType::BatchJobQueue || definition.typ != ResourceType::BatchJobDefinition {
+    return None;
+  }
+
+  let mut edge = Edge::with(Node::from(queue), Node::from(definition), Relationship::Invokes);
+  edge.detail = target.pointer("/BatchParameters/JobName").and_then(Value::as_str).map(|name| format!("({})", name));
+  Some(edge)
+}
+
+// Only the shape of what reaches the target is shown: the path taken from the
+// event, or the top-level keys of a constant input.
+fn input_summary(target: &Value) -> Option<String> {
+  if let Some(path) = target.get("InputPath").and_then(Value::as_str) {
+    return Some(path.to_string());
+  }
+  if target.get("InputTransformer").is_some() {
+    return Some("input transformer".to_string());
+  }
+
+  let input = target.get("Input")?.as_str()?;
+  match serde_json::from_str::<Value>(input) {
+    Ok(Value::Object(map)) if !map.is_empty() => {
+      Some(format!("input: {}", map.keys().cloned().collect::<Vec<String>>().join(", ")))
+    }
+    _ => Some("constant input".to_string()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::ast::AST;
+
+  use super::*;
+
+  #[test]
+  fn test_rule_targets() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Nightly": {
+          "Type": "AWS::Events::Rule",
+          "Properties": {
+            "ScheduleExpression": "rate(1 day)",
+            "Targets": [
+              { "Id": "worker", "Arn": { "Fn::GetAtt": ["Worker", "Arn"] }, "Input": "{\"mode\":\"full\",\"dryRun\":false}" },
+              { "Id": "queue", "Arn": { "Fn::Sub": "${Queue.Arn}" }, "InputPath": "$.detail" },
+              { "Id": "flow", "Arn": { "Ref": "Flow" } },
+              { "Id": "external", "Arn": "arn:aws:sns:eu-west-2:123:alerts" }
+            ]
+          }
+        },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Flow": { "Type": "AWS::StepFunctions::StateMachine", "Properties": { "DefinitionString": "{}" } }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Nightly[/Nightly\\] -.->|routes| Flow(Flow)\n",
+      "Nightly[/Nightly\\] -.->|routes ($.detail)| Queue((\"jobs\"))\n",
+      "Nightly[/Nightly\\] -.->|routes (input: mode, dryRun)| Worker([\"worker\"])\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_batch_job_targets() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Nightly": {
+          "Type": "AWS::Events::Rule",
+          "Properties": {
+            "ScheduleExpression": "cron(0 2 * * ? *)",
+            "Targets": [{
+              "Id": "report",
+              "Arn": { "Ref": "Reports" },
+              "RoleArn": { "Fn::GetAtt": ["EventsRole", "Arn"] },
+              "BatchParameters": { "JobDefinition": { "Ref": "Report" }, "JobName": "nightly-report" }
+            }]
+          }
+        },
+        "Reports": {
+          "Type": "AWS::Batch::JobQueue",
+          "Properties": { "JobQueueName": "reports", "Priority": 1, "ComputeEnvironmentOrder": [{ "Order": 1, "ComputeEnvironment": { "Ref": "Spot" } }] }
+        },
+        "Report": { "Type": "AWS::Batch::JobDefinition", "Properties": { "Type": "container" } },
+        "Spot": { "Type": "AWS::Batch::ComputeEnvironment", "Properties": { "Type": "MANAGED", "ComputeEnvironmentName": "spot" } }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Nightly[/Nightly\\] -.->|routes| Reports((\"reports\"))\n",
+      "Reports((\"reports\")) -->|invokes (nightly-report)| Report(Report)\n",
+      "Reports((\"reports\")) --> Spot[[\"spot\"]]\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+}
@@ -0,0 +1,119 @@
+use serde_json::Value;
+
+use crate::ast::ast::literal_node;
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::arn::Arn;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Resource, ResourceType};
+use crate::cloudformation::template::Template;
+
+// Destinations given by `Ref` or `Fn::GetAtt` are linked like any other
+// reference. Literal ones are matched to the cluster, workgroup or bucket
+// created with that name, or drawn as external nodes: Redshift by the host of
+// its JDBC URL, whose first label is the cluster or workgroup name, and S3 by
+// its bucket ARN.
+pub(crate) fn destination_edges(stream: &Resource, template: &Template) -> Vec<Edge> {
+  let Property::Other(properties) = &*stream.properties else {
+    return Vec::new();
+  };
+  if stream.typ != ResourceType::Firehose {
+    return Vec::new();
+  }
+
+  let mut destinations = Vec::new();
+  collect_destinations(properties, &mut destinations);
+
+  destinations
+    .iter()
+    .map(|arn| Edge::with(Node::from(stream), literal_node(arn, template), Relationship::Writes))
+    .collect()
+}
+
+fn collect_destinations(value: &Value, destinations: &mut Vec<Arn>) {
+  let Value::Object(map) = value else {
+    return;
+  };
+
+  for (key, value) in map {
+    let destination = match (key.as_str(), value.as_str()) {
+      ("ClusterJDBCURL", Some(url)) => cluster(url),
+      ("BucketARN", Some(arn)) => Arn::parse(arn),
+      _ => {
+        collect_destinations(value, destinations);
+        None
+      }
+    };
+    if let Some(arn) = destination.filter(|arn| !destinations.contains(arn)) {
+      destinations.push(arn);
+    }
+  }
+}
+
+// `jdbc:redshift://name.id.region.redshift.amazonaws.com:5439/dev`
+fn cluster(url: &str) -> Option<Arn> {
+  let host = url.strip_prefix("jdbc:redshift://")?.split([':', '/']).next()?;
+  let name = host.split('.').next().filter(|name| !name.is_empty())?;
+
+  Some(Arn {
+    service: "redshift".to_string(),
+    region: String::new(),
+    account: String::new(),
+    resource: name.to_string(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::ast::AST;
+
+  use super::*;
+
+  #[test]
+  fn test_delivery_stream_destinations() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Warehouse": { "Type": "AWS::Redshift::Cluster", "Properties": { "ClusterIdentifier": "analytics", "NodeType": "ra3.xlplus" } },
+        "Serverless": { "Type": "AWS::RedshiftServerless::Workgroup", "Properties": { "WorkgroupName": "adhoc" } },
+        "Staging": { "Type": "AWS::S3::Bucket", "Properties": {} },
+        "Loader": {
+          "Type": "AWS::KinesisFirehose::DeliveryStream",
+          "Properties": {
+            "DeliveryStreamName": "clicks",
+            "RedshiftDestinationConfiguration": {
+              "ClusterJDBCURL": "jdbc:redshift://analytics.abc123.eu-west-2.redshift.amazonaws.com:5439/dev",
+              "S3Configuration": { "BucketARN": { "Fn::GetAtt": ["Staging", "Arn"] } }
+            }
+          }
+        },
+        "Archive": {
+          "Type": "AWS::KinesisFirehose::DeliveryStream",
+          "Properties": {
+            "ExtendedS3DestinationConfiguration": { "BucketARN": "arn:aws:s3:::cold-archive" }
+          }
+        },
+        "Report": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": {
+            "FunctionName": "report",
+            "Environment": { "Variables": { "WORKGROUP": { "Ref": "Serverless" } } }
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Archive[/Archive/] -->|writes| cold_archive[\"cold-archive\"]\n",
+      "Loader[/\"clicks\"/] -->|writes| Staging[(Staging)]\n",
+      "Loader[/\"clicks\"/] -->|writes| Warehouse[(\"analytics\")]\n",
+      "Report([\"report\"]) -->|configures| Serverless[(\"adhoc\")]\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+}
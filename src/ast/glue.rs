@@ -0,0 +1,163 @@
+use serde_json::Value;
+
+use crate::ast::ast::{literal_node, referenced_resource};
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::arn::Arn;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Resource, ResourceType};
+use crate::cloudformation::template::Template;
+
+pub(crate) fn edges(resource: &Resource, template: &Template) -> Vec<Edge> {
+  let Property::Other(properties) = &*resource.properties else {
+    return Vec::new();
+  };
+
+  match resource.typ {
+    ResourceType::GlueJob | ResourceType::GlueCrawler => bucket_edges(resource, properties, template),
+    ResourceType::GlueTrigger => trigger_edges(resource, properties, template),
+    _ => Vec::new(),
+  }
+}
+
+// Script locations, crawler targets and job arguments are mostly written as
+// literal `s3://` paths. Each bucket is matched to the one created with that
+// name, or drawn as an external node.
+fn bucket_edges(resource: &Resource, properties: &Value, template: &Template) -> Vec<Edge> {
+  let mut buckets = Vec::new();
+  collect_buckets(properties, &mut buckets);
+
+  buckets
+    .into_iter()
+    .map(|bucket| {
+      let arn = Arn {
+        service: "s3".to_string(),
+        region: String::new(),
+        account: String::new(),
+        resource: bucket,
+      };
+      Edge::new(Node::from(resource), literal_node(&arn, template))
+    })
+    .collect()
+}
+
+fn collect_buckets(value: &Value, buckets: &mut Vec<String>) {
+  match value {
+    Value::String(path) => {
+      let Some(bucket) = path.strip_prefix("s3://").and_then(|rest| rest.split('/').next()) else {
+        return;
+      };
+      // `${Bucket}` placeholders are references, and already linked.
+      if !bucket.is_empty() && !bucket.contains("${") && !buckets.iter().any(|b| b == bucket) {
+        buckets.push(bucket.to_string());
+      }
+    }
+    Value::Object(map) => map.values().for_each(|value| collect_buckets(value, buckets)),
+    Value::Array(items) => items.iter().for_each(|item| collect_buckets(item, buckets)),
+    _ => {}
+  }
+}
+
+// A trigger starts the jobs and crawlers in its `Actions`, and a conditional
+// one waits on those in its `Predicate`, labelled with the state it waits for.
+fn trigger_edges(trigger: &Resource, properties: &Value, template: &Template) -> Vec<Edge> {
+  let mut edges = Vec::new();
+
+  for action in properties.get("Actions").and_then(Value::as_array).into_iter().flatten() {
+    if let Some(target) = run(action, template) {
+      edges.push(Edge::with(Node::from(trigger), target, Relationship::Triggers));
+    }
+  }
+
+  let conditions = properties.pointer("/Predicate/Conditions").and_then(Value::as_array);
+  for condition in conditions.into_iter().flatten() {
+    if let Some(source) = run(condition, template) {
+      let mut edge = Edge::with(source, Node::from(trigger), Relationship::Triggers);
+      edge.detail = ["State", "CrawlState"]
+        .iter()
+        .find_map(|key| condition.get(*key)?.as_str())
+        .map(|state| format!("({})", state));
+      edges.push(edge);
+    }
+  }
+
+  edges
+}
+
+// The job or crawler an action or condition names, by `Ref` or by the name it
+// was created with.
+fn run(entry: &Value, template: &Template) -> Option<Node> {
+  let (name, typ) = match (entry.get("JobName"), entry.get("CrawlerName")) {
+    (Some(name), _) => (name, ResourceType::GlueJob),
+    (None, Some(name)) => (name, ResourceType::GlueCrawler),
+    _ => return None,
+  };
+
+  if let Some(resource) = referenced_resource(name, template) {
+    return Some(Node::from(resource));
+  }
+
+  let name = name.as_str()?;
+  template
+    .resources
+    .iter()
+    .map(Node::from)
+    .find(|node| node.typ == typ && node.get_name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::ast::AST;
+
+  use super::*;
+
+  #[test]
+  fn test_glue_pipeline() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Raw": { "Type": "AWS::S3::Bucket", "Properties": { "BucketName": "raw-data" } },
+        "Catalog": { "Type": "AWS::Glue::Database", "Properties": { "CatalogId": { "Ref": "AWS::AccountId" }, "DatabaseInput": { "Name": "sales" } } },
+        "Crawler": {
+          "Type": "AWS::Glue::Crawler",
+          "Properties": {
+            "Name": "raw-crawler",
+            "DatabaseName": { "Ref": "Catalog" },
+            "Targets": { "S3Targets": [{ "Path": "s3://raw-data/orders/" }] }
+          }
+        },
+        "Etl": {
+          "Type": "AWS::Glue::Job",
+          "Properties": {
+            "Name": "etl",
+            "Command": { "Name": "glueetl", "ScriptLocation": "s3://scripts-bucket/etl.py" },
+            "DefaultArguments": { "--output": { "Fn::Sub": "s3://${Raw}/clean/" } }
+          }
+        },
+        "AfterCrawl": {
+          "Type": "AWS::Glue::Trigger",
+          "Properties": {
+            "Type": "CONDITIONAL",
+            "Actions": [{ "JobName": { "Ref": "Etl" } }],
+            "Predicate": { "Conditions": [{ "CrawlerName": "raw-crawler", "CrawlState": "SUCCEEDED", "LogicalOperator": "EQUALS" }] }
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "AfterCrawl[/AfterCrawl\\] -.->|triggers| Etl([\"etl\"])\n",
+      "Crawler(\"raw-crawler\") -.->|triggers (SUCCEEDED)| AfterCrawl[/AfterCrawl\\]\n",
+      "Crawler(\"raw-crawler\") -->|writes| Catalog[(\"sales\")]\n",
+      "Crawler(\"raw-crawler\") -->|reads| Raw[(\"raw-data\")]\n",
+      "Etl([\"etl\"]) --> Raw[(\"raw-data\")]\n",
+      "Etl([\"etl\"]) --> scripts_bucket[\"scripts-bucket\"]\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use petgraph::Direction;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+
+use crate::ast::ast::AST;
+use crate::ast::edge::Edge;
+use crate::ast::node::Node;
+
+// A view of the diagram for petgraph's algorithms. Node indices follow
+// `AST::nodes()` and edge indices the stored order, same-stack edges first, so
+// results come out in the order the diagram is drawn.
+pub type Graph<'a> = DiGraph<&'a Node, &'a Edge>;
+
+// The graph together with where each node sits in it, so that a query builds
+// it once and looks resources up without a scan.
+pub struct Indexed<'a> {
+  pub graph: Graph<'a>,
+  indices: HashMap<&'a Node, NodeIndex>,
+}
+
+impl AST {
+  pub fn graph(&self) -> Graph<'_> {
+    self.indexed().graph
+  }
+
+  // Properties kept as JSON text are parsed on first use, which clippy takes
+  // for a key that can change; the hash only ever depends on the text.
+  #[allow(clippy::mutable_key_type)]
+  pub fn indexed(&self) -> Indexed<'_> {
+    let nodes = self.nodes();
+    let edges: Vec<&Edge> = self.edges.iter().chain(self.cross_stack_edges.iter()).collect();
+    let mut graph = Graph::with_capacity(nodes.len(), edges.len());
+    let mut indices = HashMap::with_capacity(nodes.len());
+
+    for node in nodes {
+      indices.insert(node, graph.add_node(node));
+    }
+    for edge in edges {
+      let (Some(&from), Some(&to)) = (indices.get(&edge.from), indices.get(&edge.to)) else {
+        continue;
+      };
+      graph.add_edge(from, to, edge);
+    }
+
+    Indexed { graph, indices }
+  }
+}
+
+impl Indexed<'_> {
+  pub fn index_of(&self, node: &Node) -> Option<NodeIndex> {
+    self.indices.get(node).copied()
+  }
+}
+
+// petgraph walks a node's edges newest first; these come back in stored order
+// so traversals stay in diagram order.
+pub(crate) fn neighbours(graph: &Graph, node: NodeIndex, direction: Direction) -> Vec<NodeIndex> {
+  let mut edges: Vec<_> = graph
+    .edges_directed(node, direction)
+    .map(|edge| match direction {
+      Direction::Outgoing => (edge.id(), edge.target()),
+      Direction::Incoming => (edge.id(), edge.source()),
+    })
+    .collect();
+  edges.sort();
+  edges.into_iter().map(|(_, neighbour)| neighbour).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_graph_follows_diagram_order() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Jobs": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Orders": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "orders" } },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": {
+            "FunctionName": "worker",
+            "Architectures": ["arm64"],
+            "Environment": { "Variables": { "QUEUE": { "Ref": "Jobs" }, "TABLE": { "Ref": "Orders" } } }
+          }
+        }
+      }
+    }))
+    .unwrap();
+    let ast = AST::from(template);
+    let indexed = ast.indexed();
+    let graph = &indexed.graph;
+
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(graph.edge_count(), 2);
+
+    let worker = indexed.index_of(ast.find("worker").unwrap()).unwrap();
+    let names: Vec<&str> = neighbours(graph, worker, Direction::Outgoing)
+      .into_iter()
+      .map(|index| &*graph[index].name.0)
+      .collect();
+    let expected: Vec<&str> = ast.edges.iter().map(|edge| &*edge.to.name.0).collect();
+    assert_eq!(names, expected);
+    assert!(neighbours(graph, worker, Direction::Incoming).is_empty());
+  }
+}
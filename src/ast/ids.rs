@@ -0,0 +1,125 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::ast::AST;
+use crate::ast::node::{Node, sanitize_id};
+
+// The IDs nodes are drawn with, unique across the whole diagram. Logical IDs
+// are only unique within their own template, and sanitizing can turn two names
+// into one (`/app/queue-url` and `/app/queue_url`), so an ID shared by more
+// than one node is qualified with the stack or subgraph each is drawn in, and
+// any that still clash are numbered in drawing order.
+pub struct Ids<'a>(HashMap<&'a Node, String>);
+
+impl AST {
+  pub fn ids(&self) -> Ids<'_> {
+    Ids::new(self.all_nodes(), |node| self.group_of(node))
+  }
+}
+
+impl<'a> Ids<'a> {
+  // Properties kept as JSON text are parsed on first use, which clippy takes
+  // for a key that can change; the hash only ever depends on the text.
+  #[allow(clippy::mutable_key_type)]
+  pub fn new(nodes: Vec<&'a Node>, group: impl Fn(&Node) -> Option<&'a str>) -> Self {
+    let mut counts: HashMap<Cow<str>, usize> = HashMap::new();
+    for node in &nodes {
+      *counts.entry(node.id()).or_default() += 1;
+    }
+
+    let mut taken = HashSet::new();
+    let mut ids = HashMap::new();
+    for node in nodes {
+      if ids.contains_key(node) {
+        continue;
+      }
+
+      let id = match group(node) {
+        Some(group) if counts[&node.id()] > 1 => format!("{}_{}", sanitize_id(group), node.id()),
+        _ => node.id().into_owned(),
+      };
+      let mut unique = id.clone();
+      for suffix in 2.. {
+        if !taken.contains(&unique) {
+          break;
+        }
+        unique = format!("{}_{}", id, suffix);
+      }
+
+      taken.insert(unique.clone());
+      ids.insert(node, unique);
+    }
+
+    Ids(ids)
+  }
+
+  // A node from outside the diagram keeps the ID it would have on its own.
+  pub fn of<'b>(&'b self, node: &'b Node) -> Cow<'b, str> {
+    match self.0.get(node) {
+      Some(id) => Cow::Borrowed(id),
+      None => node.id(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ast::edge::Edge;
+  use crate::ast::subgraph::Subgraph;
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::{Name, ResourceType};
+
+  use super::*;
+
+  fn queue(name: &str, queue_name: &str) -> Node {
+    Node {
+      name: Name::from(name),
+      typ: ResourceType::Sqs,
+      properties: Property::Sqs {
+        queue_name: queue_name.into(),
+      }.into(),
+    }
+  }
+
+  #[test]
+  fn test_ids_are_qualified_by_stack() {
+    let (a, b) = (queue("Jobs", "a-jobs"), queue("Jobs", "b-jobs"));
+    let ast = AST {
+      subgraphs: vec![
+        Subgraph {
+          name: "stack-a".to_string(),
+          nodes: vec![a.clone()],
+          ..Default::default()
+        },
+        Subgraph {
+          name: "stack-b".to_string(),
+          nodes: vec![b.clone()],
+          ..Default::default()
+        },
+      ],
+      ..Default::default()
+    };
+    let ids = ast.ids();
+
+    assert_eq!(ids.of(&a), "stack_a_Jobs");
+    assert_eq!(ids.of(&b), "stack_b_Jobs");
+    assert_eq!(
+      ast.to_flowchart(),
+      "flowchart LR\nsubgraph stack_a[stack-a]\nstack_a_Jobs((\"a-jobs\"))\nend\nsubgraph stack_b[stack-b]\nstack_b_Jobs((\"b-jobs\"))\nend\n"
+    );
+  }
+
+  #[test]
+  fn test_sanitized_ids_are_numbered_apart() {
+    let (dash, underscore, other) = (queue("/app/queue-url", "dash"), queue("/app/queue_url", "underscore"), queue("Other", "other"));
+    let ast = AST {
+      edges: vec![Edge::new(dash.clone(), other.clone()), Edge::new(underscore.clone(), other.clone())],
+      ..Default::default()
+    };
+    let ids = ast.ids();
+
+    assert_eq!(ids.of(&dash), "_app_queue_url");
+    assert_eq!(ids.of(&underscore), "_app_queue_url_2");
+    assert_eq!(ids.of(&other), "Other");
+  }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::ast::ast::{extract_ref_from_getatt, extract_ref_from_ref, referenced_resource};
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::intrinsic::resolve_string;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Resource, ResourceType};
+use crate::cloudformation::stack::Stack;
+
+/// The edges between stacks: every `Fn::ImportValue` matched against the
+/// `Outputs` another stack exports under that name.
+pub(crate) fn edges(stacks: &[Stack], keep: &(dyn Fn(&Node) -> bool + Sync)) -> Vec<Edge> {
+  let exports = collect_exports(stacks);
+  let mut cross_stack_edges = Vec::new();
+
+  for stack in stacks {
+    for resource in &stack.template.resources {
+      if !resource.typ.is_supported() || !keep(&Node::from(resource)) {
+        continue;
+      }
+
+      for import in find_imports(resource, &stack.name) {
+        let producer = match exports.get(&import) {
+          Some(producer) if producer.typ.is_supported() && keep(&Node::from(producer)) => Node::from(producer),
+          _ => continue,
+        };
+
+        match resource.typ {
+          ResourceType::EventSourceMapping => {
+            if let Some(target) = event_source_mapping_target(resource, stack).filter(|target| keep(target)) {
+              cross_stack_edges.push(Edge::new(producer, target));
+            }
+          }
+          ResourceType::Lambda => {
+            cross_stack_edges.push(Edge::with(Node::from(resource), producer, Relationship::Configures))
+          }
+          _ => cross_stack_edges.push(Edge::new(Node::from(resource), producer)),
+        }
+      }
+    }
+  }
+
+  cross_stack_edges
+}
+
+fn collect_exports(stacks: &[Stack]) -> HashMap<String, &Resource> {
+  let mut exports = HashMap::new();
+
+  for stack in stacks {
+    for output in stack.template.outputs.values() {
+      let Some(export_name) = output.export_name(&stack.name) else {
+        continue;
+      };
+      let Some(logical_id) = extract_ref_from_ref(&output.value).or_else(|| extract_ref_from_getatt(&output.value)) else {
+        continue;
+      };
+
+      if let Some(resource) = stack.template.resources.iter().find(|r| *r.name.0 == logical_id) {
+        exports.insert(export_name, resource);
+      }
+    }
+  }
+
+  exports
+}
+
+fn find_imports(resource: &Resource, stack_name: &str) -> Vec<String> {
+  let mut imports = Vec::new();
+
+  match &*resource.properties {
+    Property::Other(properties) => collect_imports(properties, stack_name, &mut imports),
+    Property::ApiGateway { integration, .. } => collect_imports(integration, stack_name, &mut imports),
+    Property::Lambda { environment, .. } => collect_imports(environment, stack_name, &mut imports),
+    Property::EventSourceMapping { event_source_arn, .. } => {
+      collect_imports(event_source_arn, stack_name, &mut imports)
+    }
+    _ => {}
+  }
+
+  imports
+}
+
+fn collect_imports(value: &Value, stack_name: &str, imports: &mut Vec<String>) {
+  match value {
+    Value::Object(map) => {
+      for (key, value) in map {
+        match key.as_str() {
+          "Fn::ImportValue" => imports.extend(resolve_string(value, stack_name)),
+          _ => collect_imports(value, stack_name, imports),
+        }
+      }
+    }
+    Value::Array(items) => {
+      for item in items {
+        collect_imports(item, stack_name, imports);
+      }
+    }
+    _ => {}
+  }
+}
+
+fn event_source_mapping_target(resource: &Resource, stack: &Stack) -> Option<Node> {
+  let Property::EventSourceMapping { function_name, .. } = &*resource.properties else {
+    return None;
+  };
+  referenced_resource(function_name, &stack.template).map(Node::from)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ast::ast::AST;
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_cross_stack_event_source_mapping() {
+    let producer: Template = serde_json::from_str(
+      r#"{
+        "Resources": {
+          "MyQueue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "my-queue" } }
+        },
+        "Outputs": {
+          "ExportsOutputFnGetAttMyQueueArn": {
+            "Value": { "Fn::GetAtt": ["MyQueue", "Arn"] },
+            "Export": { "Name": { "Fn::Sub": "${AWS::StackName}:MyQueueArn" } }
+          }
+        }
+      }"#,
+    )
+    .unwrap();
+    let consumer: Template = serde_json::from_str(
+      r#"{
+        "Resources": {
+          "MyLambda": {
+            "Type": "AWS::Lambda::Function",
+            "Properties": { "FunctionName": "my-lambda", "Architectures": ["arm64"] }
+          },
+          "MyMapping": {
+            "Type": "AWS::Lambda::EventSourceMapping",
+            "Properties": {
+              "EventSourceArn": { "Fn::ImportValue": "Producer:MyQueueArn" },
+              "FunctionName": { "Ref": "MyLambda" }
+            }
+          }
+        }
+      }"#,
+    )
+    .unwrap();
+
+    let ast = AST::from(vec![
+      Stack {
+        name: "Producer".to_string(),
+        template: producer,
+      },
+      Stack {
+        name: "Consumer".to_string(),
+        template: consumer,
+      },
+    ]);
+
+    let expected_output = "```mermaid\nflowchart LR\nsubgraph Producer\nMyQueue((\"my-queue\"))\nend\nsubgraph Consumer\nMyLambda([\"my-lambda\"])\nend\nMyQueue((\"my-queue\")) ==>|triggers| MyLambda([\"my-lambda\"])\n```";
+
+    assert_eq!(ast.to_mermaid(), expected_output);
+  }
+}
@@ -0,0 +1,116 @@
+use serde_json::Value;
+
+use crate::ast::ast::target_node;
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Resource, ResourceType};
+use crate::cloudformation::template::Template;
+
+// The field naming each kind of action's target, and the service a literal
+// name in it belongs to. Every action also carries a `RoleArn`, which is not
+// where the message goes.
+const ACTIONS: [(&str, &str, &str); 5] = [
+  ("Lambda", "FunctionArn", "lambda"),
+  ("Sqs", "QueueUrl", "sqs"),
+  ("Kinesis", "StreamName", "kinesis"),
+  ("S3", "BucketName", "s3"),
+  ("Firehose", "DeliveryStreamName", "firehose"),
+];
+
+// A topic rule routes the messages selected by its SQL to each of its actions,
+// and to its error action when one of those fails. Edges are labelled with
+// the topic filter the rule selects from.
+pub(crate) fn action_edges(rule: &Resource, template: &Template) -> Vec<Edge> {
+  let Property::Other(properties) = &*rule.properties else {
+    return Vec::new();
+  };
+  if rule.typ != ResourceType::IotRule {
+    return Vec::new();
+  }
+  let Some(payload) = properties.get("TopicRulePayload") else {
+    return Vec::new();
+  };
+
+  let topic = payload.get("Sql").and_then(Value::as_str).and_then(topic);
+  let mut actions: Vec<(&Value, Option<String>)> = payload
+    .get("Actions")
+    .and_then(Value::as_array)
+    .into_iter()
+    .flatten()
+    .map(|action| (action, topic.clone()))
+    .collect();
+  actions.extend(payload.get("ErrorAction").map(|action| (action, Some("error".to_string()))));
+
+  let mut edges = Vec::new();
+  for (action, detail) in actions {
+    for target in targets(action, template) {
+      let mut edge = Edge::with(Node::from(rule), target, Relationship::Routes);
+      edge.detail = detail.as_ref().map(|detail| format!("({})", detail));
+      edges.push(edge);
+    }
+  }
+  edges
+}
+
+fn targets(action: &Value, template: &Template) -> Vec<Node> {
+  ACTIONS
+    .iter()
+    .filter_map(|(kind, field, service)| target_node(action.get(*kind)?.get(*field)?, service, template))
+    .collect()
+}
+
+// `SELECT temp FROM 'sensors/+/telemetry' WHERE temp > 50`
+fn topic(sql: &str) -> Option<String> {
+  let from = sql.to_ascii_uppercase().find(" FROM ")?;
+  let rest = sql[from + " FROM ".len()..].trim_start().strip_prefix('\'')?;
+  rest.split_once('\'').map(|(topic, _)| topic.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::ast::AST;
+
+  use super::*;
+
+  #[test]
+  fn test_topic_rule_actions() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Telemetry": {
+          "Type": "AWS::IoT::TopicRule",
+          "Properties": {
+            "RuleName": "telemetry",
+            "TopicRulePayload": {
+              "Sql": "SELECT temp FROM 'sensors/+/telemetry' WHERE temp > 50",
+              "Actions": [
+                { "Lambda": { "FunctionArn": { "Fn::GetAtt": ["Alert", "Arn"] } } },
+                { "Kinesis": { "StreamName": { "Ref": "Readings" }, "RoleArn": { "Fn::GetAtt": ["IotRole", "Arn"] } } },
+                { "S3": { "BucketName": "raw-telemetry", "Key": "${topic()}/${timestamp()}", "RoleArn": { "Fn::GetAtt": ["IotRole", "Arn"] } } }
+              ],
+              "ErrorAction": { "Sqs": { "QueueUrl": "https://sqs.eu-west-2.amazonaws.com/123/telemetry-errors", "RoleArn": { "Fn::GetAtt": ["IotRole", "Arn"] } } }
+            }
+          }
+        },
+        "Alert": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "alert", "Architectures": ["arm64"] } },
+        "Readings": { "Type": "AWS::Kinesis::Stream", "Properties": { "Name": "readings", "ShardCount": 1 } },
+        "Dead": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "telemetry-errors" } },
+        "IotRole": { "Type": "AWS::IAM::Role", "Properties": {} }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Telemetry[/\"telemetry\"\\] -.->|routes (sensors/+/telemetry)| Alert([\"alert\"])\n",
+      "Telemetry[/\"telemetry\"\\] -.->|routes (error)| Dead((\"telemetry-errors\"))\n",
+      "Telemetry[/\"telemetry\"\\] -.->|routes (sensors/+/telemetry)| Readings((\"readings\"))\n",
+      "Telemetry[/\"telemetry\"\\] -.->|routes (sensors/+/telemetry)| raw_telemetry[\"raw-telemetry\"]\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+}
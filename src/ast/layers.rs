@@ -0,0 +1,85 @@
+use petgraph::visit::EdgeRef;
+
+use crate::ast::ast::AST;
+use crate::ast::subgraph::Subgraph;
+
+// A node's layer is the length of the longest edge path leading into it, so
+// entry points (APIs, schedules) sit in the first column and the stores they
+// eventually write to in the last. Edges inside a cycle stop being followed
+// once every node has been relaxed, which keeps loops from growing forever.
+pub fn group(ast: &mut AST) {
+  let graph = ast.graph();
+  let count = graph.node_count();
+  let mut depth = vec![0; count];
+
+  for _ in 0..count {
+    let mut changed = false;
+
+    for edge in graph.edge_references() {
+      let (from, to) = (edge.source().index(), edge.target().index());
+      if from != to && depth[to] < depth[from] + 1 && depth[from] + 1 < count {
+        depth[to] = depth[from] + 1;
+        changed = true;
+      }
+    }
+
+    if !changed {
+      break;
+    }
+  }
+
+  let layers = depth.iter().max().map_or(0, |max| max + 1);
+  let subgraphs = (0..layers)
+    .map(|layer| Subgraph {
+      name: format!("Layer {}", layer + 1),
+      nodes: graph
+        .node_indices()
+        .filter(|index| depth[index.index()] == layer)
+        .map(|index| graph[index].clone())
+        .collect(),
+      direction: Some("TB".to_string()),
+      ..Default::default()
+    })
+    .collect();
+
+  ast.subgraphs = subgraphs;
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_layers_follow_longest_path() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Api": { "Type": "AWS::ApiGateway::Method", "Properties": { "HttpMethod": "GET", "Integration": { "Uri": { "Ref": "Worker" } } } },
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } }
+      }
+    }))
+    .unwrap();
+    let mut ast = AST::from(template);
+
+    group(&mut ast);
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "subgraph Layer_1[Layer 1]\ndirection TB\nApi[[Api]]\nQueue((\"jobs\"))\nend\n",
+      "subgraph Layer_2[Layer 2]\ndirection TB\nWorker([\"worker\"])\nend\n",
+      "Api[[Api]] -->|invokes| Worker([\"worker\"])\n",
+      "Queue((\"jobs\")) -.->|triggers| Worker([\"worker\"])\n",
+      "```",
+    );
+
+    assert_eq!(ast.to_mermaid(), expected_output);
+  }
+}
@@ -0,0 +1,105 @@
+use crate::ast::ast::{AST, canonical};
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::intrinsic::explicit_references;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Name, Resource, ResourceType};
+use crate::cloudformation::stack::Stack;
+use crate::cloudformation::template::Template;
+
+// Logical IDs a resource names through `Ref` or `Fn::GetAtt` that are neither
+// a resource nor a parameter of its template: a typo, or a resource removed by
+// hand. CloudFormation would reject the template, so nothing is drawn for them
+// unless asked.
+pub(crate) fn dangling(resource: &Resource, template: &Template) -> Vec<String> {
+  if template.is_sam() {
+    return Vec::new();
+  }
+
+  let mut names: Vec<String> = resource.properties.json().iter().flat_map(|json| explicit_references(json)).collect();
+  names.sort();
+  names.dedup();
+  names.retain(|name| !template.parameters.contains_key(name) && template.resource(name).is_none());
+  names
+}
+
+// Each dangling reference is drawn as a `missing` node, so the edge the
+// template meant to make is visible rather than silently left out.
+pub fn add(ast: &mut AST, stacks: &[Stack]) {
+  for stack in stacks {
+    for resource in stack.template.resources.iter().filter(|r| r.typ.is_supported()) {
+      for name in dangling(resource, &stack.template) {
+        let missing = Node {
+          name: Name::from(name),
+          typ: ResourceType::Missing,
+          properties: Property::default().into(),
+        };
+        let mut edge = Edge::with(Node::from(resource), missing, Relationship::References);
+        edge.detail = Some("missing".to_string());
+        ast.edges.push(edge);
+      }
+    }
+  }
+
+  ast.edges = canonical(std::mem::take(&mut ast.edges));
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::render::{Format, RenderOptions};
+
+  #[test]
+  fn test_dangling_references() {
+    let template = |transform: Option<&str>| -> Template {
+      serde_json::from_value(json!({
+        "Transform": transform,
+        "Parameters": { "Stage": { "Type": "String" } },
+        "Resources": {
+          "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+          "Worker": {
+            "Type": "AWS::Lambda::Function",
+            "Properties": {
+              "FunctionName": "worker",
+              "Role": { "Fn::GetAtt": ["WorkerRol", "Arn"] },
+              "Environment": {
+                "Variables": {
+                  "QUEUE": { "Ref": "Queue" },
+                  "STAGE": { "Ref": "Stage" },
+                  "TABLE": { "Ref": "Tabel" },
+                  "URL": { "Fn::Sub": "https://${stageVariables.host}/${AWS::Region}" }
+                }
+              }
+            }
+          }
+        }
+      }))
+      .unwrap()
+    };
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template: template(None),
+    }];
+
+    assert_eq!(dangling(&stacks[0].template.resources[1], &stacks[0].template), ["Tabel", "WorkerRol"]);
+    let sam = template(Some("AWS::Serverless-2016-10-31"));
+    assert!(dangling(&sam.resources[1], &sam).is_empty());
+
+    let mut ast = AST::from(stacks[0].template.clone());
+    add(&mut ast, &stacks);
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Worker([\"worker\"]) -->|configures| Queue((\"jobs\"))\n",
+      "Worker([\"worker\"]) -->|missing| Tabel[Tabel]\n",
+      "Worker([\"worker\"]) -->|missing| WorkerRol[WorkerRol]\n",
+      "```",
+    );
+    assert_eq!(ast.to_mermaid(), expected_output);
+
+    let rendered = Format::Mermaid.renderer(RenderOptions::default()).render(&ast);
+    assert!(rendered.contains("class Tabel,WorkerRol missing\n"));
+  }
+}
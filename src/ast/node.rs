@@ -1,39 +1,262 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
 use crate::cloudformation::property::Property;
 use crate::cloudformation::resource::{Name, Resource, ResourceType};
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize)]
 pub struct Node {
   pub name: Name,
   pub typ: ResourceType,
-  pub properties: Property,
+  // Shared with the resource the node was made from, since every edge holds
+  // its own copy of both nodes.
+  pub properties: Arc<Property>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Shape {
+  Stadium,
+  Circle,
+  Subroutine,
+  Rhombus,
+  Cylinder,
+  Trapezoid,
+  Rounded,
+  Hexagon,
+  Parallelogram,
+  Asymmetric,
+  Rectangle,
+}
+
+pub const DEFAULT_LABEL: &str = "{physical_name}";
+
+/// The common choices of node text, as shorthands for a label format. CDK
+/// logical IDs carry hashes, while physical names may be unresolved tokens.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Label {
+  LogicalId,
+  PhysicalName,
+  /// The physical name above the logical ID.
+  Both,
+}
+
+impl Label {
+  pub fn format(&self) -> &'static str {
+    match self {
+      Label::LogicalId => "{logical_id}",
+      Label::PhysicalName => DEFAULT_LABEL,
+      Label::Both => "{physical_name}\\n{logical_id}",
+    }
+  }
+}
+
+// Parameter paths, ARNs, stack and construct names carry slashes, dashes,
+// dots, colons and spaces, none of which mermaid accepts in an ID; the
+// original text is kept as a quoted label instead. `end` is a mermaid keyword.
+pub fn sanitize_id(name: &str) -> String {
+  let id: String = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+
+  match id.as_str() {
+    "" => "_".to_string(),
+    id if id.eq_ignore_ascii_case("end") => format!("{}_", id),
+    _ => id,
+  }
+}
+
+// The first word of a logical ID, which hand-written templates use to group
+// resources: `OrdersTable` is `Orders`. An ID of one word has no prefix.
+pub(crate) fn logical_prefix(logical_id: &str) -> Option<&str> {
+  let end = logical_id.char_indices().skip(1).find(|(_, c)| c.is_ascii_uppercase() || c.is_ascii_digit())?.0;
+  Some(&logical_id[..end])
+}
+
+// Types kept as plain JSON still carry their physical name in a field of their
+// own, when it is given as text rather than built by an intrinsic.
+fn name_field<'a>(typ: &ResourceType, properties: &'a serde_json::Value) -> Option<&'a str> {
+  let name = match typ {
+    ResourceType::Bucket => properties.get("BucketName"),
+    ResourceType::GlueJob
+    | ResourceType::GlueCrawler
+    | ResourceType::GlueTrigger
+    | ResourceType::Pipeline
+    | ResourceType::CodeBuild
+    | ResourceType::Kinesis
+    | ResourceType::ApiKey => properties.get("Name"),
+    ResourceType::GlueDatabase => properties.get("DatabaseInput")?.get("Name"),
+    ResourceType::Firehose => properties.get("DeliveryStreamName"),
+    ResourceType::BatchJobQueue => properties.get("JobQueueName"),
+    ResourceType::BatchJobDefinition => properties.get("JobDefinitionName"),
+    ResourceType::BatchComputeEnvironment => properties.get("ComputeEnvironmentName"),
+    ResourceType::IotRule => properties.get("RuleName"),
+    ResourceType::Sns => properties.get("TopicName"),
+    ResourceType::SesReceiptRule => properties.get("Rule")?.get("Name"),
+    ResourceType::SesIdentity => properties.get("EmailIdentity"),
+    ResourceType::UsagePlan => properties.get("UsagePlanName"),
+    ResourceType::Redshift => properties.get("ClusterIdentifier").or(properties.get("WorkgroupName")),
+    _ => None,
+  };
+  name?.as_str()
 }
 
 impl std::fmt::Display for Node {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    match &self.typ {
-      ResourceType::Lambda => write!(f, "{}([{}])", &self.get_name(), &self.get_name()),
-      ResourceType::Sqs => write!(f, "{}(({}))", &self.get_name(), &self.get_name()),
-      ResourceType::ApiGateway => write!(f, "{}[[{}]]", &self.get_name(), &self.get_name()),
-      ResourceType::EventSourceMapping => write!(f, "{}{{{}||}}", &self.get_name(), &self.get_name()),
-      _ => write!(f, ""),
-    }
+    write!(f, "{}", self.to_mermaid(DEFAULT_LABEL))
   }
 }
 
 impl Node {
-  pub fn from(resource: Resource) -> Self {
+  pub fn from(resource: &Resource) -> Self {
     Node {
-      name: resource.name,
-      typ: resource.typ,
-      properties: resource.properties,
+      name: resource.name.clone(),
+      typ: resource.typ.clone(),
+      properties: resource.properties.clone(),
+    }
+  }
+
+  pub fn get_name(&self) -> &str {
+    match &*self.properties {
+      // Names are optional, and CDK usually leaves them to CloudFormation.
+      Property::Lambda { function_name: serde_json::Value::String(function_name), .. } => function_name,
+      Property::Sqs { queue_name: serde_json::Value::String(queue_name), .. } => queue_name,
+      Property::DynamoDb { table_name: serde_json::Value::String(table_name), .. } => table_name,
+      Property::Other(properties) => name_field(&self.typ, properties).unwrap_or(&self.name.0),
+      _ => &self.name.0,
+    }
+  }
+
+  // `\n` is accepted literally so templates can be passed straight from a shell.
+  // A resource without a physical name would show its logical ID twice when
+  // both are stacked, so repeated lines are shown once.
+  pub fn label(&self, format: &str) -> String {
+    let label = format
+      .replace("\\n", "\n")
+      .replace("{logical_id}", &self.name.0)
+      .replace("{physical_name}", self.get_name())
+      .replace("{type}", &self.typ.to_string());
+
+    let mut lines: Vec<&str> = label.split('\n').collect();
+    lines.dedup();
+    lines.join("\n")
+  }
+
+  // IDs come from the logical ID, which is unique within a template, while
+  // physical names may repeat or equal another resource's logical ID, and
+  // mermaid would merge the nodes. Most logical IDs are already valid IDs, and
+  // those are borrowed as they are.
+  pub fn id(&self) -> Cow<'_, str> {
+    let name: &str = &self.name.0;
+    let valid = !name.is_empty()
+      && !name.eq_ignore_ascii_case("end")
+      && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    match valid {
+      true => Cow::Borrowed(name),
+      false => Cow::Owned(sanitize_id(name)),
+    }
+  }
+
+  pub fn to_mermaid(&self, label_format: &str) -> String {
+    self.to_mermaid_as(&self.id(), label_format)
+  }
+
+  // Drawn under an ID given by the diagram, which keeps every node's ID unique.
+  pub fn to_mermaid_as(&self, id: &str, label_format: &str) -> String {
+    let label = match self.label(label_format) {
+      label if label == id => label,
+      label => format!("\"{}\"", label.replace('"', "#quot;").replace('\n', "<br/>")),
+    };
+
+    match self.shape() {
+      Some(Shape::Stadium) => format!("{}([{}])", id, label),
+      Some(Shape::Circle) => format!("{}(({}))", id, label),
+      Some(Shape::Subroutine) => format!("{}[[{}]]", id, label),
+      Some(Shape::Rhombus) => format!("{}{{{}||}}", id, label),
+      Some(Shape::Cylinder) => format!("{}[({})]", id, label),
+      Some(Shape::Trapezoid) => format!("{}[/{}\\]", id, label),
+      Some(Shape::Rounded) => format!("{}({})", id, label),
+      Some(Shape::Hexagon) => format!("{}{{{{{}}}}}", id, label),
+      Some(Shape::Parallelogram) => format!("{}[/{}/]", id, label),
+      Some(Shape::Asymmetric) => format!("{}>{}]", id, label),
+      Some(Shape::Rectangle) => format!("{}[{}]", id, label),
+      None => String::new(),
     }
   }
 
-  pub fn get_name(&self) -> String {
-    match &self.properties {
-      Property::Lambda { function_name, .. } => function_name.to_string(),
-      Property::Sqs { queue_name, .. } => queue_name.to_string(),
-      _ => self.name.0.clone(),
+  pub fn shape(&self) -> Option<Shape> {
+    match &self.typ {
+      ResourceType::Lambda => Some(Shape::Stadium),
+      ResourceType::Sqs | ResourceType::BatchJobQueue | ResourceType::Kinesis | ResourceType::Sns => Some(Shape::Circle),
+      ResourceType::ApiGateway | ResourceType::FunctionUrl => Some(Shape::Subroutine),
+      ResourceType::EventSourceMapping => Some(Shape::Rhombus),
+      ResourceType::DynamoDb => Some(Shape::Cylinder),
+      ResourceType::EventRule | ResourceType::IotRule | ResourceType::SesReceiptRule => Some(Shape::Trapezoid),
+      ResourceType::StateMachine => Some(Shape::Rounded),
+      ResourceType::Parameter => Some(Shape::Hexagon),
+      ResourceType::SsmParameter => Some(Shape::Parallelogram),
+      ResourceType::Secret => Some(Shape::Asymmetric),
+      ResourceType::Bucket | ResourceType::GlueDatabase | ResourceType::Redshift | ResourceType::FileSystem => Some(Shape::Cylinder),
+      ResourceType::GlueJob | ResourceType::CodeBuild => Some(Shape::Stadium),
+      ResourceType::GlueCrawler | ResourceType::BatchJobDefinition => Some(Shape::Rounded),
+      ResourceType::BatchComputeEnvironment => Some(Shape::Subroutine),
+      ResourceType::GlueTrigger | ResourceType::Pipeline => Some(Shape::Trapezoid),
+      ResourceType::Firehose | ResourceType::UsagePlan => Some(Shape::Parallelogram),
+      ResourceType::AccessPoint | ResourceType::SesIdentity | ResourceType::ApiKey => Some(Shape::Asymmetric),
+      ResourceType::External | ResourceType::Missing => Some(Shape::Rectangle),
+      ResourceType::Other => None,
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_label_format() {
+    let node = Node {
+      name: Name::from("MyQueue"),
+      typ: ResourceType::Sqs,
+      properties: Property::Sqs {
+        queue_name: "my-queue".into(),
+      }.into(),
+    };
+
+    assert_eq!(node.to_mermaid(DEFAULT_LABEL), "MyQueue((\"my-queue\"))");
+    assert_eq!(node.to_mermaid("{logical_id}\\n{type}"), "MyQueue((\"MyQueue<br/>sqs\"))");
+    assert!(matches!(node.id(), Cow::Borrowed("MyQueue")));
+    assert_eq!(node.label(Label::Both.format()), "my-queue\nMyQueue");
+
+    let external = Node {
+      name: Name::from("/app/queue-url"),
+      typ: ResourceType::SsmParameter,
+      properties: Property::default().into(),
+    };
+    assert!(matches!(external.id(), Cow::Owned(id) if id == "_app_queue_url"));
+    assert_eq!(external.label(Label::Both.format()), "/app/queue-url");
+  }
+
+  #[test]
+  fn test_ids_do_not_collide_on_physical_names() {
+    let queue = |name: &str, queue_name: &str| Node {
+      name: Name::from(name),
+      typ: ResourceType::Sqs,
+      properties: Property::Sqs {
+        queue_name: queue_name.into(),
+      }.into(),
+    };
+
+    let (jobs, retries, named_after) = (queue("Jobs", "work"), queue("Retries", "work"), queue("Work", "Jobs"));
+    assert_eq!(jobs.to_mermaid(DEFAULT_LABEL), "Jobs((\"work\"))");
+    assert_eq!(retries.to_mermaid(DEFAULT_LABEL), "Retries((\"work\"))");
+    assert_eq!(named_after.to_mermaid(DEFAULT_LABEL), "Work((\"Jobs\"))");
+  }
+
+  #[test]
+  fn test_sanitize_id() {
+    assert_eq!(sanitize_id("my-stack.queue:v1 copy"), "my_stack_queue_v1_copy");
+    assert_eq!(sanitize_id("${Token[TOKEN.123]}"), "__Token_TOKEN_123__");
+    assert_eq!(sanitize_id("End"), "End_");
+  }
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeSet;
+
+use crate::ast::ast::{AST, canonical};
+use crate::ast::edge::Edge;
+use crate::ast::node::Node;
+use crate::cloudformation::intrinsic::references;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Name, ResourceType};
+use crate::cloudformation::stack::Stack;
+
+// Parameters are the knobs a stack is deployed with, so they are drawn as
+// inputs pointing at the resources they configure. Unreferenced parameters
+// are left out, as they affect nothing on the diagram.
+pub fn add(ast: &mut AST, stacks: &[Stack]) {
+  for stack in stacks {
+    let template = &stack.template;
+
+    for resource in template.resources.iter().filter(|r| r.typ.is_supported()) {
+      let names: BTreeSet<String> = resource.properties.json().iter().flat_map(|json| references(json)).collect();
+
+      for name in names {
+        if !template.parameters.contains_key(&name) {
+          continue;
+        }
+
+        let parameter = Node {
+          name: Name::from(name),
+          typ: ResourceType::Parameter,
+          properties: Property::default().into(),
+        };
+        ast.edges.push(Edge::new(parameter, Node::from(resource)));
+      }
+    }
+  }
+
+  ast.edges = canonical(std::mem::take(&mut ast.edges));
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_parameter_edges() {
+    let template: Template = serde_json::from_value(json!({
+      "Parameters": {
+        "Stage": { "Type": "String", "Default": "prod" },
+        "Unused": { "Type": "Number" }
+      },
+      "Resources": {
+        "Api": {
+          "Type": "AWS::ApiGateway::Method",
+          "Properties": {
+            "HttpMethod": "GET",
+            "Integration": { "Uri": { "Fn::Sub": "https://example.com/${Stage}/${AWS::Region}" } }
+          }
+        }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template: template.clone(),
+    }];
+
+    let mut ast = AST::from(template);
+    add(&mut ast, &stacks);
+
+    let expected_output = "```mermaid\nflowchart LR\nStage{{Stage}} --> Api[[Api]]\n```";
+
+    assert_eq!(ast.to_mermaid(), expected_output);
+  }
+}
@@ -0,0 +1,275 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::ast::ast::{AST, canonical, named_resource};
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::arn::Arn;
+use crate::cloudformation::intrinsic::references;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::Resource;
+use crate::cloudformation::stack::Stack;
+use crate::cloudformation::template::Template;
+
+// Verbs are matched on the start of the action name, so `Get*` and
+// `GetItem` read alike.
+const READS: [&str; 12] = [
+  "Get", "List", "Describe", "Query", "Scan", "BatchGet", "Receive", "Read", "Select", "Head", "Lookup",
+  "ConditionCheck",
+];
+const WRITES: [&str; 22] = [
+  "Put", "Update", "Delete", "BatchWrite", "Send", "Create", "Write", "Publish", "Change", "Purge", "Tag",
+  "Untag", "Set", "Start", "Stop", "Invoke", "Abort", "Restore", "Modify", "Remove", "Add", "Append",
+];
+
+// Policies say what a role may do, not what the code does, so these edges are
+// opt-in and drawn dotted. Each role is followed to the functions and state
+// machines that assume it, through inline, attached and managed policies.
+pub fn add(ast: &mut AST, stacks: &[Stack]) {
+  for stack in stacks {
+    let template = &stack.template;
+    let statements = role_statements(template);
+
+    for principal in template.resources.iter().filter(|r| r.typ.is_supported()) {
+      let mut granted: BTreeMap<&str, (&Resource, Vec<String>)> = BTreeMap::new();
+
+      for role in role_of(principal) {
+        for statement in statements.get(&role).into_iter().flatten() {
+          let actions = strings(statement.get("Action"));
+          for target in statement_targets(statement, template) {
+            if target.name == principal.name || !target.typ.is_supported() {
+              continue;
+            }
+            let (_, granted_actions) = granted.entry(&target.name.0).or_insert((target, Vec::new()));
+            for action in &actions {
+              if !granted_actions.contains(action) {
+                granted_actions.push(action.clone());
+              }
+            }
+          }
+        }
+      }
+
+      for (target, actions) in granted.into_values() {
+        let mut edge = Edge::with(Node::from(principal), Node::from(target), Relationship::Grants);
+        edge.detail = Some(condense(&actions));
+        ast.edges.push(edge);
+      }
+    }
+  }
+
+  ast.edges = canonical(std::mem::take(&mut ast.edges));
+}
+
+// The allowed statements of every role, keyed by the role's logical ID. IAM
+// resources are not typed, so roles are told apart by their trust policy and
+// `AWS::IAM::Policy` / `ManagedPolicy` by their `PolicyDocument`.
+fn role_statements(template: &Template) -> BTreeMap<String, Vec<Value>> {
+  let mut statements: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+
+  for resource in &template.resources {
+    let Some(properties) = resource.properties.untyped() else {
+      continue;
+    };
+
+    if properties.get("AssumeRolePolicyDocument").is_some() {
+      let inline = properties.get("Policies").and_then(Value::as_array).into_iter().flatten();
+      let managed = references(properties.get("ManagedPolicyArns").unwrap_or(&Value::Null))
+        .into_iter()
+        .filter_map(|name| template.resource(&name))
+        .filter_map(|policy| policy.properties.untyped());
+
+      let documents = inline.chain(managed).filter_map(|policy| policy.get("PolicyDocument"));
+      statements.entry(resource.name.0.to_string()).or_default().extend(documents.flat_map(allowed));
+    } else if let Some(document) = properties.get("PolicyDocument") {
+      for role in references(properties.get("Roles").unwrap_or(&Value::Null)) {
+        statements.entry(role).or_default().extend(allowed(document));
+      }
+    }
+  }
+
+  statements
+}
+
+fn allowed(document: &Value) -> Vec<Value> {
+  let statements = match document.get("Statement") {
+    Some(Value::Array(statements)) => statements.clone(),
+    Some(statement) => vec![statement.clone()],
+    None => Vec::new(),
+  };
+
+  statements
+    .into_iter()
+    .filter(|statement| statement.get("Effect").and_then(Value::as_str) == Some("Allow"))
+    .collect()
+}
+
+fn role_of(principal: &Resource) -> Vec<String> {
+  match &*principal.properties {
+    Property::Lambda { role, .. } => references(role),
+    Property::Other(properties) => ["Role", "RoleArn"]
+      .iter()
+      .filter_map(|key| properties.get(key))
+      .flat_map(references)
+      .collect(),
+    _ => Vec::new(),
+  }
+}
+
+fn statement_targets<'a>(statement: &Value, template: &'a Template) -> Vec<&'a Resource> {
+  let resources = match statement.get("Resource") {
+    Some(Value::Array(resources)) => resources.iter().collect(),
+    Some(resource) => vec![resource],
+    None => Vec::new(),
+  };
+
+  resources
+    .into_iter()
+    .flat_map(|resource| match resource.as_str().and_then(Arn::parse) {
+      Some(arn) => named_resource(&arn, template).into_iter().collect(),
+      None => references(resource)
+        .into_iter()
+        .filter_map(|name| template.resource(&name))
+        .collect::<Vec<&Resource>>(),
+    })
+    .collect()
+}
+
+fn strings(value: Option<&Value>) -> Vec<String> {
+  match value {
+    Some(Value::String(string)) => vec![string.clone()],
+    Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+    _ => Vec::new(),
+  }
+}
+
+// Generated grants list a dozen actions and are summarised as `r`, `w` or
+// `rw`. A few hand-picked actions are worth reading, so they are listed per
+// service instead, e.g. `dynamodb:Query,PutItem`.
+fn condense(actions: &[String]) -> String {
+  let access: Option<Vec<(bool, bool)>> = actions.iter().map(|action| access(action)).collect();
+
+  if let Some(access) = access
+    && (actions.len() > 3 || actions.iter().any(|action| action.contains('*')))
+  {
+    let reads = access.iter().any(|(read, _)| *read);
+    let writes = access.iter().any(|(_, write)| *write);
+    return match (reads, writes) {
+      (true, false) => "r",
+      (false, true) => "w",
+      _ => "rw",
+    }
+    .to_string();
+  }
+
+  let mut services: Vec<(&str, Vec<&str>)> = Vec::new();
+  for action in actions {
+    let (service, name) = action.split_once(':').unwrap_or(("", action));
+    match services.iter_mut().find(|(existing, _)| *existing == service) {
+      Some((_, names)) => names.push(name),
+      None => services.push((service, vec![name])),
+    }
+  }
+
+  services
+    .iter()
+    .map(|(service, names)| match service {
+      &"" => names.join(","),
+      service => format!("{}:{}", service, names.join(",")),
+    })
+    .collect::<Vec<String>>()
+    .join(" ")
+}
+
+// Whether an action reads, writes or (for a bare wildcard) both.
+fn access(action: &str) -> Option<(bool, bool)> {
+  let name = action.split_once(':').map_or(action, |(_, name)| name).trim_end_matches('*');
+  if name.is_empty() {
+    return Some((true, true));
+  }
+
+  let read = READS.iter().any(|verb| name.starts_with(verb));
+  let write = WRITES.iter().any(|verb| name.starts_with(verb));
+  (read || write).then_some((read, write))
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn test_permission_edges() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Table": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "orders" } },
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Audit": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "audit" } },
+        "Role": {
+          "Type": "AWS::IAM::Role",
+          "Properties": {
+            "AssumeRolePolicyDocument": {
+              "Statement": [{ "Action": "sts:AssumeRole", "Effect": "Allow", "Principal": { "Service": "lambda.amazonaws.com" } }]
+            },
+            "Policies": [{
+              "PolicyName": "audit",
+              "PolicyDocument": {
+                "Statement": { "Effect": "Allow", "Action": ["dynamodb:Query", "dynamodb:PutItem"], "Resource": "arn:aws:dynamodb:eu-west-2:123456789012:table/audit" }
+              }
+            }]
+          }
+        },
+        "RoleDefaultPolicy": {
+          "Type": "AWS::IAM::Policy",
+          "Properties": {
+            "Roles": [{ "Ref": "Role" }],
+            "PolicyDocument": {
+              "Statement": [
+                {
+                  "Effect": "Allow",
+                  "Action": ["dynamodb:BatchGetItem", "dynamodb:GetItem", "dynamodb:Query", "dynamodb:Scan", "dynamodb:PutItem"],
+                  "Resource": [{ "Fn::GetAtt": ["Table", "Arn"] }, { "Fn::Join": ["", [{ "Fn::GetAtt": ["Table", "Arn"] }, "/index/*"]] }]
+                },
+                { "Effect": "Allow", "Action": "sqs:ReceiveMessage*", "Resource": { "Fn::GetAtt": ["Queue", "Arn"] } },
+                { "Effect": "Deny", "Action": "sqs:DeleteMessage", "Resource": { "Fn::GetAtt": ["Queue", "Arn"] } }
+              ]
+            }
+          }
+        },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": { "FunctionName": "worker", "Architectures": ["arm64"], "Role": { "Fn::GetAtt": ["Role", "Arn"] } }
+        }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template,
+    }];
+
+    let mut ast = AST::default();
+    add(&mut ast, &stacks);
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Worker([\"worker\"]) -.->|dynamodb:Query,PutItem| Audit[(\"audit\")]\n",
+      "Worker([\"worker\"]) -.->|r| Queue((\"jobs\"))\n",
+      "Worker([\"worker\"]) -.->|rw| Table[(\"orders\")]\n",
+      "linkStyle 0,1,2 stroke-dasharray:1 3\n",
+      "```",
+    );
+    assert_eq!(ast.to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_condense_actions() {
+    let actions = |actions: &[&str]| actions.iter().map(|action| action.to_string()).collect::<Vec<String>>();
+
+    assert_eq!(condense(&actions(&["s3:GetObject*", "s3:List*"])), "r");
+    assert_eq!(condense(&actions(&["sqs:SendMessage", "sqs:GetQueueUrl", "sqs:GetQueueAttributes", "sqs:ChangeMessageVisibility"])), "rw");
+    assert_eq!(condense(&actions(&["*"])), "rw");
+    assert_eq!(condense(&actions(&["sqs:SendMessage", "kms:Decrypt"])), "sqs:SendMessage kms:Decrypt");
+  }
+}
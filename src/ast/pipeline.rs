@@ -0,0 +1,131 @@
+use serde_json::Value;
+
+use crate::ast::ast::referenced_resource;
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Name, Resource, ResourceType};
+use crate::cloudformation::template::Template;
+
+// What an action acts on, in the order it is looked for in its `Configuration`.
+const TARGETS: [&str; 7] = ["ProjectName", "FunctionName", "StackName", "FullRepositoryId", "RepositoryName", "S3Bucket", "BucketName"];
+
+// Stages run one after another, so each action is linked from every action of
+// the stage before it, and the first stage's from the pipeline itself. Edges
+// are labelled with the provider of the action they lead to.
+pub(crate) fn stage_edges(pipeline: &Resource, template: &Template) -> Vec<Edge> {
+  let Property::Other(properties) = &*pipeline.properties else {
+    return Vec::new();
+  };
+  if pipeline.typ != ResourceType::Pipeline {
+    return Vec::new();
+  }
+
+  let mut edges = Vec::new();
+  let mut previous = vec![Node::from(pipeline)];
+
+  for stage in properties.get("Stages").and_then(Value::as_array).into_iter().flatten() {
+    let actions: Vec<(Node, Option<String>)> = stage
+      .get("Actions")
+      .and_then(Value::as_array)
+      .into_iter()
+      .flatten()
+      .filter_map(|action| Some((target(action, template)?, provider(action))))
+      .collect();
+    if actions.is_empty() {
+      continue;
+    }
+
+    for from in &previous {
+      for (to, provider) in &actions {
+        let mut edge = Edge::with(from.clone(), to.clone(), Relationship::References);
+        edge.detail = provider.clone();
+        edges.push(edge);
+      }
+    }
+    previous = actions.into_iter().map(|(node, _)| node).collect();
+  }
+
+  edges
+}
+
+// An in-template resource, by reference or by the name it was created with,
+// or else an external node for the repository, stack or bucket named.
+fn target(action: &Value, template: &Template) -> Option<Node> {
+  let configuration = action.get("Configuration");
+  let value = TARGETS.iter().find_map(|key| configuration?.get(*key));
+
+  if let Some(resource) = value.and_then(|value| referenced_resource(value, template)) {
+    return resource.typ.is_supported().then(|| Node::from(resource));
+  }
+
+  let name = match value.and_then(Value::as_str) {
+    Some(name) => name,
+    None => action.get("Name")?.as_str()?,
+  };
+  let named = template
+    .resources
+    .iter()
+    .filter(|resource| resource.typ.is_supported())
+    .map(Node::from)
+    .find(|node| node.get_name() == name);
+
+  Some(named.unwrap_or_else(|| Node {
+    name: Name::from(name),
+    typ: ResourceType::External,
+    properties: Property::default().into(),
+  }))
+}
+
+fn provider(action: &Value) -> Option<String> {
+  action.pointer("/ActionTypeId/Provider").and_then(Value::as_str).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::ast::AST;
+
+  use super::*;
+
+  #[test]
+  fn test_pipeline_stages() {
+    let action = |name: &str, provider: &str, configuration: Value| {
+      json!({ "Name": name, "ActionTypeId": { "Category": "Build", "Owner": "AWS", "Provider": provider, "Version": "1" }, "Configuration": configuration })
+    };
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Build": { "Type": "AWS::CodeBuild::Project", "Properties": { "Name": "app-build" } },
+        "Tests": { "Type": "AWS::CodeBuild::Project", "Properties": {} },
+        "Release": {
+          "Type": "AWS::CodePipeline::Pipeline",
+          "Properties": {
+            "Name": "release",
+            "Stages": [
+              { "Name": "Source", "Actions": [action("Checkout", "CodeStarSourceConnection", json!({ "FullRepositoryId": "acme/app" }))] },
+              { "Name": "Build", "Actions": [
+                action("Compile", "CodeBuild", json!({ "ProjectName": "app-build" })),
+                action("Test", "CodeBuild", json!({ "ProjectName": { "Ref": "Tests" } }))
+              ] },
+              { "Name": "Deploy", "Actions": [action("Deploy", "CloudFormation", json!({ "StackName": "app-prod" }))] }
+            ]
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Build([\"app-build\"]) -->|CloudFormation| app_prod[\"app-prod\"]\n",
+      "Release[/\"release\"\\] -->|CodeStarSourceConnection| acme_app[\"acme/app\"]\n",
+      "Tests([Tests]) -->|CloudFormation| app_prod[\"app-prod\"]\n",
+      "acme_app[\"acme/app\"] -->|CodeBuild| Build([\"app-build\"])\n",
+      "acme_app[\"acme/app\"] -->|CodeBuild| Tests([Tests])\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+}
@@ -0,0 +1,289 @@
+use std::collections::{HashSet, VecDeque};
+use std::hash::RandomState;
+
+use clap::ValueEnum;
+use petgraph::algo::{all_simple_paths, has_path_connecting};
+use petgraph::Direction::{Incoming, Outgoing};
+use petgraph::graph::NodeIndex;
+
+use crate::ast::ast::AST;
+use crate::ast::graph::{Indexed, neighbours};
+use crate::ast::node::Node;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Direction {
+  Downstream,
+  Upstream,
+  Both,
+}
+
+impl AST {
+  // Resources are looked up by logical ID first, then by physical name, so
+  // either spelling from the console or the template works.
+  pub fn find(&self, name: &str) -> Option<&Node> {
+    let nodes = self.nodes();
+    nodes
+      .iter()
+      .find(|node| &*node.name.0 == name)
+      .or_else(|| nodes.iter().find(|node| node.get_name() == name))
+      .copied()
+  }
+
+  // Hops are counted ignoring edge direction: a queue's neighbourhood includes
+  // both the functions it triggers and the functions writing to it.
+  pub fn neighbourhood(&self, seed: &Node, depth: usize) -> Vec<Node> {
+    self.indexed().neighbourhood(seed, depth)
+  }
+
+  pub fn blast_radius(&self, seed: &Node, direction: Direction) -> Vec<Node> {
+    self.indexed().blast_radius(seed, direction)
+  }
+
+  pub fn paths(&self, from: &Node, to: &Node, limit: usize) -> Vec<Vec<Node>> {
+    self.indexed().paths(from, to, limit)
+  }
+
+  pub fn focus(&mut self, seed: &Node, depth: usize) {
+    let keep = self.neighbourhood(seed, depth);
+    self.retain(|node| keep.contains(node));
+  }
+}
+
+// Asking several questions of one diagram, such as a page per resource, builds
+// the graph once and queries it for each.
+impl Indexed<'_> {
+  pub fn neighbourhood(&self, seed: &Node, depth: usize) -> Vec<Node> {
+    self.traverse(seed, &[Outgoing, Incoming], Some(depth))
+  }
+
+  // Downstream follows edges the way data flows (API to function, queue to
+  // consumer); upstream walks back to whatever feeds the seed. Both is the two
+  // walks together, so what feeds an upstream resource's other consumers is
+  // not included.
+  pub fn blast_radius(&self, seed: &Node, direction: Direction) -> Vec<Node> {
+    match direction {
+      Direction::Downstream => self.traverse(seed, &[Outgoing], None),
+      Direction::Upstream => self.traverse(seed, &[Incoming], None),
+      Direction::Both => {
+        let mut nodes = self.traverse(seed, &[Outgoing], None);
+        for node in self.traverse(seed, &[Incoming], None) {
+          if !nodes.contains(&node) {
+            nodes.push(node);
+          }
+        }
+        nodes
+      }
+    }
+  }
+
+  // The shortest simple paths following edge direction, at most `limit` of
+  // them. A densely wired stack has far too many paths to list them all, so
+  // they are searched one length at a time and the search stops at `limit`.
+  // Nodes are never revisited within one path, so cycles cannot make it run
+  // forever, and parallel edges do not repeat a path.
+  pub fn paths(&self, from: &Node, to: &Node, limit: usize) -> Vec<Vec<Node>> {
+    let graph = &self.graph;
+    let (Some(source), Some(target)) = (self.index_of(from), self.index_of(to)) else {
+      return Vec::new();
+    };
+    if source == target {
+      return vec![vec![from.clone()]];
+    }
+    if !has_path_connecting(graph, source, target, None) {
+      return Vec::new();
+    }
+
+    let mut paths: Vec<Vec<NodeIndex>> = Vec::new();
+    for length in 0..graph.node_count() - 1 {
+      let mut found: Vec<Vec<NodeIndex>> = Vec::new();
+      for path in all_simple_paths::<Vec<NodeIndex>, _, RandomState>(graph, source, target, length, Some(length)) {
+        if paths.len() + found.len() == limit {
+          break;
+        }
+        if !found.contains(&path) {
+          found.push(path);
+        }
+      }
+      found.sort();
+      paths.extend(found);
+      if paths.len() == limit {
+        break;
+      }
+    }
+
+    paths
+      .into_iter()
+      .map(|path| path.into_iter().map(|index| graph[index].clone()).collect())
+      .collect()
+  }
+
+  // Breadth first from the seed, following edges in each of `directions` at
+  // every hop.
+  fn traverse(&self, seed: &Node, directions: &[petgraph::Direction], depth: Option<usize>) -> Vec<Node> {
+    let Some(start) = self.index_of(seed) else {
+      return vec![seed.clone()];
+    };
+
+    let mut seen = HashSet::from([start]);
+    let mut visited = vec![start];
+    let mut queue = VecDeque::from([(start, 0)]);
+
+    while let Some((node, distance)) = queue.pop_front() {
+      if Some(distance) == depth {
+        continue;
+      }
+
+      for &direction in directions {
+        for neighbour in neighbours(&self.graph, node, direction) {
+          if seen.insert(neighbour) {
+            visited.push(neighbour);
+            queue.push_back((neighbour, distance + 1));
+          }
+        }
+      }
+    }
+
+    visited.into_iter().map(|index| self.graph[index].clone()).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::edge::Edge;
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::{Name, ResourceType};
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_focus_keeps_nodes_within_depth() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Api": { "Type": "AWS::ApiGateway::Method", "Properties": { "HttpMethod": "GET", "Integration": { "Uri": { "Ref": "Front" } } } },
+        "Front": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "front", "Architectures": ["arm64"] } },
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "Other": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "other" } },
+        "OtherMapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Other", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        }
+      }
+    }))
+    .unwrap();
+    let mut ast = AST::from(template);
+
+    let seed = ast.find("jobs").unwrap().clone();
+    ast.focus(&seed, 1);
+
+    let expected_output = "```mermaid\nflowchart LR\nQueue((\"jobs\")) -.->|triggers| Worker([\"worker\"])\n```";
+
+    assert_eq!(ast.find("Queue"), Some(&seed));
+    assert_eq!(ast.to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_paths_between_resources() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Api": { "Type": "AWS::ApiGateway::Method", "Properties": { "HttpMethod": "GET", "Integration": { "Uri": { "Ref": "Front" } } } },
+        "Front": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "front", "Architectures": ["arm64"] } },
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Front" } }
+        }
+      }
+    }))
+    .unwrap();
+    let ast = AST::from(template);
+
+    let api = ast.find("Api").unwrap();
+    let front = ast.find("front").unwrap();
+    let queue = ast.find("Queue").unwrap();
+    let paths: Vec<Vec<String>> = ast
+      .paths(api, front, 10)
+      .into_iter()
+      .map(|path| path.into_iter().map(|node| node.name.0.to_string()).collect())
+      .collect();
+
+    assert_eq!(paths, vec![vec!["Api".to_string(), "Front".to_string()]]);
+    assert!(ast.paths(front, queue, 10).is_empty());
+  }
+
+  #[test]
+  fn test_paths_stop_at_the_limit_shortest_first() {
+    let node = |name: &str| Node {
+      name: Name::from(name),
+      typ: ResourceType::Lambda,
+      properties: Property::Other(serde_json::Value::Null).into(),
+    };
+    let ast = AST {
+      edges: [("A", "B"), ("B", "C"), ("A", "C"), ("C", "D"), ("B", "D")]
+        .into_iter()
+        .map(|(from, to)| Edge::new(node(from), node(to)))
+        .collect(),
+      ..Default::default()
+    };
+    let names = |paths: Vec<Vec<Node>>| {
+      paths.into_iter().map(|path| path.iter().map(|node| node.name.0.to_string()).collect::<Vec<_>>().join(" ")).collect::<Vec<_>>()
+    };
+
+    assert_eq!(names(ast.paths(&node("A"), &node("D"), 10)), ["A B D", "A C D", "A B C D"]);
+    assert_eq!(names(ast.paths(&node("A"), &node("D"), 2)), ["A B D", "A C D"]);
+  }
+
+  #[test]
+  fn test_blast_radius() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Api": { "Type": "AWS::ApiGateway::Method", "Properties": { "HttpMethod": "GET", "Integration": { "Uri": { "Ref": "Front" } } } },
+        "Front": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "front", "Architectures": ["arm64"] } },
+        "Other": { "Type": "AWS::ApiGateway::Method", "Properties": { "HttpMethod": "GET", "Integration": { "Uri": { "Ref": "Back" } } } },
+        "Back": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "back", "Architectures": ["arm64"] } }
+      }
+    }))
+    .unwrap();
+    let ast = AST::from(template);
+    let names = |nodes: Vec<Node>| nodes.into_iter().map(|node| node.name.0.to_string()).collect::<Vec<String>>();
+
+    let api = ast.find("Api").unwrap();
+    let front = ast.find("Front").unwrap();
+
+    assert_eq!(names(ast.blast_radius(api, Direction::Downstream)), vec!["Api", "Front"]);
+    assert_eq!(names(ast.blast_radius(api, Direction::Upstream)), vec!["Api"]);
+    assert_eq!(names(ast.blast_radius(front, Direction::Both)), vec!["Front", "Api"]);
+  }
+
+  #[test]
+  fn test_blast_radius_leaves_out_siblings() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Jobs": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Orders": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "orders" } },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": {
+            "FunctionName": "worker",
+            "Environment": { "Variables": { "QUEUE": { "Ref": "Jobs" }, "TABLE": { "Ref": "Orders" } } }
+          }
+        }
+      }
+    }))
+    .unwrap();
+    let ast = AST::from(template);
+    let names = |nodes: Vec<Node>| nodes.into_iter().map(|node| node.name.0.to_string()).collect::<Vec<String>>();
+
+    // The table is configured by the same function, but nothing flows between
+    // it and the queue.
+    let jobs = ast.find("Jobs").unwrap();
+    assert_eq!(names(ast.blast_radius(jobs, Direction::Both)), vec!["Jobs", "Worker"]);
+  }
+}
@@ -0,0 +1,146 @@
+use serde_json::Value;
+
+use crate::ast::ast::target_node;
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Resource, ResourceType};
+use crate::cloudformation::template::Template;
+
+// The field naming each kind of action's target, and the service a literal
+// name in it belongs to. An S3 action may notify a topic as well.
+const ACTIONS: [(&str, &str, &str); 4] = [
+  ("S3Action", "BucketName", "s3"),
+  ("S3Action", "TopicArn", "sns"),
+  ("LambdaAction", "FunctionArn", "lambda"),
+  ("SNSAction", "TopicArn", "sns"),
+];
+
+// Mail to a rule's recipients fans out to each of its actions, with the edges
+// labelled by those recipients. Identities in the template that receive that
+// mail, by address or by domain, trigger the rule.
+pub(crate) fn action_edges(rule: &Resource, template: &Template) -> Vec<Edge> {
+  let Property::Other(properties) = &*rule.properties else {
+    return Vec::new();
+  };
+  if rule.typ != ResourceType::SesReceiptRule {
+    return Vec::new();
+  }
+  let Some(definition) = properties.get("Rule") else {
+    return Vec::new();
+  };
+
+  let recipients: Vec<&str> = definition
+    .get("Recipients")
+    .and_then(Value::as_array)
+    .into_iter()
+    .flatten()
+    .filter_map(Value::as_str)
+    .collect();
+  let detail = (!recipients.is_empty()).then(|| format!("({})", recipients.join(", ")));
+
+  let mut edges = Vec::new();
+  for action in definition.get("Actions").and_then(Value::as_array).into_iter().flatten() {
+    for (kind, field, service) in ACTIONS {
+      let Some(target) = action.get(kind).and_then(|action| action.get(field)) else {
+        continue;
+      };
+      if let Some(target) = target_node(target, service, template) {
+        let mut edge = Edge::with(Node::from(rule), target, Relationship::Routes);
+        edge.detail = detail.clone();
+        edges.push(edge);
+      }
+    }
+  }
+
+  for identity in template.resources.iter().filter(|r| r.typ == ResourceType::SesIdentity) {
+    let node = Node::from(identity);
+    if recipients.iter().any(|recipient| receives(node.get_name(), recipient)) {
+      edges.push(Edge::with(node, Node::from(rule), Relationship::Triggers));
+    }
+  }
+
+  edges
+}
+
+// A recipient is an address, a domain, or a subdomain with a leading dot.
+fn receives(identity: &str, recipient: &str) -> bool {
+  let domain = recipient.rsplit('@').next().unwrap_or(recipient).trim_start_matches('.');
+  identity.eq_ignore_ascii_case(recipient) || identity.eq_ignore_ascii_case(domain)
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::ast::AST;
+
+  use super::*;
+
+  #[test]
+  fn test_receipt_rule_actions() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Domain": { "Type": "AWS::SES::EmailIdentity", "Properties": { "EmailIdentity": "example.com" } },
+        "Inbound": {
+          "Type": "AWS::SES::ReceiptRule",
+          "Properties": {
+            "RuleSetName": { "Ref": "RuleSet" },
+            "Rule": {
+              "Name": "support",
+              "Enabled": true,
+              "Recipients": ["support@example.com"],
+              "Actions": [
+                { "S3Action": { "BucketName": { "Ref": "Mail" }, "ObjectKeyPrefix": "support/" } },
+                { "LambdaAction": { "FunctionArn": { "Fn::GetAtt": ["Triage", "Arn"] }, "InvocationType": "Event" } },
+                { "SNSAction": { "TopicArn": "arn:aws:sns:eu-west-2:123:support-alerts" } }
+              ]
+            }
+          }
+        },
+        "RuleSet": { "Type": "AWS::SES::ReceiptRuleSet", "Properties": {} },
+        "Mail": { "Type": "AWS::S3::Bucket", "Properties": { "BucketName": "inbound-mail" } },
+        "Triage": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "triage" } },
+        "Alerts": { "Type": "AWS::SNS::Topic", "Properties": { "TopicName": "support-alerts" } }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Domain>\"example.com\"] -.->|triggers| Inbound[/\"support\"\\]\n",
+      "Inbound[/\"support\"\\] -.->|routes (support@example.com)| Alerts((\"support-alerts\"))\n",
+      "Inbound[/\"support\"\\] -.->|routes (support@example.com)| Mail[(\"inbound-mail\")]\n",
+      "Inbound[/\"support\"\\] -.->|routes (support@example.com)| Triage([\"triage\"])\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_topics_only_subscribe_their_endpoints() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Alerts": {
+          "Type": "AWS::SNS::Topic",
+          "Properties": {
+            "TopicName": "support-alerts",
+            "Subscription": [{ "Endpoint": { "Fn::GetAtt": ["Triage", "Arn"] }, "Protocol": "lambda" }]
+          }
+        },
+        "Triage": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "triage" } },
+        "Mail": { "Type": "AWS::S3::Bucket", "Properties": { "BucketName": "inbound-mail" } }
+      }
+    }))
+    .unwrap();
+    let node = |index: usize| Node::from(&template.resources[index]);
+
+    assert_eq!(Relationship::between(&node(0), &node(1)), Relationship::Subscribes);
+    assert_eq!(Relationship::between(&node(0), &node(2)), Relationship::References);
+    assert_eq!(
+      AST::from(template).to_mermaid(),
+      "```mermaid\nflowchart LR\nAlerts((\"support-alerts\")) -.->|subscribes| Triage([\"triage\"])\n```"
+    );
+  }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::ast::ast::AST;
+use crate::ast::node::{Node, logical_prefix};
+use crate::cloudformation::resource::Resource;
+use crate::cloudformation::stack::Stack;
+
+/// How `--split-by` partitions the graph: by the value of one tag, or by the
+/// first word of each logical ID.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SplitBy {
+  Tag(String),
+  Prefix,
+}
+
+impl FromStr for SplitBy {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match (s, s.strip_prefix("tag:")) {
+      ("prefix", _) => Ok(SplitBy::Prefix),
+      (_, Some(key)) if !key.is_empty() => Ok(SplitBy::Tag(key.to_string())),
+      _ => Err(format!("`{}` is neither `tag:<key>` nor `prefix`", s)),
+    }
+  }
+}
+
+/// One diagram of a split graph: the resources of one part, the edges touching
+/// them, and the resources of other parts at the far end of those edges.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Part {
+  pub name: String,
+  pub ast: AST,
+  pub ghosts: Vec<Node>,
+}
+
+// Resources without the tag, or whose prefix no other resource shares.
+const REST: &str = "other";
+
+/// Splits the graph so each team gets a diagram of its own resources. A
+/// resource another part connects to is drawn in that part too, as a ghost,
+/// and nodes that are not resources of the stacks (external sources,
+/// parameters) are drawn wherever they are connected.
+// Resources are looked up by stack and logical ID, since the same logical ID
+// is common across stacks. The stack of a node is the subgraph it is drawn in
+// when there is more than one. Nodes are keyed by their properties too, which
+// clippy takes for a key that can change; see `Ids`.
+#[allow(clippy::mutable_key_type)]
+pub fn split(ast: &AST, stacks: &[Stack], by: &SplitBy) -> Vec<Part> {
+  let nodes = ast.all_nodes();
+  let resources: HashMap<(&str, &str), &Resource> = stacks
+    .iter()
+    .flat_map(|stack| stack.template.resources.iter().map(move |resource| ((stack.name.as_str(), &*resource.name.0), resource)))
+    .collect();
+  let mut prefixes: HashMap<&str, usize> = HashMap::new();
+  for prefix in nodes.iter().filter_map(|node| logical_prefix(&node.name.0)) {
+    *prefixes.entry(prefix).or_default() += 1;
+  }
+
+  let part_of = |node: &Node| -> Option<String> {
+    let stack = match stacks {
+      [stack] => stack.name.as_str(),
+      _ => ast.group_of(node)?,
+    };
+    let resource = resources.get(&(stack, &*node.name.0))?;
+
+    let part = match by {
+      SplitBy::Tag(key) => resource.tags.iter().find(|(tag, _)| tag == key).map(|(_, value)| value.as_str()),
+      SplitBy::Prefix => logical_prefix(&node.name.0).filter(|prefix| prefixes[prefix] > 1),
+    };
+    Some(part.unwrap_or(REST).to_string())
+  };
+
+  let parts: HashMap<&Node, Option<String>> = nodes.iter().map(|&node| (node, part_of(node))).collect();
+  let lookup = |node: &Node| parts.get(node).cloned().flatten();
+
+  let mut names: Vec<String> = parts.values().flatten().cloned().collect();
+  names.sort();
+  names.dedup();
+
+  names
+    .into_iter()
+    .map(|name| {
+      let inside = |node: &Node| lookup(node).as_ref() == Some(&name);
+      let touches = |from: &Node, to: &Node| inside(from) || inside(to);
+
+      let mut part = ast.clone();
+      part.edges.retain(|edge| touches(&edge.from, &edge.to));
+      part.cross_stack_edges.retain(|edge| touches(&edge.from, &edge.to));
+      for subgraph in &mut part.subgraphs {
+        subgraph.retain(&|node| inside(node));
+      }
+      part.subgraphs.retain(|subgraph| !subgraph.is_empty());
+
+      let ghosts = part
+        .nodes()
+        .into_iter()
+        .filter(|node| lookup(node).is_some_and(|other| other != name))
+        .cloned()
+        .collect();
+      Part { name, ast: part, ghosts }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::resource::ResourceType;
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_split_by_tag() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": {
+          "Type": "AWS::SQS::Queue",
+          "Properties": { "QueueName": "jobs", "Tags": [{ "Key": "team", "Value": "ingest" }] }
+        },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": { "FunctionName": "worker", "Tags": [{ "Key": "team", "Value": "orders" }], "Environment": { "Variables": { "TABLE": { "Ref": "Table" } } } }
+        },
+        "Table": {
+          "Type": "AWS::DynamoDB::Table",
+          "Properties": { "TableName": "orders", "Tags": [{ "Key": "team", "Value": "orders" }] }
+        }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template: template.clone(),
+    }];
+    let ast = AST::from(template);
+
+    let parts = split(&ast, &stacks, &"tag:team".parse().unwrap());
+    let names: Vec<&str> = parts.iter().map(|part| part.name.as_str()).collect();
+    assert_eq!(names, ["ingest", "orders"]);
+
+    let ingest = &parts[0];
+    assert_eq!(ingest.ast.edges.len(), 1);
+    assert_eq!(ingest.ghosts.iter().map(|node| &*node.name.0).collect::<Vec<_>>(), ["Worker"]);
+
+    let orders = &parts[1];
+    assert_eq!(orders.ast.edges.len(), 2);
+    assert_eq!(orders.ghosts.iter().map(|node| &*node.name.0).collect::<Vec<_>>(), ["Queue"]);
+  }
+
+  #[test]
+  fn test_split_tells_stacks_apart() {
+    let stack = |name: &str, team: &str| Stack {
+      name: name.to_string(),
+      template: serde_json::from_value(json!({
+        "Resources": {
+          "Jobs": {
+            "Type": "AWS::SQS::Queue",
+            "Properties": { "QueueName": format!("{}-jobs", name), "Tags": [{ "Key": "team", "Value": team }] }
+          },
+          "Worker": {
+            "Type": "AWS::Lambda::Function",
+            "Properties": { "FunctionName": format!("{}-worker", name), "Environment": { "Variables": { "QUEUE": { "Ref": "Jobs" } } } }
+          }
+        }
+      }))
+      .unwrap(),
+    };
+    let stacks = vec![stack("ingest", "ingest"), stack("orders", "orders")];
+    let ast = crate::ast::cross_stack::build(&stacks, None, &|_| true);
+
+    let parts = split(&ast, &stacks, &"tag:team".parse().unwrap());
+    let jobs = |part: &Part| -> Vec<String> {
+      let nodes = part.ast.all_nodes();
+      nodes.iter().filter(|node| node.typ == ResourceType::Sqs).map(|node| node.get_name().to_string()).collect()
+    };
+
+    assert_eq!(parts.iter().map(|part| part.name.as_str()).collect::<Vec<_>>(), ["ingest", "orders", "other"]);
+    assert_eq!(jobs(&parts[0]), ["ingest-jobs"]);
+    assert_eq!(jobs(&parts[1]), ["orders-jobs"]);
+    assert!(parts[2].ghosts.iter().all(|node| node.typ == ResourceType::Sqs));
+    assert_eq!(parts[2].ghosts.len(), 2);
+  }
+
+  #[test]
+  fn test_parse_split_by() {
+    assert_eq!("prefix".parse(), Ok(SplitBy::Prefix));
+    assert_eq!("tag:team".parse(), Ok(SplitBy::Tag("team".to_string())));
+    assert!("tag:".parse::<SplitBy>().is_err());
+    assert!("stack".parse::<SplitBy>().is_err());
+  }
+}
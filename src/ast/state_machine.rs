@@ -0,0 +1,180 @@
+use serde_json::{Map, Value};
+
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::intrinsic::{splice, spliced_references};
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::Resource;
+use crate::cloudformation::template::Template;
+
+// The definition is JSON inside a string, with the ARNs it calls spliced in by
+// `Fn::Sub` or `Fn::Join`. Every spliced-in resource is swapped for a marker
+// before parsing, so only resources that task states actually use are linked.
+pub(crate) fn task_edges(machine: &Resource, template: &Template) -> Vec<Edge> {
+  let Property::Other(properties) = &*machine.properties else {
+    return Vec::new();
+  };
+  let Some(definition) = definition(properties) else {
+    return Vec::new();
+  };
+
+  let mut tasks = Vec::new();
+  collect_tasks(&definition, &mut tasks);
+
+  let mut edges = Vec::new();
+  for task in tasks {
+    let integration = task.get("Resource").and_then(Value::as_str).unwrap_or_default();
+
+    let mut names = Vec::new();
+    task.values().for_each(|value| collect_spliced(value, &mut names));
+
+    for name in names {
+      let Some(resource) = template.resource(&name).filter(|r| r.typ.is_supported()) else {
+        continue;
+      };
+
+      let from = Node::from(machine);
+      let to = Node::from(resource);
+      edges.push(match relationship(integration) {
+        Some(relationship) => Edge::with(from, to, relationship),
+        None => Edge::new(from, to),
+      });
+    }
+  }
+
+  edges
+}
+
+pub(crate) fn definition(properties: &Value) -> Option<Value> {
+  let mut variables = Map::new();
+  if let Some(substitutions) = properties.get("DefinitionSubstitutions").and_then(Value::as_object) {
+    variables.extend(substitutions.clone());
+  }
+
+  let text = match (properties.get("DefinitionString"), properties.get("Definition")) {
+    (Some(definition), _) => splice(definition, &variables)?,
+    (None, Some(definition)) => splice(&Value::String(definition.to_string()), &variables)?,
+    _ => return None,
+  };
+
+  serde_json::from_str(&text).ok()
+}
+
+fn collect_spliced(value: &Value, names: &mut Vec<String>) {
+  match value {
+    Value::String(s) => {
+      for name in spliced_references(s) {
+        if !names.contains(&name) {
+          names.push(name);
+        }
+      }
+    }
+    Value::Object(map) => map.values().for_each(|value| collect_spliced(value, names)),
+    Value::Array(items) => items.iter().for_each(|item| collect_spliced(item, names)),
+    _ => {}
+  }
+}
+
+// States nest inside `Parallel` branches and `Map` iterators, so any object
+// carrying a `States` map is searched.
+fn collect_tasks<'a>(value: &'a Value, tasks: &mut Vec<&'a Map<String, Value>>) {
+  match value {
+    Value::Object(map) => {
+      if let Some(Value::Object(states)) = map.get("States") {
+        for state in states.values().filter_map(Value::as_object) {
+          if state.get("Type").and_then(Value::as_str) == Some("Task") {
+            tasks.push(state);
+          }
+        }
+      }
+      map.values().for_each(|value| collect_tasks(value, tasks));
+    }
+    Value::Array(items) => items.iter().for_each(|item| collect_tasks(item, tasks)),
+    _ => {}
+  }
+}
+
+// Optimised integrations name the API being called, e.g.
+// `arn:aws:states:::dynamodb:putItem`; a bare function ARN is a plain invoke.
+fn relationship(integration: &str) -> Option<Relationship> {
+  let Some(api) = integration.strip_prefix("arn:aws:states:::") else {
+    return (!spliced_references(integration).is_empty()).then_some(Relationship::Invokes);
+  };
+  let api = api.split('.').next().unwrap_or(api);
+
+  match api.split_once(':') {
+    Some(("lambda", _)) => Some(Relationship::Invokes),
+    Some(("dynamodb", "getItem")) => Some(Relationship::Reads),
+    Some(("dynamodb" | "sqs" | "sns" | "events", _)) => Some(Relationship::Writes),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::ast::AST;
+
+  use super::*;
+
+  #[test]
+  fn test_task_states_reference_resources() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Flow": {
+          "Type": "AWS::StepFunctions::StateMachine",
+          "Properties": {
+            "RoleArn": { "Fn::GetAtt": ["Role", "Arn"] },
+            "DefinitionString": {
+              "Fn::Sub": [
+                "{\"StartAt\":\"Work\",\"States\":{\"Work\":{\"Type\":\"Task\",\"Resource\":\"${WorkerArn}\",\"Next\":\"Fan\"},\"Fan\":{\"Type\":\"Parallel\",\"End\":true,\"Branches\":[{\"StartAt\":\"Save\",\"States\":{\"Save\":{\"Type\":\"Task\",\"Resource\":\"arn:aws:states:::dynamodb:putItem\",\"Parameters\":{\"TableName\":\"${Table}\"},\"End\":true}}}]},\"Note\":{\"Type\":\"Pass\",\"Result\":\"${Queue.Arn}\",\"End\":true}}}",
+                { "WorkerArn": { "Fn::GetAtt": ["Worker", "Arn"] } }
+              ]
+            }
+          }
+        },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } },
+        "Table": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "orders" } },
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Role": { "Type": "AWS::IAM::Role", "Properties": {} }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Flow(Flow) -->|writes| Table[(\"orders\")]\n",
+      "Flow(Flow) -->|invokes| Worker([\"worker\"])\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_joined_definition() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Flow": {
+          "Type": "AWS::StepFunctions::StateMachine",
+          "Properties": {
+            "DefinitionString": {
+              "Fn::Join": ["", [
+                "{\"StartAt\":\"Invoke\",\"States\":{\"Invoke\":{\"Type\":\"Task\",\"Resource\":\"arn:aws:states:::lambda:invoke\",\"Parameters\":{\"FunctionName\":\"",
+                { "Fn::GetAtt": ["Worker", "Arn"] },
+                "\"},\"End\":true}}}"
+              ]]
+            }
+          }
+        },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Architectures": ["arm64"] } }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = "```mermaid\nflowchart LR\nFlow(Flow) -->|invokes| Worker([\"worker\"])\n```";
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+}
@@ -0,0 +1,83 @@
+use serde::Serialize;
+
+use crate::ast::ids::Ids;
+use crate::ast::node::{DEFAULT_LABEL, Node, sanitize_id};
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct Subgraph {
+  pub name: String,
+  pub nodes: Vec<Node>,
+  pub subgraphs: Vec<Subgraph>,
+  pub direction: Option<String>,
+}
+
+impl Subgraph {
+  pub fn contains(&self, node: &Node) -> bool {
+    self.nodes.contains(node) || self.subgraphs.iter().any(|subgraph| subgraph.contains(node))
+  }
+
+  pub fn all_nodes(&self) -> Vec<&Node> {
+    let mut nodes: Vec<&Node> = self.nodes.iter().collect();
+    for subgraph in &self.subgraphs {
+      nodes.extend(subgraph.all_nodes());
+    }
+    nodes
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty() && self.subgraphs.is_empty()
+  }
+
+  pub fn retain(&mut self, keep: &dyn Fn(&Node) -> bool) {
+    self.nodes.retain(keep);
+    for subgraph in &mut self.subgraphs {
+      subgraph.retain(keep);
+    }
+    self.subgraphs.retain(|subgraph| !subgraph.is_empty());
+  }
+
+  pub fn for_each_node(&mut self, f: &dyn Fn(&mut Node)) {
+    self.nodes.iter_mut().for_each(f);
+    for subgraph in &mut self.subgraphs {
+      subgraph.for_each_node(f);
+    }
+  }
+
+  pub fn to_mermaid(&self) -> String {
+    self.to_mermaid_with(DEFAULT_LABEL)
+  }
+
+  pub fn to_mermaid_with(&self, label_format: &str) -> String {
+    self.to_mermaid_within(None, label_format, &Ids::new(self.all_nodes(), |_| None))
+  }
+
+  // Construct names such as `Resource` or `Default` repeat across a CDK app, so
+  // nested subgraphs are keyed by their full path and labelled with their name.
+  pub(crate) fn to_mermaid_within(&self, parent: Option<&str>, label_format: &str, ids: &Ids) -> String {
+    let id = match parent {
+      Some(parent) => format!("{}_{}", parent, sanitize_id(&self.name)),
+      None => sanitize_id(&self.name),
+    };
+
+    let mut result = match id == self.name {
+      true => format!("subgraph {}\n", self.name),
+      false => format!("subgraph {}[{}]\n", id, self.name),
+    };
+
+    if let Some(direction) = &self.direction {
+      result.push_str(&format!("direction {}\n", direction));
+    }
+
+    for subgraph in &self.subgraphs {
+      result.push_str(&subgraph.to_mermaid_within(Some(&id), label_format, ids));
+    }
+
+    for node in &self.nodes {
+      result.push_str(&format!("{}\n", node.to_mermaid_as(&ids.of(node), label_format)));
+    }
+
+    result.push_str("end\n");
+    result
+  }
+}
+
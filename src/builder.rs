@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::ast::ast::{self, AST};
+use crate::ast::cache::ReferenceCache;
+use crate::ast::edge::EdgeDirection;
+use crate::ast::node::Node;
+use crate::ast::{aliases, api, construct, cross_stack, depends_on, layers, missing, parameters, permissions};
+use crate::cloudformation::resource::{ResourceType, TypeMappings};
+use crate::cloudformation::stack::Stack;
+use crate::cloudformation::{assembly, input, nested, resolve};
+use crate::error::Result;
+use crate::render::{Format, KeepPolicy, RenderOptions, validate};
+
+/// Converts CloudFormation templates to diagrams, the same way the
+/// `cloudmaid` binary does.
+///
+/// ```no_run
+/// use cloudmaid::Cloudmaid;
+/// use cloudmaid::cloudformation::resource::ResourceType;
+/// use cloudmaid::render::Format;
+///
+/// let diagram = Cloudmaid::builder()
+///   .input("cdk.out")
+///   .format(Format::PlantUml)
+///   .exclude([ResourceType::Parameter])
+///   .filter(|node| !node.name.0.contains("LogRetention"))
+///   .build()
+///   .run()?;
+///
+/// println!("{}", diagram.rendered);
+/// # Ok::<(), cloudmaid::error::CloudmaidError>(())
+/// ```
+pub struct Cloudmaid {
+  inputs: Vec<PathBuf>,
+  stacks: Vec<Stack>,
+  format: Format,
+  options: RenderOptions,
+  parameters: Vec<(String, String)>,
+  region: Option<String>,
+  account_id: Option<String>,
+  nested_stacks: bool,
+  template_map: HashMap<String, PathBuf>,
+  type_mappings: TypeMappings,
+  show_depends_on: bool,
+  show_missing: bool,
+  show_parameters: bool,
+  show_permissions: bool,
+  show_aliases: bool,
+  collapse_apis: bool,
+  layers: bool,
+  edge_direction: Option<EdgeDirection>,
+  cache: Option<ReferenceCache>,
+}
+
+/// The result of [`Cloudmaid::run`].
+#[derive(Debug, Clone)]
+pub struct Diagram {
+  /// Every stack read from the inputs, with intrinsics resolved.
+  pub stacks: Vec<Stack>,
+  /// The graph after filtering, as it was rendered.
+  pub graph: AST,
+  /// The diagram in the chosen format. For `svg` and `png` this is the mermaid
+  /// source the image would be drawn from.
+  pub rendered: String,
+}
+
+/// Configures a [`Cloudmaid`]. Every option defaults to what the binary does
+/// without flags.
+pub struct Builder {
+  cloudmaid: Cloudmaid,
+}
+
+impl Cloudmaid {
+  pub fn builder() -> Builder {
+    Builder {
+      cloudmaid: Cloudmaid {
+        inputs: Vec::new(),
+        stacks: Vec::new(),
+        format: Format::Mermaid,
+        options: RenderOptions::default(),
+        parameters: Vec::new(),
+        region: None,
+        account_id: None,
+        nested_stacks: false,
+        template_map: HashMap::new(),
+        type_mappings: TypeMappings::new(),
+        show_depends_on: false,
+        show_missing: false,
+        show_parameters: false,
+        show_permissions: false,
+        show_aliases: false,
+        collapse_apis: false,
+        layers: false,
+        edge_direction: None,
+        cache: None,
+      },
+    }
+  }
+
+  /// Reads, resolves, filters and renders all inputs as one diagram. A
+  /// flowchart mermaid would reject is returned as an error instead.
+  pub fn run(&self) -> Result<Diagram> {
+    let mut stacks = self.stacks.clone();
+    for path in &self.inputs {
+      stacks.extend(self.read(path)?);
+    }
+    self.resolve(&mut stacks);
+
+    let graph = self.graph(&stacks);
+    let rendered = self.render(&graph);
+    validate::flowchart(&rendered)?;
+    Ok(Diagram { stacks, graph, rendered })
+  }
+
+  /// Reads the stacks in one template, cloud assembly (`cdk.out`) or stdin
+  /// (`-`), without resolving intrinsics.
+  pub fn read(&self, path: &Path) -> Result<Vec<Stack>> {
+    if assembly::is_assembly(path) {
+      return assembly::load(path, &self.type_mappings);
+    }
+
+    let name = match path.to_str() {
+      Some(input::STDIN) => "stdin".to_string(),
+      _ => path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+    };
+
+    if self.nested_stacks {
+      return nested::expand(&name, input::read_value(path)?, path, &self.template_map, &self.type_mappings);
+    }
+
+    Ok(vec![Stack {
+      name,
+      template: input::read_template(path, &self.type_mappings)?,
+    }])
+  }
+
+  /// Evaluates pseudo parameters, parameters and mappings where they are known.
+  pub fn resolve(&self, stacks: &mut [Stack]) {
+    for stack in stacks {
+      let context = resolve::Context {
+        stack_name: &stack.name,
+        region: self.region.as_deref(),
+        account_id: self.account_id.as_deref(),
+        parameters: &self.parameters,
+      };
+      resolve::resolve(&mut stack.template, &context);
+    }
+  }
+
+  /// Builds the filtered graph of already resolved stacks.
+  pub fn graph(&self, stacks: &[Stack]) -> AST {
+    let keep = |node: &Node| self.options.keep.keeps_resource(node);
+    let mut ast = match stacks {
+      [stack] => ast::build(&stack.template, self.cache.as_ref(), &keep),
+      _ => cross_stack::build(stacks, self.cache.as_ref(), &keep),
+    };
+
+    if self.show_depends_on {
+      depends_on::add(&mut ast, stacks);
+    }
+
+    if self.show_missing {
+      missing::add(&mut ast, stacks);
+    }
+
+    if self.show_parameters {
+      parameters::add(&mut ast, stacks);
+    }
+
+    if self.show_permissions {
+      permissions::add(&mut ast, stacks);
+    }
+
+    if self.collapse_apis {
+      api::consolidate(&mut ast, stacks);
+    }
+
+    construct::group(&mut ast, stacks);
+    // The passes above add nodes of their own, such as parameters and missing
+    // resources.
+    ast.retain(|node| self.options.keep.keeps(node));
+
+    if let Some(direction) = self.edge_direction {
+      ast.orient(direction);
+    }
+
+    if self.layers {
+      layers::group(&mut ast);
+    }
+
+    if self.show_aliases {
+      aliases::suffix(&mut ast, stacks);
+    }
+    ast
+  }
+
+  /// Renders a graph with the configured format and options.
+  pub fn render(&self, graph: &AST) -> String {
+    self.format.renderer(self.options.clone()).render(graph)
+  }
+}
+
+impl Builder {
+  /// Adds a template file, a cloud assembly (`cdk.out`) or `-` for stdin.
+  pub fn input(mut self, path: impl Into<PathBuf>) -> Self {
+    self.cloudmaid.inputs.push(path.into());
+    self
+  }
+
+  /// Adds a stack that has already been parsed.
+  pub fn stack(mut self, stack: Stack) -> Self {
+    self.cloudmaid.stacks.push(stack);
+    self
+  }
+
+  pub fn format(mut self, format: Format) -> Self {
+    self.cloudmaid.format = format;
+    self
+  }
+
+  /// Title, header, label format, highlighting and the keep policy. No title
+  /// is drawn unless one is set here. Call this before the filtering methods
+  /// below, which change the policy it sets.
+  pub fn options(mut self, options: RenderOptions) -> Self {
+    self.cloudmaid.options = options;
+    self
+  }
+
+  /// Replaces which resource types and nodes are drawn.
+  pub fn keep(mut self, keep: KeepPolicy) -> Self {
+    self.cloudmaid.options.keep = keep;
+    self
+  }
+
+  /// Draws only these types, as `--only` does.
+  pub fn only(mut self, types: impl IntoIterator<Item = ResourceType>) -> Self {
+    self.cloudmaid.options.keep.allow.extend(types);
+    self
+  }
+
+  /// Leaves these types out, as `--exclude` does.
+  pub fn exclude(mut self, types: impl IntoIterator<Item = ResourceType>) -> Self {
+    self.cloudmaid.options.keep.deny.extend(types);
+    self
+  }
+
+  /// Keeps only the nodes the predicate accepts, along with the edges between
+  /// them.
+  pub fn filter(mut self, keep: impl Fn(&Node) -> bool + Send + Sync + 'static) -> Self {
+    self.cloudmaid.options.keep.predicate = Some(Arc::new(keep));
+    self
+  }
+
+  /// Sets a template parameter, as `--parameter KEY=VALUE` does.
+  pub fn parameter(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+    self.cloudmaid.parameters.push((key.into(), value.into()));
+    self
+  }
+
+  pub fn region(mut self, region: impl Into<String>) -> Self {
+    self.cloudmaid.region = Some(region.into());
+    self
+  }
+
+  pub fn account_id(mut self, account_id: impl Into<String>) -> Self {
+    self.cloudmaid.account_id = Some(account_id.into());
+    self
+  }
+
+  /// Reads `AWS::CloudFormation::Stack` children into their own stacks.
+  pub fn nested_stacks(mut self, nested_stacks: bool) -> Self {
+    self.cloudmaid.nested_stacks = nested_stacks;
+    self
+  }
+
+  /// Maps a nested stack's `TemplateURL` to a local file.
+  pub fn template_map(mut self, url: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+    self.cloudmaid.template_map.insert(url.into(), path.into());
+    self
+  }
+
+  /// Draws resources of a CloudFormation type, such as `Custom::Queue`, as one
+  /// of the known types.
+  pub fn type_mapping(mut self, raw_type: impl Into<String>, typ: ResourceType) -> Self {
+    self.cloudmaid.type_mappings.insert(raw_type.into(), typ);
+    self
+  }
+
+  pub fn show_depends_on(mut self, show: bool) -> Self {
+    self.cloudmaid.show_depends_on = show;
+    self
+  }
+
+  /// Draws references to logical IDs the template does not define as red
+  /// `missing` nodes.
+  pub fn show_missing(mut self, show: bool) -> Self {
+    self.cloudmaid.show_missing = show;
+    self
+  }
+
+  pub fn show_parameters(mut self, show: bool) -> Self {
+    self.cloudmaid.show_parameters = show;
+    self
+  }
+
+  pub fn show_permissions(mut self, show: bool) -> Self {
+    self.cloudmaid.show_permissions = show;
+    self
+  }
+
+  pub fn show_aliases(mut self, show: bool) -> Self {
+    self.cloudmaid.show_aliases = show;
+    self
+  }
+
+  pub fn collapse_apis(mut self, collapse: bool) -> Self {
+    self.cloudmaid.collapse_apis = collapse;
+    self
+  }
+
+  pub fn layers(mut self, layers: bool) -> Self {
+    self.cloudmaid.layers = layers;
+    self
+  }
+
+  pub fn edge_direction(mut self, direction: EdgeDirection) -> Self {
+    self.cloudmaid.edge_direction = Some(direction);
+    self
+  }
+
+  /// Reuses what an earlier build found in unchanged resources, as `--watch`
+  /// does between saves.
+  pub fn cache(mut self, cache: ReferenceCache) -> Self {
+    self.cloudmaid.cache = Some(cache);
+    self
+  }
+
+  pub fn build(self) -> Cloudmaid {
+    self.cloudmaid
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_builder_renders_stacks() {
+    let template: Template = serde_json::from_value(serde_json::json!({
+      "Resources": {
+        "Jobs": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Orders": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "orders" } },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": {
+            "FunctionName": "worker",
+            "Environment": { "Variables": { "QUEUE": { "Ref": "Jobs" }, "TABLE": { "Ref": "Orders" } } }
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let diagram = Cloudmaid::builder()
+      .stack(Stack {
+        name: "app".to_string(),
+        template,
+      })
+      .filter(|node| node.typ != ResourceType::DynamoDb)
+      .build()
+      .run()
+      .unwrap();
+
+    assert_eq!(diagram.stacks.len(), 1);
+    assert_eq!(diagram.graph.nodes().len(), 2);
+    assert_eq!(diagram.rendered, "```mermaid\nflowchart LR\nWorker([\"worker\"]) -->|configures| Jobs((\"jobs\"))\n```");
+  }
+
+  #[test]
+  fn test_event_source_mappings_are_filtered_while_building() {
+    let stack = Stack {
+      name: "app".to_string(),
+      template: serde_json::from_value(serde_json::json!({
+        "Resources": {
+          "Jobs": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+          "Mapping": {
+            "Type": "AWS::Lambda::EventSourceMapping",
+            "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Jobs", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+          },
+          "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker" } }
+        }
+      }))
+      .unwrap(),
+    };
+
+    let excluded = Cloudmaid::builder().exclude([ResourceType::EventSourceMapping]).build();
+    assert!(excluded.graph(std::slice::from_ref(&stack)).edges.is_empty());
+
+    let only = Cloudmaid::builder().only([ResourceType::Sqs, ResourceType::Lambda]).build();
+    assert_eq!(only.graph(&[stack]).edges.len(), 1);
+  }
+}
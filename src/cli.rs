@@ -1 +1,8 @@
-pub mod parse;
\ No newline at end of file
+pub mod parse;
+pub mod config;
+pub mod inject;
+pub mod inputs;
+pub mod pattern;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod watch;
@@ -0,0 +1,276 @@
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::ast::edge::EdgeDirection;
+use crate::ast::node::Label;
+use crate::cli::parse::Args;
+use crate::cloudformation::resource::TypeMappings;
+use crate::error::{CloudmaidError, Result};
+use crate::render::{Format, Icons};
+
+pub const FILE_NAME: &str = "cloudmaid.toml";
+
+#[derive(Debug, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+  pub input: Vec<String>,
+  pub output_file: Option<String>,
+  pub output_dir: Option<String>,
+  pub format: Option<String>,
+  pub filter: Filter,
+  pub style: Style,
+  // Extra CloudFormation types drawn as one of the known kinds, e.g.
+  // `"Custom::Queue" = "sqs"`.
+  pub types: BTreeMap<String, String>,
+  #[serde(skip)]
+  pub path: PathBuf,
+}
+
+#[derive(Debug, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Filter {
+  pub only: Vec<String>,
+  pub exclude: Vec<String>,
+  pub include_name: Vec<String>,
+  pub exclude_name: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Style {
+  pub title: Option<String>,
+  pub no_title: Option<bool>,
+  pub header: Option<bool>,
+  pub raw: Option<bool>,
+  pub label_format: Option<String>,
+  pub label: Option<String>,
+  pub layers: Option<bool>,
+  pub edge_direction: Option<String>,
+  pub icons: Option<String>,
+  // Icons for particular types, e.g. `sqs = "logos:aws-sqs"`, in the form the
+  // chosen icon set expects.
+  pub type_icons: BTreeMap<String, String>,
+}
+
+// The nearest `cloudmaid.toml` in the directory or any of its parents, so a
+// diagram can be regenerated from anywhere inside a project.
+pub fn discover(start: &Path) -> Option<PathBuf> {
+  start.ancestors().map(|dir| dir.join(FILE_NAME)).find(|path| path.is_file())
+}
+
+pub fn load(path: &Path) -> Result<Config> {
+  let contents = fs::read_to_string(path).map_err(|e| CloudmaidError::io(path, e))?;
+  let mut config: Config = toml::from_str(&contents).map_err(|e| CloudmaidError::Config {
+    path: path.to_path_buf(),
+    message: e.to_string(),
+  })?;
+  config.path = path.to_path_buf();
+  Ok(config)
+}
+
+impl Config {
+  // Flags given on the command line win; the file only fills in the rest.
+  // Paths in the file are relative to the file, not the working directory.
+  pub fn apply(&self, args: &mut Args, matches: &ArgMatches) -> Result<()> {
+    // Global flags are propagated into the subcommand, so its matches see both,
+    // while render arguments are only known to `render` or the top level.
+    let given = |matches: &ArgMatches, id: &str| {
+      matches.ids().any(|known| known == id) && matches.value_source(id) == Some(ValueSource::CommandLine)
+    };
+    let subcommand = matches.subcommand().map(|(_, matches)| matches);
+    let unset = |id: &str| !given(matches, id) && !subcommand.is_some_and(|matches| given(matches, id));
+
+    if unset("inputs") && unset("input_file") && unset("stack_name") {
+      args.render.inputs = self.input.iter().map(|input| self.path(input)).collect();
+    }
+    if unset("output_file") && unset("output_dir") && unset("inject") {
+      args.render.output_file = args.render.output_file.take().or(self.output_file.as_deref().map(|path| self.path(path)));
+      args.render.output_dir = args.render.output_dir.take().or(self.output_dir.as_deref().map(|path| self.path(path)));
+    }
+    if let Some(format) = &self.format
+      && unset("format")
+    {
+      args.format = Format::from_str(format, true).map_err(|_| self.error(format!("unknown format `{}`", format)))?;
+    }
+
+    if unset("only") {
+      args.only = self.parse_all(&self.filter.only)?;
+    }
+    if unset("exclude") {
+      args.exclude = self.parse_all(&self.filter.exclude)?;
+    }
+    if unset("include_name") {
+      args.include_name = self.parse_all(&self.filter.include_name)?;
+    }
+    if unset("exclude_name") {
+      args.exclude_name = self.parse_all(&self.filter.exclude_name)?;
+    }
+
+    let style = &self.style;
+    if unset("title") && unset("no_title") {
+      args.title = args.title.take().or(style.title.clone());
+      args.no_title |= style.no_title.unwrap_or_default();
+    }
+    // `--label` on the command line also overrides a `label-format` in the file.
+    if unset("label_format") && unset("label") {
+      args.label_format = args.label_format.take().or(style.label_format.clone());
+      if let Some(label) = &style.label {
+        args.label = Some(Label::from_str(label, true).map_err(|_| self.error(format!("unknown label `{}`", label)))?);
+      }
+    }
+    for (id, flag, value) in [
+      ("header", &mut args.header, style.header),
+      ("raw", &mut args.raw, style.raw),
+      ("layers", &mut args.layers, style.layers),
+    ] {
+      if let Some(value) = value
+        && unset(id)
+      {
+        *flag = value;
+      }
+    }
+    if let Some(direction) = &style.edge_direction
+      && unset("edge_direction")
+    {
+      let direction = EdgeDirection::from_str(direction, true).map_err(|_| self.error(format!("unknown edge direction `{}`", direction)))?;
+      args.edge_direction = Some(direction);
+    }
+    if let Some(icons) = &style.icons
+      && unset("icons")
+    {
+      args.icons = Some(Icons::from_str(icons, true).map_err(|_| self.error(format!("unknown icon set `{}`", icons)))?);
+    }
+    args.type_icons = style
+      .type_icons
+      .iter()
+      .map(|(typ, icon)| Ok((typ.parse().map_err(|e| self.error(e))?, icon.clone())))
+      .collect::<Result<_>>()?;
+    args.type_mappings = self.type_mappings()?;
+
+    Ok(())
+  }
+
+  fn type_mappings(&self) -> Result<TypeMappings> {
+    self
+      .types
+      .iter()
+      .map(|(raw_type, typ)| Ok((raw_type.clone(), typ.parse().map_err(|e| self.error(e))?)))
+      .collect()
+  }
+
+  fn path(&self, path: &str) -> String {
+    match path {
+      "-" => path.to_string(),
+      path => self.path.parent().unwrap_or(Path::new("")).join(path).display().to_string(),
+    }
+  }
+
+  fn parse_all<T: std::str::FromStr<Err = String>>(&self, values: &[String]) -> Result<Vec<T>> {
+    values.iter().map(|value| value.parse().map_err(|e| self.error(e))).collect()
+  }
+
+  fn error(&self, message: String) -> CloudmaidError {
+    CloudmaidError::Config {
+      path: self.path.clone(),
+      message,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use clap::{CommandFactory, FromArgMatches};
+
+  use crate::cloudformation::resource::ResourceType;
+
+  use super::*;
+
+  fn parse(config: &str, argv: &[&str]) -> Args {
+    let mut config: Config = toml::from_str(config).unwrap();
+    config.path = PathBuf::from("project").join(FILE_NAME);
+
+    let matches = Args::command().get_matches_from(argv);
+    let mut args = Args::from_arg_matches(&matches).unwrap();
+    config.apply(&mut args, &matches).unwrap();
+    args
+  }
+
+  #[test]
+  fn test_config_defaults() {
+    let config = r#"
+      input = ["cdk.out"]
+      output-file = "docs/architecture.md"
+      format = "plantuml"
+
+      [filter]
+      only = ["lambda", "sqs"]
+
+      [style]
+      layers = true
+      edge-direction = "dependency"
+
+      [types]
+      "Custom::Queue" = "sqs"
+    "#;
+
+    let args = parse(config, &["cloudmaid"]);
+    assert_eq!(args.inputs(), vec!["project/cdk.out".to_string()]);
+    assert_eq!(args.render.output_file.as_deref(), Some("project/docs/architecture.md"));
+    assert_eq!(args.format, Format::PlantUml);
+    assert_eq!(args.only, vec![ResourceType::Lambda, ResourceType::Sqs]);
+    assert!(args.layers);
+    assert_eq!(args.edge_direction, Some(EdgeDirection::Dependency));
+    assert_eq!(args.type_mappings, BTreeMap::from([("Custom::Queue".to_string(), ResourceType::Sqs)]));
+
+    let args = parse(config, &["cloudmaid", "app.yaml", "-o", "-", "--format", "json", "--only", "dynamodb"]);
+    assert_eq!(args.inputs(), vec!["app.yaml".to_string()]);
+    assert_eq!(args.render.output_file.as_deref(), Some("-"));
+    assert_eq!(args.format, Format::Json);
+    assert_eq!(args.only, vec![ResourceType::DynamoDb]);
+
+    let args = parse(config, &["cloudmaid", "list", "--only", "dynamodb"]);
+    assert_eq!(args.inputs(), vec!["project/cdk.out".to_string()]);
+    assert_eq!(args.format, Format::PlantUml);
+    assert_eq!(args.only, vec![ResourceType::DynamoDb]);
+  }
+
+  #[test]
+  fn test_label_shorthand() {
+    let args = parse("[style]\nlabel = \"both\"\n", &["cloudmaid"]);
+    assert_eq!(args.label, Some(Label::Both));
+    assert_eq!(args.render_options("-", None).label_format(), Label::Both.format());
+
+    let args = parse("[style]\nlabel-format = \"{type}\"\n", &["cloudmaid", "--label", "logical-id"]);
+    assert_eq!(args.render_options("-", None).label_format(), "{logical_id}");
+  }
+
+  #[test]
+  fn test_icons() {
+    let config = "[style]\nicons = \"font-awesome\"\n\n[style.type-icons]\nsqs = \"fa:fa-envelope\"\n";
+    let options = parse(config, &["cloudmaid"]).render_options("-", None);
+    assert_eq!(options.icons, Some(Icons::FontAwesome));
+    assert_eq!(options.type_icons, vec![(ResourceType::Sqs, "fa:fa-envelope".to_string())]);
+
+    assert_eq!(parse(config, &["cloudmaid", "--icons", "logos"]).icons, Some(Icons::Logos));
+  }
+
+  #[test]
+  fn test_discover_config() {
+    let root = std::env::temp_dir().join(format!("cloudmaid-config-{}", std::process::id()));
+    let nested = root.join("infra").join("stacks");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(root.join(FILE_NAME), "format = \"json\"\n").unwrap();
+
+    let found = discover(&nested);
+    let config = load(&root.join(FILE_NAME)).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(found, Some(root.join(FILE_NAME)));
+    assert_eq!(config.format.as_deref(), Some("json"));
+    assert_eq!(config.path, root.join(FILE_NAME));
+  }
+}
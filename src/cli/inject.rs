@@ -0,0 +1,50 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::{CloudmaidError, Result};
+
+pub const START: &str = "<!-- cloudmaid:start -->";
+pub const END: &str = "<!-- cloudmaid:end -->";
+
+// Everything between the markers is replaced and the markers are kept, so the
+// document can be regenerated any number of times.
+pub fn inject(document: &str, diagram: &str) -> Option<String> {
+  let start = document.find(START)? + START.len();
+  let end = start + document[start..].find(END)?;
+
+  Some(format!("{}\n{}\n{}", &document[..start], diagram.trim_end(), &document[end..]))
+}
+
+// The file is left alone when the diagram has not changed, so `--watch` and
+// build tools that look at modification times are not triggered for nothing.
+pub fn write(path: &Path, diagram: &str) -> Result<bool> {
+  let document = fs::read_to_string(path).map_err(|e| CloudmaidError::io(path, e))?;
+  let injected = inject(&document, diagram)
+    .ok_or_else(|| CloudmaidError::parse(path.display(), format!("missing `{}` and `{}` markers", START, END)))?;
+
+  if injected == document {
+    return Ok(false);
+  }
+
+  fs::write(path, injected).map_err(|e| CloudmaidError::io(path, e))?;
+  Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_inject_between_markers() {
+    let document = "# App\n\n<!-- cloudmaid:start -->\nold\n<!-- cloudmaid:end -->\n\nMore text\n";
+    let expected = "# App\n\n<!-- cloudmaid:start -->\n```mermaid\na --> b\n```\n<!-- cloudmaid:end -->\n\nMore text\n";
+
+    let injected = inject(document, "```mermaid\na --> b\n```\n").unwrap();
+    assert_eq!(injected, expected);
+    assert_eq!(inject(&injected, "```mermaid\na --> b\n```").unwrap(), expected);
+
+    assert_eq!(inject("<!-- cloudmaid:start --><!-- cloudmaid:end -->", "x").unwrap(), "<!-- cloudmaid:start -->\nx\n<!-- cloudmaid:end -->");
+    assert_eq!(inject("<!-- cloudmaid:end --> <!-- cloudmaid:start -->", "x"), None);
+    assert_eq!(inject("# App\n", "x"), None);
+  }
+}
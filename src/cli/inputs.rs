@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cloudformation::{assembly, input};
+use crate::error::{CloudmaidError, Result};
+
+const TEMPLATE_EXTENSIONS: [&str; 4] = ["json", "yaml", "yml", "template"];
+
+// Inputs are checked up front so a typo fails before any template in a batch
+// is parsed, rather than halfway through writing its outputs.
+pub fn expand(inputs: &[String]) -> Result<Vec<PathBuf>> {
+  let mut paths = Vec::new();
+
+  for input in inputs {
+    let path = Path::new(input);
+
+    if is_glob(input) {
+      let matches = glob::glob(input).map_err(|e| CloudmaidError::Usage(format!("{}: {}", input, e)))?;
+      let before = paths.len();
+      for entry in matches {
+        paths.push(entry.map_err(|e| CloudmaidError::io(e.path().to_path_buf(), e.into()))?);
+      }
+      if paths.len() == before {
+        return Err(CloudmaidError::NoMatch(input.clone()));
+      }
+    } else if input != input::STDIN && !path.exists() {
+      return Err(CloudmaidError::NotFound(input.clone()));
+    } else if path.is_dir() && !assembly::is_assembly(path) {
+      collect_templates(path, &mut paths)?;
+    } else {
+      paths.push(path.to_path_buf());
+    }
+  }
+
+  Ok(paths)
+}
+
+// `{name}` is the file stem, `{path}` the path (without extension) with
+// separators flattened so batch outputs from different directories never clash.
+pub fn output_name(pattern: &str, path: &Path) -> String {
+  let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+  let flattened = path
+    .with_extension("")
+    .components()
+    .filter_map(|c| match c {
+      std::path::Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+      _ => None,
+    })
+    .collect::<Vec<_>>()
+    .join("-");
+
+  pattern.replace("{name}", &name).replace("{path}", &flattened)
+}
+
+// The output name of every input, in order. Two inputs sharing a name, such as
+// `a/stack.json` and `b/stack.json` under `{name}.md`, would overwrite one
+// another, so that fails before anything is written.
+pub fn output_names(pattern: &str, paths: &[PathBuf]) -> Result<Vec<String>> {
+  let names: Vec<String> = paths.iter().map(|path| output_name(pattern, path)).collect();
+  let mut seen = HashMap::new();
+
+  for (path, name) in paths.iter().zip(&names) {
+    if let Some(first) = seen.insert(name, path) {
+      return Err(CloudmaidError::Usage(format!(
+        "{} and {} would both be written to {}; use an --output-name with {{path}} to tell them apart",
+        first.display(),
+        path.display(),
+        name
+      )));
+    }
+  }
+
+  Ok(names)
+}
+
+fn is_glob(input: &str) -> bool {
+  input.contains(['*', '?', '['])
+}
+
+fn collect_templates(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+  let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+    .and_then(|entries| entries.map(|entry| entry.map(|e| e.path())).collect())
+    .map_err(|e| CloudmaidError::io(dir, e))?;
+  entries.sort();
+
+  for entry in entries {
+    let hidden = entry.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.'));
+
+    if hidden {
+      continue;
+    } else if assembly::is_assembly(&entry) {
+      paths.push(entry);
+    } else if entry.is_dir() {
+      collect_templates(&entry, paths)?;
+    } else if entry
+      .extension()
+      .is_some_and(|ext| TEMPLATE_EXTENSIONS.contains(&ext.to_string_lossy().as_ref()))
+    {
+      paths.push(entry);
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_output_name() {
+    let path = Path::new("stacks/api/service.template.json");
+
+    assert_eq!(output_name("{name}.md", path), "service.template.md");
+    assert_eq!(output_name("{path}.mmd", path), "stacks-api-service.template.mmd");
+  }
+
+  #[test]
+  fn test_output_names_clash() {
+    let paths = [PathBuf::from("a/stack.json"), PathBuf::from("b/stack.json")];
+
+    let error = output_names("{name}.md", &paths).unwrap_err();
+
+    assert_eq!(
+      error.to_string(),
+      "a/stack.json and b/stack.json would both be written to stack.md; use an --output-name with {path} to tell them apart"
+    );
+    assert_eq!(output_names("{path}.md", &paths).unwrap(), ["a-stack.md", "b-stack.md"]);
+  }
+
+  #[test]
+  fn test_expand_directory() {
+    let dir = std::env::temp_dir().join(format!("cloudmaid-inputs-{}", std::process::id()));
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    fs::write(dir.join("a.json"), "{}").unwrap();
+    fs::write(dir.join("nested").join("b.yaml"), "{}").unwrap();
+    fs::write(dir.join("README.md"), "").unwrap();
+
+    let paths = expand(&[dir.display().to_string()]).unwrap();
+    let globbed = expand(&[format!("{}/*.json", dir.display())]).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(paths, vec![dir.join("a.json"), dir.join("nested").join("b.yaml")]);
+    assert_eq!(globbed, vec![dir.join("a.json")]);
+  }
+
+  #[test]
+  fn test_expand_missing_input() {
+    let missing = std::env::temp_dir().join(format!("cloudmaid-missing-{}", std::process::id()));
+
+    let error = expand(&[missing.display().to_string()]).unwrap_err();
+    let unmatched = expand(&[format!("{}/*.json", missing.display())]).unwrap_err();
+
+    assert_eq!(error.to_string(), format!("{}: no such file or directory", missing.display()));
+    assert!(matches!(unmatched, CloudmaidError::NoMatch(_)));
+    assert_eq!(expand(&[input::STDIN.to_string()]).unwrap(), vec![PathBuf::from(input::STDIN)]);
+  }
+}
@@ -1,11 +1,433 @@
-use clap::Parser;
+use clap::{ArgAction, Parser, Subcommand};
+use clap_complete::Shell;
+use std::sync::Arc;
 
+use crate::ast::cache::ReferenceCache;
+use crate::ast::node::{Label, Node};
+use crate::ast::edge::EdgeDirection;
+use crate::ast::query::Direction;
+use crate::ast::split::SplitBy;
+use crate::cli::pattern::NamePattern;
+use crate::cloudformation::resource::{ResourceType, TypeMappings};
+use crate::render::{Format, Icons, KeepPolicy, RenderOptions};
+
+// Rendering is the default, so its arguments are accepted both after `render`
+// and without a subcommand at all.
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 pub struct Args {
-  #[arg(short, long)]
-  pub input_file: String,
+  #[command(subcommand)]
+  pub command: Option<Command>,
+
+  #[command(flatten)]
+  pub render: RenderArgs,
+
+  #[arg(short, long, action = ArgAction::Count, global = true)]
+  pub verbose: u8,
+
+  #[arg(short, long, conflicts_with = "verbose", global = true)]
+  pub quiet: bool,
+
+  #[arg(long, value_name = "FILE", conflicts_with = "no_config", global = true)]
+  pub config: Option<String>,
+
+  #[arg(long, global = true)]
+  pub no_config: bool,
+
+  #[arg(short, long, value_enum, default_value_t = Format::Mermaid, global = true)]
+  pub format: Format,
+
+  #[arg(long, global = true)]
+  pub raw: bool,
+
+  #[arg(long, conflicts_with = "no_title", global = true)]
+  pub title: Option<String>,
+
+  #[arg(long, global = true)]
+  pub no_title: bool,
+
+  #[arg(long, global = true)]
+  pub header: bool,
+
+  #[arg(long, value_name = "TEMPLATE", global = true)]
+  pub label_format: Option<String>,
+
+  #[arg(long, value_enum, conflicts_with = "label_format", global = true)]
+  pub label: Option<Label>,
+
+  #[arg(long, value_enum, global = true)]
+  pub icons: Option<Icons>,
+
+  // Only set from `[style.type-icons]` in the config file.
+  #[arg(skip)]
+  pub type_icons: Vec<(ResourceType, String)>,
+
+  // Only set from `[types]` in the config file.
+  #[arg(skip)]
+  pub type_mappings: TypeMappings,
+
+  // Kept from one render to the next while watching.
+  #[arg(skip)]
+  pub cache: Option<ReferenceCache>,
+
+  #[arg(long, value_enum, value_delimiter = ',', value_name = "TYPES", ignore_case = true, global = true)]
+  pub only: Vec<ResourceType>,
+
+  #[arg(long, value_enum, value_delimiter = ',', value_name = "TYPES", ignore_case = true, global = true)]
+  pub exclude: Vec<ResourceType>,
+
+  #[arg(long, value_name = "PATTERN", global = true)]
+  pub include_name: Vec<NamePattern>,
+
+  #[arg(long, value_name = "PATTERN", global = true)]
+  pub exclude_name: Vec<NamePattern>,
+
+  #[arg(long, global = true)]
+  pub layers: bool,
+
+  #[arg(long, global = true)]
+  pub show_depends_on: bool,
+
+  #[arg(long, global = true)]
+  pub show_missing: bool,
+
+  #[arg(long, global = true)]
+  pub show_parameters: bool,
+
+  #[arg(long, global = true)]
+  pub show_permissions: bool,
+
+  #[arg(long, global = true)]
+  pub collapse_apis: bool,
+
+  #[arg(long, global = true)]
+  pub show_aliases: bool,
+
+  #[arg(long, value_enum, global = true)]
+  pub edge_direction: Option<EdgeDirection>,
+
+  #[arg(long, value_name = "KEY=VALUE", value_parser = key_value, global = true)]
+  pub parameter: Vec<(String, String)>,
+
+  #[arg(long, global = true)]
+  pub nested_stacks: bool,
+
+  #[arg(long, value_name = "URL=PATH", value_parser = key_value, global = true)]
+  pub template_map: Vec<(String, String)>,
+
+  #[arg(long, global = true)]
+  pub profile: Option<String>,
+
+  #[arg(long, global = true)]
+  pub region: Option<String>,
+
+  #[arg(long, global = true)]
+  pub account_id: Option<String>,
+}
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct RenderArgs {
+  #[arg(value_name = "INPUT")]
+  pub inputs: Vec<String>,
+
+  #[arg(short, long, num_args = 1..)]
+  pub input_file: Vec<String>,
 
   #[arg(short, long)]
-  pub output_file: String,
+  pub output_file: Option<String>,
+
+  #[arg(long)]
+  pub output_dir: Option<String>,
+
+  #[arg(long, value_name = "FILE", conflicts_with_all = ["output_file", "output_dir"])]
+  pub inject: Option<String>,
+
+  #[arg(long, conflicts_with_all = ["output_file", "output_dir", "index", "inject", "watch"])]
+  pub share: bool,
+
+  #[arg(long)]
+  pub output_name: Option<String>,
+
+  #[arg(long)]
+  pub index: Option<String>,
+
+  // Templates rendered at once with `--output-dir`; one per CPU by default.
+  #[arg(short, long, value_name = "N")]
+  pub jobs: Option<usize>,
+
+  #[arg(long)]
+  pub watch: bool,
+
+  #[arg(long)]
+  pub stack_name: Vec<String>,
+
+  // Where `sequence` starts.
+  #[arg(long, value_name = "RESOURCE")]
+  pub entry: Option<String>,
+
+  // The state machine `statediagram` draws. `focus` and `blast-radius` take a
+  // `--resource` of their own, so neither is global.
+  #[arg(long, value_name = "RESOURCE")]
+  pub resource: Option<String>,
+
+  // `tag:<key>` or `prefix`; each part is written next to the output file.
+  #[arg(long, value_name = "BY", conflicts_with_all = ["inject", "share"])]
+  pub split_by: Option<SplitBy>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+  #[command(about = "Render templates to a diagram (the default)")]
+  Render(RenderArgs),
+  #[command(about = "Print shell completions to stdout")]
+  Completions {
+    shell: Shell,
+  },
+  #[command(about = "List the resources that would be drawn")]
+  List {
+    // The config file's `input` when none are given.
+    inputs: Vec<String>,
+
+    #[arg(long)]
+    json: bool,
+
+    #[arg(short, long, default_value = "-")]
+    output_file: String,
+  },
+  #[command(about = "Check that templates parse and render to valid mermaid, and warn about cycles")]
+  Validate {
+    // The config file's `input` when none are given.
+    inputs: Vec<String>,
+  },
+  #[command(about = "Show what changed between two templates")]
+  Diff {
+    old: String,
+
+    new: String,
+
+    #[arg(short, long, default_value = "-")]
+    output_file: String,
+  },
+  #[command(about = "Render the neighbourhood of one resource")]
+  Focus {
+    // The config file's `input` when none are given.
+    inputs: Vec<String>,
+
+    #[arg(long)]
+    resource: String,
+
+    #[arg(long, default_value_t = 1)]
+    depth: usize,
+
+    #[arg(short, long, default_value = "-")]
+    output_file: String,
+  },
+  #[command(about = "Find paths between two resources")]
+  Path {
+    // The config file's `input` when none are given.
+    inputs: Vec<String>,
+
+    #[arg(long)]
+    from: String,
+
+    #[arg(long)]
+    to: String,
+
+    // The shortest ones are kept; a busy stack has too many to list them all.
+    #[arg(long, default_value_t = 10)]
+    limit: usize,
+
+    #[arg(short, long, default_value = "-")]
+    output_file: String,
+
+    #[arg(long, value_name = "FILE")]
+    diagram: Option<String>,
+  },
+  #[command(about = "Render everything a resource affects or depends on")]
+  BlastRadius {
+    // The config file's `input` when none are given.
+    inputs: Vec<String>,
+
+    #[arg(long)]
+    resource: String,
+
+    #[arg(long, value_enum, default_value_t = Direction::Both)]
+    direction: Direction,
+
+    #[arg(short, long, default_value = "-")]
+    output_file: String,
+  },
+  #[command(about = "Count resources, edges and the busiest nodes")]
+  Stats {
+    // The config file's `input` when none are given.
+    inputs: Vec<String>,
+
+    #[arg(long)]
+    json: bool,
+
+    #[arg(short, long, default_value = "-")]
+    output_file: String,
+  },
+  #[command(about = "Explore resources interactively and export a subgraph")]
+  Tui {
+    // The config file's `input` when none are given.
+    inputs: Vec<String>,
+
+    // Defaults to `explorer.<extension>`; stdout is taken by the terminal UI.
+    #[arg(short, long)]
+    output_file: Option<String>,
+  },
+  #[command(about = "Write a markdown page per resource, with its edges and neighbours, and an index")]
+  Docs {
+    // The config file's `input` when none are given.
+    inputs: Vec<String>,
+
+    #[arg(long, default_value = "docs")]
+    output_dir: String,
+  },
+  #[command(about = "Write a markdown summary for a PR or CI job: the diagram, changes against a base and the resources")]
+  Summary {
+    // The config file's `input` when none are given.
+    inputs: Vec<String>,
+
+    #[arg(long, value_name = "TEMPLATE")]
+    base: Option<String>,
+
+    #[arg(short, long, default_value = "-")]
+    output_file: String,
+
+    // `$GITHUB_STEP_SUMMARY` collects the output of every step in a job.
+    #[arg(long)]
+    append: bool,
+  },
+  #[command(about = "Report orphaned resources and cycles")]
+  Analyze {
+    // The config file's `input` when none are given.
+    inputs: Vec<String>,
+
+    #[arg(short, long, default_value = "-")]
+    output_file: String,
+  },
+}
+
+impl Args {
+  pub fn render_options(&self, output_file: &str, default_title: Option<&str>) -> RenderOptions {
+    RenderOptions {
+      raw: self.raw || output_file.ends_with(".mmd"),
+      title: match self.no_title {
+        true => None,
+        false => self.title.clone().or(default_title.map(str::to_string)),
+      },
+      header: self.header.then(|| self.header_comment()),
+      label_format: self.label_format.clone().or(self.label.map(|label| label.format().to_string())),
+      highlight: Vec::new(),
+      ghosts: Vec::new(),
+      keep: self.keep_policy(),
+      entry: self.render.entry.clone(),
+      resource: self.render.resource.clone(),
+      icons: self.icons,
+      type_icons: self.type_icons.clone(),
+    }
+  }
+
+  fn header_comment(&self) -> String {
+    let mut sources = self.inputs();
+    sources.extend(self.render.stack_name.iter().map(|name| format!("stack:{}", name)));
+
+    format!(
+      "Generated by cloudmaid {} from {} at {}",
+      env!("CARGO_PKG_VERSION"),
+      sources.join(", "),
+      chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    )
+  }
+
+  pub fn keeps(&self, node: &Node) -> bool {
+    self.keep_policy().keeps(node)
+  }
+
+  // `--only` and `--exclude` map onto the type lists; the name patterns, which
+  // match either the logical ID or the physical name, become the predicate.
+  pub fn keep_policy(&self) -> KeepPolicy {
+    let mut policy = KeepPolicy {
+      allow: self.only.clone(),
+      deny: self.exclude.clone(),
+      predicate: None,
+    };
+
+    if !self.include_name.is_empty() || !self.exclude_name.is_empty() {
+      let (include, exclude) = (self.include_name.clone(), self.exclude_name.clone());
+      policy.predicate = Some(Arc::new(move |node: &Node| {
+        let names = [node.name.0.to_string(), node.get_name().to_string()];
+        let matches = |pattern: &NamePattern| names.iter().any(|name| pattern.matches(name));
+        (include.is_empty() || include.iter().any(matches)) && !exclude.iter().any(matches)
+      }));
+    }
+    policy
+  }
+
+  pub fn output_name(&self) -> String {
+    match &self.render.output_name {
+      Some(output_name) => output_name.clone(),
+      None => format!("{{name}}.{}", self.format.extension()),
+    }
+  }
+
+  // Progress is reported at info, so that is the default; `-v` adds what was
+  // recognized or skipped and `-vv` every edge.
+  pub fn log_level(&self) -> tracing::Level {
+    match (self.quiet, self.verbose) {
+      (true, _) => tracing::Level::ERROR,
+      (false, 0) => tracing::Level::INFO,
+      (false, 1) => tracing::Level::DEBUG,
+      (false, _) => tracing::Level::TRACE,
+    }
+  }
+
+  pub fn inputs(&self) -> Vec<String> {
+    self.render.inputs.iter().chain(self.render.input_file.iter()).cloned().collect()
+  }
+}
+
+// A pair given without `=` is a mistake rather than something to drop quietly.
+fn key_value(pair: &str) -> Result<(String, String), String> {
+  match pair.split_once('=') {
+    Some((key, value)) => Ok((key.to_string(), value.to_string())),
+    None => Err(format!("expected `=` in `{}`", pair)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_log_level() {
+    let level = |argv: &[&str]| Args::parse_from(argv).log_level();
+
+    assert_eq!(level(&["cloudmaid", "app.yaml"]), tracing::Level::INFO);
+    assert_eq!(level(&["cloudmaid", "app.yaml", "-v"]), tracing::Level::DEBUG);
+    assert_eq!(level(&["cloudmaid", "stats", "app.yaml", "-vvv"]), tracing::Level::TRACE);
+    assert_eq!(level(&["cloudmaid", "app.yaml", "--quiet"]), tracing::Level::ERROR);
+  }
+
+  #[test]
+  fn test_completions_offer_values() {
+    let mut script = Vec::new();
+    clap_complete::generate(Shell::Bash, &mut <Args as clap::CommandFactory>::command(), "cloudmaid", &mut script);
+    let script = String::from_utf8(script).unwrap();
+
+    assert!(script.contains("plantuml"));
+    assert!(script.contains("apigateway"));
+    assert_eq!(Args::parse_from(["cloudmaid", "app.yaml", "--only", "DynamoDB,sqs"]).only, vec![ResourceType::DynamoDb, ResourceType::Sqs]);
+  }
+
+  #[test]
+  fn test_pairs_need_a_value() {
+    let args = Args::try_parse_from(["cloudmaid", "app.yaml", "--parameter", "Env=prod", "--parameter", "Url=a=b"]).unwrap();
+    assert_eq!(args.parameter, vec![("Env".to_string(), "prod".to_string()), ("Url".to_string(), "a=b".to_string())]);
+
+    assert!(Args::try_parse_from(["cloudmaid", "app.yaml", "--parameter", "Env"]).is_err());
+    assert!(Args::try_parse_from(["cloudmaid", "app.yaml", "--template-map", "https://example.com/db.yaml"]).is_err());
+  }
 }
@@ -0,0 +1,46 @@
+use regex::Regex;
+
+// Patterns are regular expressions matched against the whole name; a `glob:`
+// prefix switches to shell-style wildcards for the common `*Canary*` case.
+#[derive(Debug, Clone)]
+pub enum NamePattern {
+  Regex(Regex),
+  Glob(glob::Pattern),
+}
+
+impl std::str::FromStr for NamePattern {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.strip_prefix("glob:") {
+      Some(glob) => glob::Pattern::new(glob).map(NamePattern::Glob).map_err(|e| e.to_string()),
+      None => Regex::new(&format!("^(?:{})$", s)).map(NamePattern::Regex).map_err(|e| e.to_string()),
+    }
+  }
+}
+
+impl NamePattern {
+  pub fn matches(&self, name: &str) -> bool {
+    match self {
+      NamePattern::Regex(regex) => regex.is_match(name),
+      NamePattern::Glob(glob) => glob.matches(name),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_name_patterns() {
+    let regex: NamePattern = ".*Canary.*".parse().unwrap();
+    let glob: NamePattern = "glob:LogRetention*".parse().unwrap();
+
+    assert!(regex.matches("ApiCanaryFunction"));
+    assert!(!regex.matches("ApiFunction"));
+    assert!(glob.matches("LogRetentionaae0aa3c"));
+    assert!(!glob.matches("MyLogRetention"));
+    assert!("(".parse::<NamePattern>().is_err());
+  }
+}
@@ -0,0 +1,311 @@
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::ast::ast::AST;
+use crate::ast::node::Node;
+use crate::cloudformation::resource::ResourceType;
+use crate::error::{CloudmaidError, Result};
+
+const HELP: &str = "↑/↓ move  space add  enter expand  / filter  t type  c clear  x export  q quit";
+
+#[derive(Debug, PartialEq)]
+pub enum Action {
+  Continue,
+  Export,
+  Quit,
+}
+
+// The subgraph is built up by hand: nodes are added one at a time or with
+// their neighbours, and the list is narrowed by name and type to find them.
+// Until anything is added, everything the filters match is exported.
+pub struct Explorer {
+  ast: AST,
+  nodes: Vec<Node>,
+  types: Vec<ResourceType>,
+  subgraph: Vec<Node>,
+  filter: String,
+  typ: Option<ResourceType>,
+  editing: bool,
+  selected: usize,
+  status: String,
+}
+
+impl Explorer {
+  pub fn new(ast: AST) -> Self {
+    let mut nodes: Vec<Node> = ast.nodes().into_iter().cloned().collect();
+    nodes.sort_by(|a, b| a.name.0.cmp(&b.name.0));
+
+    let mut types: Vec<ResourceType> = Vec::new();
+    for node in &nodes {
+      if !types.contains(&node.typ) {
+        types.push(node.typ.clone());
+      }
+    }
+    types.sort_by_key(|typ| typ.to_string());
+
+    Explorer {
+      ast,
+      nodes,
+      types,
+      subgraph: Vec::new(),
+      filter: String::new(),
+      typ: None,
+      editing: false,
+      selected: 0,
+      status: String::new(),
+    }
+  }
+
+  pub fn handle(&mut self, key: KeyCode) -> Action {
+    if self.editing {
+      match key {
+        KeyCode::Char(c) => self.filter.push(c),
+        KeyCode::Backspace => {
+          self.filter.pop();
+        }
+        KeyCode::Esc => {
+          self.filter.clear();
+          self.editing = false;
+        }
+        KeyCode::Enter => self.editing = false,
+        _ => {}
+      }
+      self.selected = 0;
+      return Action::Continue;
+    }
+
+    match key {
+      KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+      KeyCode::Char('x') => return Action::Export,
+      KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+      KeyCode::Down | KeyCode::Char('j') => {
+        self.selected = (self.selected + 1).min(self.matches().len().saturating_sub(1))
+      }
+      KeyCode::Char(' ') => self.toggle(),
+      KeyCode::Enter | KeyCode::Char('e') => self.expand(),
+      KeyCode::Char('/') => self.editing = true,
+      KeyCode::Char('t') => self.next_type(),
+      KeyCode::Char('c') => self.subgraph.clear(),
+      _ => {}
+    }
+    Action::Continue
+  }
+
+  pub fn subgraph(&self) -> AST {
+    let keep: Vec<&Node> = match self.subgraph.is_empty() {
+      true => self.matches(),
+      false => self.subgraph.iter().collect(),
+    };
+
+    let mut ast = self.ast.clone();
+    ast.retain(|node| keep.contains(&node));
+    ast
+  }
+
+  fn matches(&self) -> Vec<&Node> {
+    let filter = self.filter.to_lowercase();
+    self
+      .nodes
+      .iter()
+      .filter(|node| self.typ.as_ref().is_none_or(|typ| &node.typ == typ))
+      .filter(|node| {
+        node.name.0.to_lowercase().contains(&filter) || node.get_name().to_lowercase().contains(&filter)
+      })
+      .collect()
+  }
+
+  fn current(&self) -> Option<Node> {
+    self.matches().get(self.selected).map(|node| (*node).clone())
+  }
+
+  fn toggle(&mut self) {
+    let Some(node) = self.current() else {
+      return;
+    };
+
+    match self.subgraph.iter().position(|added| added == &node) {
+      Some(index) => {
+        self.subgraph.remove(index);
+      }
+      None => self.subgraph.push(node),
+    }
+  }
+
+  fn expand(&mut self) {
+    let Some(node) = self.current() else {
+      return;
+    };
+
+    for neighbour in self.ast.neighbourhood(&node, 1) {
+      if !self.subgraph.contains(&neighbour) {
+        self.subgraph.push(neighbour);
+      }
+    }
+  }
+
+  fn next_type(&mut self) {
+    let next = match &self.typ {
+      None => 0,
+      Some(typ) => self.types.iter().position(|t| t == typ).map_or(0, |index| index + 1),
+    };
+    self.typ = self.types.get(next).cloned();
+    self.selected = 0;
+  }
+
+  fn details(&self, node: &Node) -> Vec<Line<'static>> {
+    let mut lines = vec![
+      Line::from(format!("logical id  {}", node.name.0)),
+      Line::from(format!("name        {}", node.get_name())),
+      Line::from(format!("type        {}", node.typ)),
+    ];
+    if let Some(group) = self.ast.group_of(node) {
+      lines.push(Line::from(format!("group       {}", group)));
+    }
+    lines.push(Line::from(""));
+
+    for edge in self.ast.edges.iter().chain(self.ast.cross_stack_edges.iter()) {
+      let relationship = edge.detail.clone().unwrap_or_else(|| edge.relationship.to_string());
+      if edge.from == *node {
+        lines.push(Line::from(format!("→ {} {}", relationship, edge.to.get_name())));
+      } else if edge.to == *node {
+        lines.push(Line::from(format!("← {} {}", relationship, edge.from.get_name())));
+      }
+    }
+    lines
+  }
+
+  fn draw(&self, frame: &mut Frame) {
+    let [main, status] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    let [list, details] = Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(main);
+
+    let matches = self.matches();
+    let items: Vec<ListItem> = matches
+      .iter()
+      .map(|node| {
+        let marker = if self.subgraph.contains(node) { "●" } else { " " };
+        ListItem::new(format!("{} {} ({})", marker, node.get_name(), node.typ))
+      })
+      .collect();
+    let title = match &self.typ {
+      Some(typ) => format!(" Resources {}/{} · {} ", matches.len(), self.nodes.len(), typ),
+      None => format!(" Resources {}/{} ", matches.len(), self.nodes.len()),
+    };
+    let mut state = ListState::default().with_selected(Some(self.selected));
+    frame.render_stateful_widget(
+      List::new(items)
+        .block(Block::bordered().title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+      list,
+      &mut state,
+    );
+
+    let lines = self.current().map(|node| self.details(&node)).unwrap_or_default();
+    let title = format!(" Subgraph: {} resources ", self.subgraph.len());
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), details);
+
+    let line = match (self.editing, self.status.is_empty()) {
+      (true, _) => format!("/{}", self.filter),
+      (false, false) => self.status.clone(),
+      (false, true) => HELP.to_string(),
+    };
+    frame.render_widget(Paragraph::new(line), status);
+  }
+}
+
+pub fn run(ast: AST, export: impl FnMut(&AST) -> Result<()>) -> Result<()> {
+  let mut terminal = ratatui::init();
+  let result = explore(&mut terminal, Explorer::new(ast), export);
+  ratatui::restore();
+  result
+}
+
+fn explore(terminal: &mut DefaultTerminal, mut explorer: Explorer, mut export: impl FnMut(&AST) -> Result<()>) -> Result<()> {
+  let terminal_error = |e| CloudmaidError::io("terminal", e);
+
+  loop {
+    terminal.draw(|frame| explorer.draw(frame)).map_err(terminal_error)?;
+
+    let Event::Key(key) = event::read().map_err(terminal_error)? else {
+      continue;
+    };
+    if key.kind != KeyEventKind::Press {
+      continue;
+    }
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+      return Ok(());
+    }
+
+    explorer.status.clear();
+    match explorer.handle(key.code) {
+      Action::Continue => {}
+      Action::Quit => return Ok(()),
+      Action::Export => {
+        let subgraph = explorer.subgraph();
+        explorer.status = match export(&subgraph) {
+          Ok(()) => format!("Exported {} resources", subgraph.nodes().len()),
+          Err(e) => format!("error: {}", e),
+        };
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  fn explorer() -> Explorer {
+    let template: Template = serde_json::from_value(serde_json::json!({
+      "Resources": {
+        "Jobs": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Orders": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "orders" } },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": { "FunctionName": "worker", "Environment": { "Variables": { "QUEUE": { "Ref": "Jobs" } } } }
+        },
+        "Reader": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": { "FunctionName": "reader", "Environment": { "Variables": { "TABLE": { "Ref": "Orders" } } } }
+        }
+      }
+    }))
+    .unwrap();
+    Explorer::new(AST::from(template))
+  }
+
+  fn names(ast: &AST) -> Vec<String> {
+    let mut names: Vec<String> = ast.nodes().iter().map(|node| node.name.0.to_string()).collect();
+    names.sort();
+    names
+  }
+
+  #[test]
+  fn test_explorer_filters_and_expands() {
+    let mut explorer = explorer();
+    assert_eq!(names(&explorer.subgraph()), vec!["Jobs", "Orders", "Reader", "Worker"]);
+
+    for key in [KeyCode::Char('/'), KeyCode::Char('W'), KeyCode::Char('o'), KeyCode::Enter] {
+      assert_eq!(explorer.handle(key), Action::Continue);
+    }
+    assert_eq!(explorer.current().map(|node| node.name.0.to_string()), Some("Worker".to_string()));
+
+    explorer.handle(KeyCode::Enter);
+    assert_eq!(names(&explorer.subgraph()), vec!["Jobs", "Worker"]);
+
+    explorer.handle(KeyCode::Char('c'));
+    explorer.handle(KeyCode::Char('/'));
+    explorer.handle(KeyCode::Esc);
+    explorer.handle(KeyCode::Char('t'));
+    assert_eq!(explorer.matches().len(), 1);
+    assert_eq!(explorer.typ, Some(ResourceType::DynamoDb));
+
+    assert_eq!(explorer.handle(KeyCode::Char('x')), Action::Export);
+    assert_eq!(explorer.handle(KeyCode::Char('q')), Action::Quit);
+  }
+}
@@ -0,0 +1,73 @@
+use notify::{Event, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::cloudformation::input;
+use crate::error::{CloudmaidError, Result};
+
+// Editors replace files rather than writing them in place, and `cdk synth`
+// rewrites a whole directory, so parent directories are watched and a burst of
+// events is let settle before rendering once.
+const SETTLE: Duration = Duration::from_millis(200);
+
+pub fn watch(paths: &[PathBuf], mut render: impl FnMut()) -> Result<()> {
+  let targets: Vec<PathBuf> = paths
+    .iter()
+    .filter(|path| path.to_str() != Some(input::STDIN))
+    .map(|path| fs::canonicalize(path).map_err(|e| CloudmaidError::io(path, e)))
+    .collect::<Result<_>>()?;
+
+  if targets.is_empty() {
+    return Err(CloudmaidError::Usage("--watch needs a file or directory, not stdin".to_string()));
+  }
+
+  let (sender, receiver) = mpsc::channel();
+  let mut watcher = notify::recommended_watcher(sender)?;
+
+  for target in &targets {
+    let (dir, mode) = match target.is_dir() {
+      true => (target.as_path(), RecursiveMode::Recursive),
+      false => (target.parent().unwrap_or(Path::new(".")), RecursiveMode::NonRecursive),
+    };
+    watcher.watch(dir, mode)?;
+  }
+
+  while let Ok(event) = receiver.recv() {
+    if !touches(&event, &targets) {
+      continue;
+    }
+    while receiver.recv_timeout(SETTLE).is_ok() {}
+    render();
+  }
+
+  Ok(())
+}
+
+fn touches(event: &notify::Result<Event>, targets: &[PathBuf]) -> bool {
+  let Ok(event) = event else {
+    return false;
+  };
+
+  !event.kind.is_access() && event.paths.iter().any(|path| targets.iter().any(|target| path.starts_with(target)))
+}
+
+#[cfg(test)]
+mod tests {
+  use notify::EventKind;
+  use notify::event::{AccessKind, ModifyKind};
+
+  use super::*;
+
+  #[test]
+  fn test_touches_watched_inputs() {
+    let targets = vec![PathBuf::from("/stacks/app.yaml"), PathBuf::from("/stacks/cdk.out")];
+    let event = |kind: EventKind, path: &str| Ok(Event::new(kind).add_path(PathBuf::from(path)));
+
+    assert!(touches(&event(EventKind::Modify(ModifyKind::Any), "/stacks/app.yaml"), &targets));
+    assert!(touches(&event(EventKind::Modify(ModifyKind::Any), "/stacks/cdk.out/App.template.json"), &targets));
+    assert!(!touches(&event(EventKind::Modify(ModifyKind::Any), "/stacks/app.md"), &targets));
+    assert!(!touches(&event(EventKind::Access(AccessKind::Any), "/stacks/app.yaml"), &targets));
+  }
+}
@@ -1,3 +1,18 @@
 pub mod template;
 pub mod resource;
-pub mod property;
\ No newline at end of file
+pub mod property;
+pub mod yaml;
+pub(crate) mod sam;
+pub(crate) mod alias;
+pub mod output;
+pub mod parameter;
+pub(crate) mod resolve;
+pub(crate) mod intrinsic;
+pub(crate) mod dynamic;
+pub(crate) mod arn;
+pub mod stack;
+pub mod assembly;
+pub mod input;
+pub mod nested;
+#[cfg(feature = "aws")]
+pub mod live;
\ No newline at end of file
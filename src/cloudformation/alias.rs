@@ -0,0 +1,155 @@
+use rayon::prelude::*;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::debug;
+
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::{Resource, ResourceContentsRaw, parse_properties};
+
+const VERSION: &str = "AWS::Lambda::Version";
+const ALIAS: &str = "AWS::Lambda::Alias";
+const SERVERLESS_FUNCTION: &str = "AWS::Serverless::Function";
+
+// Versions and aliases are just other names for a function, so they are
+// dropped and every reference to one is pointed at the function itself. The
+// alias names are kept per function for labelling.
+//
+// Resources are noted one at a time as they are read, before their properties
+// are parsed, and the references are only rewritten once the whole template is
+// known, since an alias can be declared after the resources that use it.
+#[derive(Debug, Default)]
+pub struct Collapse {
+  targets: BTreeMap<String, String>,
+  aliases: BTreeMap<String, Vec<String>>,
+}
+
+impl Collapse {
+  pub fn note(&mut self, key: &str, raw: &ResourceContentsRaw) {
+    // Only the few resources that can name an alias have their properties
+    // parsed here.
+    if ![VERSION, ALIAS, SERVERLESS_FUNCTION].contains(&raw.typ.as_str()) {
+      return;
+    }
+    let properties: Value = serde_json::from_str(raw.properties()).unwrap_or_default();
+    let function = properties.get("FunctionName").and_then(function_reference);
+
+    match (raw.typ.as_str(), function) {
+      (VERSION, Some(function)) => {
+        self.targets.insert(key.to_string(), function);
+      }
+      (ALIAS, Some(function)) => {
+        if let Some(name) = properties.get("Name").and_then(Value::as_str) {
+          self.aliases.entry(function.clone()).or_default().push(name.to_string());
+        }
+        self.targets.insert(key.to_string(), function);
+      }
+      _ => {}
+    }
+
+    // SAM names the alias it publishes `{Function}Alias{Name}`.
+    if let Some(name) = properties.get("AutoPublishAlias").and_then(Value::as_str) {
+      self.aliases.entry(key.to_string()).or_default().push(name.to_string());
+      self.targets.insert(format!("{}Alias{}", key, name), key.to_string());
+    }
+  }
+
+  // Properties have already been read by now, so the few templates with
+  // aliases have theirs turned back into JSON to be rewritten.
+  pub fn apply(self, resources: &mut Vec<Resource>) -> Result<(), serde_json::Error> {
+    for resource in resources.iter_mut() {
+      if let Some(aliases) = self.aliases.get(&*resource.name.0) {
+        resource.aliases = aliases.clone();
+      }
+    }
+
+    if self.targets.is_empty() {
+      return Ok(());
+    }
+    for (alias, function) in &self.targets {
+      debug!("{}: merged into function {}", alias, function);
+    }
+
+    resources.retain(|resource| !self.targets.contains_key(&*resource.name.0));
+    let targets = &self.targets;
+    resources.par_iter_mut().try_for_each(|resource| {
+      match &*resource.properties {
+        // Properties left as text stay that way unless they name an alias.
+        Property::Unparsed(properties) if !targets.keys().any(|alias| properties.text().contains(alias.as_str())) => {}
+        Property::Unparsed(properties) => {
+          let mut properties = properties.value().clone();
+          rewrite(&mut properties, targets);
+          resource.properties = Property::Unparsed(properties.into()).into();
+        }
+        _ => {
+          let mut properties = serde_json::to_value(&*resource.properties)?;
+          rewrite(&mut properties, targets);
+          resource.properties = parse_properties(resource.typ.clone(), properties)?.into();
+        }
+      }
+
+      for name in resource.depends_on.iter_mut() {
+        if let Some(function) = targets.get(name.as_str()) {
+          *name = function.clone();
+        }
+      }
+      Ok(())
+    })
+  }
+}
+
+fn function_reference(value: &Value) -> Option<String> {
+  match value {
+    Value::Object(map) => match (map.get("Ref"), map.get("Fn::GetAtt")) {
+      (Some(Value::String(name)), _) => Some(name.clone()),
+      (_, Some(Value::Array(args))) => args.first()?.as_str().map(str::to_string),
+      (_, Some(Value::String(path))) => path.split('.').next().map(str::to_string),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+fn rewrite(value: &mut Value, targets: &BTreeMap<String, String>) {
+  match value {
+    Value::Object(map) => {
+      for (key, value) in map.iter_mut() {
+        match (key.as_str(), value) {
+          ("Fn::GetAtt", Value::Array(args)) => {
+            if let Some(function) = args.first().and_then(Value::as_str).and_then(|name| targets.get(name)) {
+              *args = vec![Value::String(function.clone()), Value::String("Arn".to_string())];
+            }
+          }
+          ("Fn::GetAtt", Value::String(path)) => {
+            if let Some(function) = path.split('.').next().and_then(|name| targets.get(name)) {
+              *path = format!("{}.Arn", function);
+            }
+          }
+          ("Fn::Sub", Value::String(template)) => *template = rewrite_sub(template, targets),
+          ("Fn::Sub", Value::Array(args)) => {
+            if let Some(Value::String(template)) = args.first_mut() {
+              *template = rewrite_sub(template, targets);
+            }
+            args.iter_mut().skip(1).for_each(|arg| rewrite(arg, targets));
+          }
+          ("Ref", Value::String(name)) => {
+            if let Some(function) = targets.get(name.as_str()) {
+              *name = function.clone();
+            }
+          }
+          (_, value @ (Value::Object(_) | Value::Array(_))) => rewrite(value, targets),
+          _ => {}
+        }
+      }
+    }
+    Value::Array(items) => items.iter_mut().for_each(|item| rewrite(item, targets)),
+    _ => {}
+  }
+}
+
+fn rewrite_sub(template: &str, targets: &BTreeMap<String, String>) -> String {
+  targets.iter().fold(template.to_string(), |template, (alias, function)| {
+    template
+      .replace(&format!("${{{}}}", alias), &format!("${{{}}}", function))
+      .replace(&format!("${{{}.", alias), &format!("${{{}.", function))
+  })
+}
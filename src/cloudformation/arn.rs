@@ -0,0 +1,61 @@
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arn {
+  pub service: String,
+  pub region: String,
+  pub account: String,
+  pub resource: String,
+}
+
+impl Arn {
+  // `arn:partition:service:region:account:resource`, where the resource part
+  // may itself contain colons and slashes.
+  pub fn parse(arn: &str) -> Option<Arn> {
+    let mut parts = arn.splitn(6, ':');
+    if parts.next()? != "arn" {
+      return None;
+    }
+    parts.next()?;
+
+    Some(Arn {
+      service: parts.next()?.to_string(),
+      region: parts.next()?.to_string(),
+      account: parts.next()?.to_string(),
+      resource: parts.next()?.to_string(),
+    })
+  }
+
+  // The name a resource is created with: `function:name:alias` gives `name`,
+  // `table/name/stream/label` gives `name`, and a queue ARN is just the name.
+  // S3 has no type prefix, so `bucket/key/*` gives `bucket`.
+  pub fn name(&self) -> &str {
+    let resource = self.resource.as_str();
+    if self.service == "s3" {
+      return resource.split('/').next().unwrap_or(resource);
+    }
+
+    match resource.split_once([':', '/']) {
+      Some((_, rest)) => rest.split([':', '/']).next().unwrap_or(rest),
+      None => resource,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_arn() {
+    let queue = Arn::parse("arn:aws:sqs:eu-west-2:123456789012:jobs").unwrap();
+    let stream = Arn::parse("arn:aws:dynamodb:eu-west-2:123456789012:table/orders/stream/2024-01-01T00:00:00.000").unwrap();
+    let function = Arn::parse("arn:aws:lambda:eu-west-2:123456789012:function:worker:live").unwrap();
+
+    assert_eq!((queue.service.as_str(), queue.name()), ("sqs", "jobs"));
+    assert_eq!((stream.service.as_str(), stream.name()), ("dynamodb", "orders"));
+    assert_eq!((function.service.as_str(), function.name()), ("lambda", "worker"));
+    assert_eq!(Arn::parse("arn:aws:s3:::uploads/*").unwrap().name(), "uploads");
+    assert_eq!(Arn::parse("arn:aws:s3:::uploads/images/*").unwrap().name(), "uploads");
+    assert_eq!(Arn::parse("arn:aws:s3:::uploads").unwrap().name(), "uploads");
+    assert_eq!(Arn::parse("jobs"), None);
+  }
+}
@@ -0,0 +1,108 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::cloudformation::input;
+use crate::cloudformation::resource::TypeMappings;
+use crate::cloudformation::stack::Stack;
+use crate::error::{CloudmaidError, Result};
+
+const MANIFEST: &str = "manifest.json";
+const STACK_ARTIFACT: &str = "aws:cloudformation:stack";
+const NESTED_ASSEMBLY_ARTIFACT: &str = "cdk:cloud-assembly";
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+  #[serde(default)]
+  artifacts: BTreeMap<String, Artifact>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Artifact {
+  #[serde(rename = "type")]
+  typ: String,
+  #[serde(default)]
+  properties: ArtifactProperties,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactProperties {
+  template_file: Option<String>,
+  stack_name: Option<String>,
+  directory_name: Option<String>,
+}
+
+pub fn is_assembly(path: &Path) -> bool {
+  path.is_dir() && path.join(MANIFEST).is_file()
+}
+
+pub fn load(dir: &Path, type_mappings: &TypeMappings) -> Result<Vec<Stack>> {
+  let manifest: Manifest = from_json_file(&dir.join(MANIFEST))?;
+  let mut stacks = Vec::new();
+
+  for (id, artifact) in manifest.artifacts {
+    match (artifact.typ.as_str(), artifact.properties) {
+      (STACK_ARTIFACT, ArtifactProperties { template_file: Some(template_file), stack_name, .. }) => {
+        stacks.push(Stack {
+          name: stack_name.unwrap_or(id),
+          template: input::read_template(&dir.join(template_file), type_mappings)?,
+        });
+      }
+      (NESTED_ASSEMBLY_ARTIFACT, ArtifactProperties { directory_name: Some(directory_name), .. }) => {
+        stacks.extend(load(&dir.join(directory_name), type_mappings)?);
+      }
+      _ => {}
+    }
+  }
+
+  Ok(stacks)
+}
+
+fn from_json_file<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+  let file = File::open(path).map_err(|e| CloudmaidError::io(path, e))?;
+
+  serde_json::from_reader(BufReader::new(file)).map_err(|e| CloudmaidError::parse(path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use super::*;
+
+  #[test]
+  fn test_load_assembly() {
+    let dir = std::env::temp_dir().join(format!("cloudmaid-assembly-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join(MANIFEST),
+      r#"{
+        "version": "36.0.0",
+        "artifacts": {
+          "Tree": { "type": "cdk:tree", "properties": { "file": "tree.json" } },
+          "ProducerStack": {
+            "type": "aws:cloudformation:stack",
+            "properties": { "templateFile": "ProducerStack.template.json", "stackName": "producer" }
+          }
+        }
+      }"#,
+    )
+    .unwrap();
+    fs::write(
+      dir.join("ProducerStack.template.json"),
+      r#"{ "Resources": { "MyQueue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "q" } } } }"#,
+    )
+    .unwrap();
+
+    assert!(is_assembly(&dir));
+    let stacks = load(&dir, &TypeMappings::new()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(stacks.len(), 1);
+    assert_eq!(stacks[0].name, "producer");
+    assert_eq!(stacks[0].template.resources[0].name.0.as_ref(), "MyQueue");
+  }
+}
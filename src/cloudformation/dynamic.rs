@@ -0,0 +1,87 @@
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicReference {
+  Ssm(String),
+  Secret(String),
+}
+
+// `{{resolve:service:reference-key}}` strings are resolved by CloudFormation at
+// deploy time, so the parameter or secret they name lives outside the template.
+pub fn dynamic_references(value: &Value) -> Vec<DynamicReference> {
+  let mut references = Vec::new();
+  collect(value, &mut references);
+  references
+}
+
+fn collect(value: &Value, references: &mut Vec<DynamicReference>) {
+  match value {
+    Value::String(s) => {
+      let mut rest = s.as_str();
+
+      while let Some(start) = rest.find("{{resolve:") {
+        let after = &rest[start + "{{resolve:".len()..];
+        let Some(end) = after.find("}}") else {
+          break;
+        };
+
+        if let Some(reference) = parse(&after[..end])
+          && !references.contains(&reference)
+        {
+          references.push(reference);
+        }
+        rest = &after[end + 2..];
+      }
+    }
+    Value::Object(map) => map.values().for_each(|value| collect(value, references)),
+    Value::Array(items) => items.iter().for_each(|item| collect(item, references)),
+    _ => {}
+  }
+}
+
+fn parse(reference: &str) -> Option<DynamicReference> {
+  let (service, key) = reference.split_once(':')?;
+
+  match service {
+    // `ssm:name:version`; names cannot contain colons.
+    "ssm" | "ssm-secure" => Some(DynamicReference::Ssm(key.split(':').next()?.to_string())),
+    // `secretsmanager:secret-id:SecretString:json-key:...`, where the secret ID
+    // may itself be an ARN (`arn:aws:secretsmanager:region:account:secret:name`).
+    "secretsmanager" => {
+      let name = match key.strip_prefix("arn:") {
+        Some(arn) => arn.split(':').nth(5)?,
+        None => key.split(':').next()?,
+      };
+      Some(DynamicReference::Secret(name.to_string()))
+    }
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn test_dynamic_references() {
+    let value = json!({
+      "Url": "{{resolve:ssm:/app/db/url:3}}",
+      "Password": "{{resolve:secretsmanager:arn:aws:secretsmanager:eu-west-2:123:secret:db-credentials:SecretString:password}}",
+      "Names": ["{{resolve:ssm-secure:/app/api-key}}-{{resolve:secretsmanager:stripe:SecretString:key::}}"],
+      "Again": { "Fn::Sub": "{{resolve:ssm:/app/db/url}}" },
+      "Plain": "resolve:ssm:nothing"
+    });
+
+    assert_eq!(
+      dynamic_references(&value),
+      vec![
+        DynamicReference::Ssm("/app/db/url".to_string()),
+        DynamicReference::Secret("db-credentials".to_string()),
+        DynamicReference::Ssm("/app/api-key".to_string()),
+        DynamicReference::Secret("stripe".to_string()),
+      ]
+    );
+  }
+}
@@ -0,0 +1,138 @@
+use serde::de::DeserializeSeed;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::cloudformation::resource::TypeMappings;
+use crate::cloudformation::template::{self, Template, TemplateSeed};
+use crate::cloudformation::yaml;
+use crate::error::{CloudmaidError, Result};
+use crate::terraform::plan;
+
+pub const STDIN: &str = "-";
+
+pub fn read_value(path: &Path) -> Result<Value> {
+  let display = path.display().to_string();
+  let contents = if display == STDIN {
+    let mut contents = String::new();
+    io::stdin().read_to_string(&mut contents).map_err(|e| CloudmaidError::io("stdin", e))?;
+    contents
+  } else {
+    fs::read_to_string(path).map_err(|e| CloudmaidError::io(path, e))?
+  };
+
+  let value = parse_value(&contents, &display)?;
+
+  if plan::is_plan(&value) {
+    Ok(plan::to_template_value(&value))
+  } else {
+    Ok(value)
+  }
+}
+
+pub fn parse_value(contents: &str, path: &str) -> Result<Value> {
+  let value = if is_yaml(contents, path) {
+    let value: serde_yaml::Value = serde_yaml::from_str(contents).map_err(|e| CloudmaidError::parse(path, e))?;
+    yaml::to_json(value)
+  } else {
+    serde_json::from_str(contents).map_err(|e| CloudmaidError::parse(path, e))?
+  };
+
+  // `aws cloudformation get-template` wraps the template in a `TemplateBody`,
+  // as a string for YAML templates and as an object for JSON ones.
+  match value.get("TemplateBody") {
+    Some(Value::String(body)) if value.get("Resources").is_none() => parse_value(body, path),
+    Some(body @ Value::Object(_)) if value.get("Resources").is_none() => Ok(body.clone()),
+    _ => Ok(value),
+  }
+}
+
+pub fn read_template(path: &Path, type_mappings: &TypeMappings) -> Result<Template> {
+  match stream_template(path, type_mappings) {
+    Some(template) => Ok(template),
+    None => from_value(read_value(path)?, path, type_mappings),
+  }
+}
+
+// JSON templates are deserialized straight from the file, so a CDK mega-stack
+// is never held as a whole `Value` on top of its resources. Anything else (YAML
+// with its tags, Terraform plans, `get-template` output, or a template that
+// does not parse) falls back to `read_value`, which also reports the errors.
+fn stream_template(path: &Path, type_mappings: &TypeMappings) -> Option<Template> {
+  let display = path.display().to_string();
+  if display == STDIN || yaml::is_yaml_path(&display) {
+    return None;
+  }
+
+  let mut reader = BufReader::new(File::open(path).ok()?);
+  let first = reader.fill_buf().ok()?.iter().copied().find(|byte| !byte.is_ascii_whitespace());
+  if first != Some(b'{') {
+    return None;
+  }
+
+  let mut deserializer = serde_json::Deserializer::from_reader(reader);
+  let template = TemplateSeed(type_mappings).deserialize(&mut deserializer).ok()?;
+  deserializer.end().ok().map(|_| template)
+}
+
+pub fn from_value(value: Value, path: &Path, type_mappings: &TypeMappings) -> Result<Template> {
+  template::deserialize(value, type_mappings).map_err(|e| CloudmaidError::parse(path.display(), e))
+}
+
+fn is_yaml(contents: &str, path: &str) -> bool {
+  if yaml::is_yaml_path(path) {
+    return true;
+  }
+  if path.ends_with(".json") {
+    return false;
+  }
+
+  !contents.trim_start().starts_with('{')
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn test_parse_value_detects_format_from_content() {
+    let from_json = parse_value(r#"{ "Resources": {} }"#, STDIN).unwrap();
+    let from_yaml = parse_value("Resources: {}\n", STDIN).unwrap();
+
+    assert_eq!(from_json, json!({ "Resources": {} }));
+    assert_eq!(from_yaml, json!({ "Resources": {} }));
+  }
+
+  #[test]
+  fn test_parse_value_unwraps_get_template_output() {
+    let output = r#"{ "TemplateBody": "Resources:\n  Q:\n    Type: AWS::SQS::Queue\n", "StagesAvailable": ["Original"] }"#;
+
+    assert_eq!(
+      parse_value(output, STDIN).unwrap(),
+      json!({ "Resources": { "Q": { "Type": "AWS::SQS::Queue" } } })
+    );
+  }
+
+  #[test]
+  fn test_read_template_streams_json_and_falls_back() {
+    let dir = std::env::temp_dir().join(format!("cloudmaid-input-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let template = r#"{ "Resources": { "Q": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } } } }"#;
+    let plain = dir.join("plain.json");
+    let wrapped = dir.join("wrapped.json");
+    fs::write(&plain, format!("\n  {}", template)).unwrap();
+    fs::write(&wrapped, format!(r#"{{ "TemplateBody": {} }}"#, template)).unwrap();
+
+    let streamed = stream_template(&plain, &TypeMappings::new());
+    let unwrapped = read_template(&wrapped, &TypeMappings::new()).unwrap();
+    assert!(stream_template(&wrapped, &TypeMappings::new()).is_none());
+    fs::remove_dir_all(&dir).unwrap();
+
+    let expected = from_value(serde_json::from_str(template).unwrap(), &plain, &TypeMappings::new()).unwrap();
+    assert_eq!(streamed, Some(expected.clone()));
+    assert_eq!(unwrapped, expected);
+  }
+}
@@ -0,0 +1,227 @@
+use serde_json::{Map, Value};
+
+const STACK_NAME: &str = "AWS::StackName";
+const MARKER: &str = "cloudmaid-ref:";
+
+// Best-effort evaluation of the intrinsics that commonly build export names,
+// e.g. `{"Fn::Sub": "${AWS::StackName}-QueueArn"}` or an `Fn::Join` of the same.
+pub fn resolve_string(value: &Value, stack_name: &str) -> Option<String> {
+  match value {
+    Value::String(s) => Some(s.to_string()),
+    Value::Object(map) => {
+      if let Some(reference) = map.get("Ref") {
+        return match reference.as_str()? {
+          STACK_NAME => Some(stack_name.to_string()),
+          _ => None,
+        };
+      }
+
+      if let Some(sub) = map.get("Fn::Sub") {
+        let template = match sub {
+          Value::Array(args) => args.first()?.as_str()?,
+          other => other.as_str()?,
+        };
+        return Some(template.replace(&format!("${{{}}}", STACK_NAME), stack_name));
+      }
+
+      if let Some(join) = map.get("Fn::Join") {
+        let args = join.as_array()?;
+        let separator = args.first()?.as_str()?;
+        let parts = args
+          .get(1)?
+          .as_array()?
+          .iter()
+          .map(|part| resolve_string(part, stack_name))
+          .collect::<Option<Vec<String>>>()?;
+        return Some(parts.join(separator));
+      }
+
+      None
+    }
+    _ => None,
+  }
+}
+
+// Logical IDs a value actually points at through `Ref`, `Fn::GetAtt` or the
+// `${Name}` / `${Name.Attr}` placeholders of `Fn::Sub`. Pseudo parameters
+// (`AWS::Region`, ...) and `Fn::Sub` variables defined inline are skipped.
+pub fn references(value: &Value) -> Vec<String> {
+  let mut references = Vec::new();
+  collect_references(value, &mut references, true);
+  references
+}
+
+// Only the logical IDs named by `Ref` or `Fn::GetAtt`. `${...}` placeholders
+// are also how API Gateway and Step Functions spell their own variables, so
+// unlike these they may not mean a resource at all.
+pub fn explicit_references(value: &Value) -> Vec<String> {
+  let mut references = Vec::new();
+  collect_references(value, &mut references, false);
+  references
+}
+
+fn collect_references(value: &Value, references: &mut Vec<String>, placeholders: bool) {
+  match value {
+    Value::Object(map) => {
+      for (key, value) in map {
+        match (key.as_str(), value) {
+          ("Ref", Value::String(name)) => push_reference(name, references),
+          ("Fn::GetAtt", Value::Array(args)) => {
+            if let Some(name) = args.first().and_then(Value::as_str) {
+              push_reference(name, references);
+            }
+            args.iter().skip(1).for_each(|arg| collect_references(arg, references, placeholders));
+          }
+          ("Fn::GetAtt", Value::String(path)) => {
+            push_reference(path.split('.').next().unwrap_or(path), references)
+          }
+          ("Fn::Sub", Value::String(template)) if placeholders => sub_references(template, &[], references),
+          ("Fn::Sub", Value::Array(args)) if placeholders => {
+            let variables = args.get(1).and_then(Value::as_object);
+            let defined: Vec<&str> = variables.into_iter().flat_map(|vars| vars.keys()).map(String::as_str).collect();
+
+            if let Some(template) = args.first().and_then(Value::as_str) {
+              sub_references(template, &defined, references);
+            }
+            if let Some(variables) = variables {
+              variables.values().for_each(|value| collect_references(value, references, placeholders));
+            }
+          }
+          _ => collect_references(value, references, placeholders),
+        }
+      }
+    }
+    Value::Array(items) => items.iter().for_each(|item| collect_references(item, references, placeholders)),
+    _ => {}
+  }
+}
+
+fn sub_references(template: &str, defined: &[&str], references: &mut Vec<String>) {
+  let mut rest = template;
+
+  while let Some(start) = rest.find("${") {
+    let after = &rest[start + 2..];
+    let Some(end) = after.find('}') else {
+      break;
+    };
+
+    let placeholder = &after[..end];
+    // `${!Literal}` is an escaped, literal `${Literal}`.
+    if !placeholder.starts_with('!') {
+      let name = placeholder.split('.').next().unwrap_or(placeholder);
+      if !defined.contains(&name) {
+        push_reference(name, references);
+      }
+    }
+
+    rest = &after[end + 1..];
+  }
+}
+
+fn push_reference(name: &str, references: &mut Vec<String>) {
+  if !name.starts_with("AWS::") && !references.iter().any(|existing| existing == name) {
+    references.push(name.to_string());
+  }
+}
+
+// Renders a string built with `Fn::Sub` or `Fn::Join` (an ASL definition, an
+// integration URI) with every spliced-in resource swapped for a marker, so the
+// text can be parsed and the markers read back with `spliced_references`.
+pub fn splice(value: &Value, variables: &Map<String, Value>) -> Option<String> {
+  match value {
+    Value::String(s) => Some(replace_placeholders(s, variables)),
+    Value::Object(map) => {
+      if let Some(sub) = map.get("Fn::Sub") {
+        let (template, inline) = match sub {
+          Value::Array(args) => (args.first()?.as_str()?, args.get(1).and_then(Value::as_object)),
+          other => (other.as_str()?, None),
+        };
+        let mut variables = variables.clone();
+        variables.extend(inline.cloned().unwrap_or_default());
+        return Some(replace_placeholders(template, &variables));
+      }
+
+      if let Some(join) = map.get("Fn::Join") {
+        let args = join.as_array()?;
+        let separator = args.first()?.as_str()?;
+        let parts = args
+          .get(1)?
+          .as_array()?
+          .iter()
+          .map(|part| splice(part, variables))
+          .collect::<Option<Vec<String>>>()?;
+        return Some(parts.join(separator));
+      }
+
+      Some(references(value).first().map(|name| marker(name)).unwrap_or_default())
+    }
+    _ => None,
+  }
+}
+
+fn replace_placeholders(template: &str, variables: &Map<String, Value>) -> String {
+  let mut result = String::new();
+  let mut rest = template;
+
+  while let Some(start) = rest.find("${") {
+    result.push_str(&rest[..start]);
+    let after = &rest[start + 2..];
+    let Some(end) = after.find('}') else {
+      break;
+    };
+    let placeholder = &after[..end];
+
+    match (placeholder.strip_prefix('!'), variables.get(placeholder)) {
+      (Some(literal), _) => result.push_str(&format!("${{{}}}", literal)),
+      (None, Some(Value::String(literal))) => result.push_str(literal),
+      (None, Some(value)) => result.push_str(&references(value).first().map(|name| marker(name)).unwrap_or_default()),
+      (None, None) if placeholder.starts_with("AWS::") => result.push_str(&format!("${{{}}}", placeholder)),
+      (None, None) => result.push_str(&marker(placeholder.split('.').next().unwrap_or(placeholder))),
+    }
+
+    rest = &after[end + 1..];
+  }
+
+  result.push_str(rest);
+  result
+}
+
+fn marker(name: &str) -> String {
+  format!("{}{}", MARKER, name)
+}
+
+pub fn spliced_references(text: &str) -> Vec<String> {
+  let mut names = Vec::new();
+
+  for (index, _) in text.match_indices(MARKER) {
+    let name: String = text[index + MARKER.len()..].chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+    if !name.is_empty() && !names.contains(&name) {
+      names.push(name);
+    }
+  }
+
+  names
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn test_references() {
+    let value = json!({
+      "Queue": { "Ref": "Jobs" },
+      "Arn": { "Fn::GetAtt": ["JobsDlq", "Arn"] },
+      "Short": { "Fn::GetAtt": "Table.StreamArn" },
+      "Url": { "Fn::Sub": "https://${Api}.execute-api.${AWS::Region}.amazonaws.com/${!Stage}" },
+      "Vars": { "Fn::Sub": ["${Bucket}/${Key}", { "Key": { "Ref": "Prefix" } }] },
+      "Joined": { "Fn::Join": ["", ["arn:aws:sqs:", { "Ref": "AWS::Region" }, ":", { "Ref": "Deadletter" }]] },
+      "Region": { "Ref": "AWS::Region" },
+      "Literal": "Jobs-not-a-reference"
+    });
+
+    assert_eq!(references(&value), vec!["Jobs", "JobsDlq", "Table", "Api", "Bucket", "Prefix", "Deadletter"]);
+  }
+}
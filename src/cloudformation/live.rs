@@ -0,0 +1,64 @@
+use aws_config::BehaviorVersion;
+use aws_sdk_cloudformation::Client;
+use aws_sdk_cloudformation::types::TemplateStage;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::cloudformation::input::{from_value, parse_value};
+use crate::cloudformation::resource::TypeMappings;
+use crate::cloudformation::stack::Stack;
+use crate::error::{CloudmaidError, Result};
+
+pub fn fetch(stack_name: &str, profile: Option<&str>, region: Option<&str>, type_mappings: &TypeMappings) -> Result<Stack> {
+  let aws = |message: String| CloudmaidError::Aws {
+    stack: stack_name.to_string(),
+    message,
+  };
+  let runtime = tokio::runtime::Builder::new_current_thread()
+    .enable_all()
+    .build()
+    .map_err(|e| aws(e.to_string()))?;
+
+  runtime.block_on(async {
+    let mut loader = aws_config::defaults(BehaviorVersion::latest());
+    if let Some(profile) = profile {
+      loader = loader.profile_name(profile);
+    }
+    if let Some(region) = region {
+      loader = loader.region(aws_config::Region::new(region.to_string()));
+    }
+    let client = Client::new(&loader.load().await);
+
+    let template = client
+      .get_template()
+      .stack_name(stack_name)
+      .template_stage(TemplateStage::Processed)
+      .send()
+      .await
+      .map_err(|e| aws(format!("GetTemplate: {}", e)))?;
+    // A stack's resources come a page of 100 at a time.
+    let resources = client
+      .list_stack_resources()
+      .stack_name(stack_name)
+      .into_paginator()
+      .items()
+      .send()
+      .try_collect()
+      .await
+      .map_err(|e| aws(format!("ListStackResources: {}", e)))?;
+
+    let deployed: HashSet<&str> = resources.iter().filter_map(|resource| resource.logical_resource_id()).collect();
+
+    let body = parse_value(template.template_body().unwrap_or_default(), stack_name)?;
+    let mut template = from_value(body, Path::new(stack_name), type_mappings)?;
+
+    // Resources whose Condition evaluated to false are in the template but
+    // were never created, so only keep what the stack actually contains.
+    template.resources.retain(|resource| deployed.contains(&*resource.name.0));
+
+    Ok(Stack {
+      name: stack_name.to_string(),
+      template,
+    })
+  })
+}
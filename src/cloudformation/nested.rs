@@ -0,0 +1,228 @@
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::cloudformation::input::{from_value, read_value};
+use crate::cloudformation::resource::TypeMappings;
+use crate::cloudformation::stack::Stack;
+use crate::error::Result;
+
+const NESTED_STACK: &str = "AWS::CloudFormation::Stack";
+
+// Parameters passed into a child stack and child outputs read back by the
+// parent are rewritten into synthetic exports/imports, so the regular
+// cross-stack linking draws the edges between the two subgraphs.
+pub fn expand(
+  name: &str,
+  template: Value,
+  path: &Path,
+  template_map: &HashMap<String, PathBuf>,
+  type_mappings: &TypeMappings,
+) -> Result<Vec<Stack>> {
+  expand_within(name, name, template, path, template_map, type_mappings, &mut Vec::new())
+}
+
+// `scope` is the chain of logical IDs down to this stack, which keeps the
+// synthetic exports of two children sharing a logical ID under different
+// parents apart. `ancestors` are the templates being expanded above this one,
+// so a template that nests itself, directly or not, is expanded only once.
+fn expand_within(
+  name: &str,
+  scope: &str,
+  template: Value,
+  path: &Path,
+  template_map: &HashMap<String, PathBuf>,
+  type_mappings: &TypeMappings,
+  ancestors: &mut Vec<PathBuf>,
+) -> Result<Vec<Stack>> {
+  let mut template = template;
+  let mut children = Vec::new();
+  let base_dir = path.parent().unwrap_or(Path::new("."));
+  ancestors.push(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+
+  for (logical_id, resource) in nested_stacks(&template) {
+    let Some(child_path) = resolve_child_path(&resource, base_dir, template_map) else {
+      warn!(
+        "{}: template of nested stack {} could not be found; map its URL to a file with --template-map",
+        name, logical_id
+      );
+      continue;
+    };
+    if ancestors.contains(&child_path.canonicalize().unwrap_or_else(|_| child_path.clone())) {
+      warn!("{}: nested stack {} includes {} again and is not expanded", name, logical_id, child_path.display());
+      continue;
+    }
+    let mut child = read_value(&child_path)?;
+    let child_scope = format!("{}/{}", scope, logical_id);
+
+    // Logical IDs and output names are alphanumeric, so keys holding `::`
+    // cannot clash with the template's own outputs.
+    if let Some(Value::Object(parameters)) = resource.pointer("/Properties/Parameters") {
+      for (parameter, value) in parameters {
+        let export = format!("Cloudmaid::Nested::{}::Parameters.{}", child_scope, parameter);
+        add_output(&mut template, &format!("Cloudmaid::Nested::{}::Parameters.{}", logical_id, parameter), value.clone(), &export);
+        rewrite(&mut child, &|v| match v.get("Ref").and_then(Value::as_str) {
+          Some(r) if r == parameter => Some(json!({ "Fn::ImportValue": export })),
+          _ => None,
+        });
+      }
+    }
+
+    let outputs = child.get("Outputs").and_then(Value::as_object).cloned().unwrap_or_default();
+    for (output, definition) in outputs {
+      let export = format!("Cloudmaid::Nested::{}::Outputs.{}", child_scope, output);
+      let value = definition.get("Value").cloned().unwrap_or(Value::Null);
+      add_output(&mut child, &format!("Cloudmaid::Nested::Outputs.{}", output), value, &export);
+      rewrite(&mut template, &|v| match get_att_target(v) {
+        Some((resource, attribute)) if resource == logical_id && attribute == format!("Outputs.{}", output) => {
+          Some(json!({ "Fn::ImportValue": export }))
+        }
+        _ => None,
+      });
+    }
+
+    children.extend(expand_within(&logical_id, &child_scope, child, &child_path, template_map, type_mappings, ancestors)?);
+  }
+  ancestors.pop();
+
+  let mut stacks = vec![Stack {
+    name: name.to_string(),
+    template: from_value(template, path, type_mappings)?,
+  }];
+  stacks.extend(children);
+
+  Ok(stacks)
+}
+
+fn nested_stacks(template: &Value) -> Vec<(String, Value)> {
+  template
+    .get("Resources")
+    .and_then(Value::as_object)
+    .map(|resources| {
+      resources
+        .iter()
+        .filter(|(_, resource)| resource.get("Type").and_then(Value::as_str) == Some(NESTED_STACK))
+        .map(|(logical_id, resource)| (logical_id.clone(), resource.clone()))
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+fn resolve_child_path(resource: &Value, base_dir: &Path, template_map: &HashMap<String, PathBuf>) -> Option<PathBuf> {
+  let url = resource.pointer("/Properties/TemplateURL")?.as_str()?;
+
+  if let Some(path) = template_map.get(url) {
+    return Some(path.clone());
+  }
+
+  let local = base_dir.join(url);
+  local.is_file().then_some(local)
+}
+
+fn add_output(template: &mut Value, key: &str, value: Value, export: &str) {
+  let Some(root) = template.as_object_mut() else {
+    return;
+  };
+  let outputs = root.entry("Outputs").or_insert_with(|| Value::Object(Map::new()));
+
+  if let Some(outputs) = outputs.as_object_mut() {
+    outputs.insert(key.to_string(), json!({ "Value": value, "Export": { "Name": export } }));
+  }
+}
+
+fn get_att_target(value: &Value) -> Option<(String, String)> {
+  match value.get("Fn::GetAtt")? {
+    Value::Array(args) => Some((args.first()?.as_str()?.to_string(), args.get(1)?.as_str()?.to_string())),
+    Value::String(s) => s.split_once('.').map(|(r, a)| (r.to_string(), a.to_string())),
+    _ => None,
+  }
+}
+
+fn rewrite(value: &mut Value, replace: &dyn Fn(&Value) -> Option<Value>) {
+  if let Some(replacement) = replace(value) {
+    *value = replacement;
+    return;
+  }
+
+  match value {
+    Value::Object(map) => map.values_mut().for_each(|v| rewrite(v, replace)),
+    Value::Array(items) => items.iter_mut().for_each(|v| rewrite(v, replace)),
+    _ => {}
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use crate::ast::ast::AST;
+
+  use super::*;
+
+  #[test]
+  fn test_expand_nested_stack() {
+    let dir = std::env::temp_dir().join(format!("cloudmaid-nested-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join("child.json"),
+      r#"{
+        "Parameters": { "QueueArn": { "Type": "String" } },
+        "Resources": {
+          "ChildLambda": {
+            "Type": "AWS::Lambda::Function",
+            "Properties": { "FunctionName": "child-lambda", "Architectures": ["arm64"] }
+          },
+          "ChildMapping": {
+            "Type": "AWS::Lambda::EventSourceMapping",
+            "Properties": { "EventSourceArn": { "Ref": "QueueArn" }, "FunctionName": { "Ref": "ChildLambda" } }
+          }
+        }
+      }"#,
+    )
+    .unwrap();
+
+    let parent = json!({
+      "Resources": {
+        "ParentQueue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "parent-queue" } },
+        "Child": {
+          "Type": "AWS::CloudFormation::Stack",
+          "Properties": {
+            "TemplateURL": "https://bucket.s3.amazonaws.com/child.json",
+            "Parameters": { "QueueArn": { "Fn::GetAtt": ["ParentQueue", "Arn"] } }
+          }
+        }
+      }
+    });
+    let template_map = HashMap::from([("https://bucket.s3.amazonaws.com/child.json".to_string(), dir.join("child.json"))]);
+
+    let stacks = expand("Parent", parent, &dir.join("parent.json"), &template_map, &TypeMappings::new()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let names: Vec<&str> = stacks.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["Parent", "Child"]);
+
+    let expected_output = "```mermaid\nflowchart LR\nsubgraph Parent\nParentQueue((\"parent-queue\"))\nend\nsubgraph Child\nChildLambda([\"child-lambda\"])\nend\nParentQueue((\"parent-queue\")) ==>|triggers| ChildLambda([\"child-lambda\"])\n```";
+
+    assert_eq!(AST::from(stacks).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_stack_nesting_itself_is_expanded_once() {
+    let dir = std::env::temp_dir().join(format!("cloudmaid-nested-cycle-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let template = json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "queue" } },
+        "Again": { "Type": "AWS::CloudFormation::Stack", "Properties": { "TemplateURL": "again.json" } }
+      }
+    });
+    fs::write(dir.join("again.json"), template.to_string()).unwrap();
+
+    let stacks = expand("Root", template.clone(), &dir.join("root.json"), &HashMap::new(), &TypeMappings::new()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let names: Vec<&str> = stacks.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["Root", "Again"]);
+  }
+}
@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+use crate::cloudformation::intrinsic::resolve_string;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Output {
+  pub value: serde_json::Value,
+  pub export: Option<Export>,
+  pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Export {
+  pub name: serde_json::Value,
+}
+
+impl Output {
+  pub fn export_name(&self, stack_name: &str) -> Option<String> {
+    resolve_string(&self.export.as_ref()?.name, stack_name)
+  }
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Parameter {
+  #[serde(rename = "Type")]
+  pub typ: String,
+  pub default: Option<serde_json::Value>,
+  pub description: Option<String>,
+}
@@ -0,0 +1,343 @@
+use serde_json::Value;
+
+use crate::cloudformation::property::Property;
+use crate::cloudformation::resource::parse_properties;
+use crate::cloudformation::template::Template;
+
+const REGION: &str = "AWS::Region";
+
+// The properties read from resources whose type is not modelled: the policies
+// behind `permissions` and the path parts behind `api`. Others are left as text.
+const READ: &[&str] = &[
+  "AssumeRolePolicyDocument",
+  "Policies",
+  "ManagedPolicyArns",
+  "PolicyDocument",
+  "Roles",
+  "Role",
+  "RoleArn",
+  "PathPart",
+  "ParentId",
+];
+
+// What is known about where a template is deployed: the stack it belongs to,
+// `--region` / `--account-id`, and any `--parameter` values.
+pub struct Context<'a> {
+  pub stack_name: &'a str,
+  pub region: Option<&'a str>,
+  pub account_id: Option<&'a str>,
+  pub parameters: &'a [(String, String)],
+}
+
+impl Context<'_> {
+  fn pseudo_parameter(&self, name: &str) -> Option<String> {
+    match name {
+      "AWS::StackName" => Some(self.stack_name.to_string()),
+      REGION => self.region.map(str::to_string),
+      "AWS::AccountId" => self.account_id.map(str::to_string),
+      "AWS::Partition" => Some("aws".to_string()),
+      "AWS::URLSuffix" => Some("amazonaws.com".to_string()),
+      _ => None,
+    }
+  }
+}
+
+struct Lookup<'a> {
+  template: &'a Template,
+  context: &'a Context<'a>,
+}
+
+// Names and ARNs are often assembled from pseudo parameters or picked from a
+// mapping, and only become real strings once those are known. Anything that
+// cannot be fully evaluated, including `Fn::Sub` placeholders naming other
+// resources or parameters, is left as it is.
+pub fn resolve(template: &mut Template, context: &Context) {
+  let mut resolved = Vec::new();
+  let lookup = Lookup { template: &*template, context };
+
+  for (index, resource) in template.resources.iter().enumerate() {
+    if let Property::Unparsed(properties) = &*resource.properties
+      && !READ.iter().filter_map(|key| properties.value().get(key)).any(|value| may_resolve(value, context))
+    {
+      continue;
+    }
+    let Ok(mut properties) = serde_json::to_value(&*resource.properties) else {
+      continue;
+    };
+
+    let changed = match (&*resource.properties, properties.as_object_mut()) {
+      (Property::Unparsed(_), Some(map)) => map
+        .iter_mut()
+        .filter(|(key, _)| READ.contains(&key.as_str()))
+        .fold(false, |changed, (_, value)| rewrite(value, &lookup) | changed),
+      _ => rewrite(&mut properties, &lookup),
+    };
+    if changed
+      && let Ok(properties) = parse_properties(resource.typ.clone(), properties)
+    {
+      resolved.push((index, properties));
+    }
+  }
+
+  for (index, properties) in resolved {
+    template.resources[index].properties = properties.into();
+  }
+}
+
+// Whether a value uses one of the intrinsics evaluated below, so properties
+// left as text are only rewritten when something in them can change.
+fn may_resolve(value: &Value, context: &Context) -> bool {
+  match value {
+    Value::Object(map) => {
+      let intrinsic = match map.iter().next().map(|(function, args)| (function.as_str(), args)) {
+        Some(("Ref", Value::String(name))) if map.len() == 1 => context.pseudo_parameter(name).is_some(),
+        Some((function, _)) if map.len() == 1 => matches!(function, "Fn::FindInMap" | "Fn::Sub" | "Fn::Join"),
+        _ => false,
+      };
+      intrinsic || map.values().any(|value| may_resolve(value, context))
+    }
+    Value::Array(items) => items.iter().any(|value| may_resolve(value, context)),
+    _ => false,
+  }
+}
+
+fn rewrite(value: &mut Value, lookup: &Lookup) -> bool {
+  if let Some(resolved) = intrinsic(value, lookup) {
+    *value = resolved;
+    return true;
+  }
+
+  let children: Vec<&mut Value> = match value {
+    Value::Object(map) => map.values_mut().collect(),
+    Value::Array(items) => items.iter_mut().collect(),
+    _ => Vec::new(),
+  };
+
+  let mut changed = false;
+  for child in children {
+    changed |= rewrite(child, lookup);
+  }
+  changed
+}
+
+fn intrinsic(value: &Value, lookup: &Lookup) -> Option<Value> {
+  let map = value.as_object()?;
+  if map.len() != 1 {
+    return None;
+  }
+
+  let (function, args) = map.iter().next()?;
+  match (function.as_str(), args) {
+    ("Ref", Value::String(name)) => lookup.context.pseudo_parameter(name).map(Value::String),
+    ("Fn::FindInMap", _) => find_in_map(value, lookup),
+    ("Fn::Sub", sub) => substitute(sub, lookup).map(Value::String),
+    ("Fn::Join", Value::Array(args)) => {
+      let separator = args.first()?.as_str()?;
+      let parts = args
+        .get(1)?
+        .as_array()?
+        .iter()
+        .map(|part| evaluate(part, lookup))
+        .collect::<Option<Vec<String>>>()?;
+      Some(Value::String(parts.join(separator)))
+    }
+    _ => None,
+  }
+}
+
+fn substitute(sub: &Value, lookup: &Lookup) -> Option<String> {
+  let (template, variables) = match sub {
+    Value::Array(args) => (args.first()?.as_str()?, args.get(1).and_then(Value::as_object)),
+    other => (other.as_str()?, None),
+  };
+
+  let mut result = String::new();
+  let mut rest = template;
+
+  while let Some(start) = rest.find("${") {
+    result.push_str(&rest[..start]);
+    let after = &rest[start + 2..];
+    let end = after.find('}')?;
+    let placeholder = &after[..end];
+
+    match placeholder.strip_prefix('!') {
+      Some(literal) => result.push_str(&format!("${{{}}}", literal)),
+      None => {
+        let value = match variables.and_then(|vars| vars.get(placeholder)) {
+          Some(variable) => evaluate(variable, lookup)?,
+          None => lookup.context.pseudo_parameter(placeholder)?,
+        };
+        result.push_str(&value);
+      }
+    }
+
+    rest = &after[end + 1..];
+  }
+
+  result.push_str(rest);
+  Some(result)
+}
+
+fn find_in_map(value: &Value, lookup: &Lookup) -> Option<Value> {
+  let args = value.get("Fn::FindInMap")?.as_array()?;
+  let map = key(args.first()?, lookup)?;
+  let top_level = key(args.get(1)?, lookup)?;
+  let second_level = key(args.get(2)?, lookup)?;
+
+  lookup
+    .template
+    .mappings
+    .get(&map)
+    .and_then(|m| m.get(&top_level))
+    .and_then(|m| m.get(&second_level))
+    .cloned()
+    // `AWS::LanguageExtensions` allows a fallback as the fourth argument.
+    .or_else(|| args.get(3)?.get("DefaultValue").cloned())
+}
+
+// Mapping keys are commonly a parameter such as the stage, so unlike names,
+// keys may also be evaluated from `--parameter` values and parameter defaults.
+fn key(value: &Value, lookup: &Lookup) -> Option<String> {
+  let Some(name) = value.get("Ref").and_then(Value::as_str) else {
+    return evaluate(value, lookup);
+  };
+
+  if let Some(pseudo) = lookup.context.pseudo_parameter(name) {
+    return Some(pseudo);
+  }
+  if let Some((_, given)) = lookup.context.parameters.iter().find(|(parameter, _)| parameter == name) {
+    return Some(given.clone());
+  }
+  match lookup.template.parameters.get(name)?.default.as_ref()? {
+    Value::String(s) => Some(s.clone()),
+    other => Some(other.to_string()),
+  }
+}
+
+fn evaluate(value: &Value, lookup: &Lookup) -> Option<String> {
+  match value {
+    Value::String(s) => Some(s.clone()),
+    Value::Number(n) => Some(n.to_string()),
+    _ => match intrinsic(value, lookup)? {
+      Value::String(s) => Some(s),
+      _ => None,
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::property::Property;
+
+  use super::*;
+
+  #[test]
+  fn test_resolve_find_in_map() {
+    let mut template: Template = serde_json::from_value(json!({
+      "Parameters": { "Stage": { "Type": "String", "Default": "dev" } },
+      "Mappings": {
+        "Queues": {
+          "dev": { "Name": "jobs-dev" },
+          "prod": { "Name": "jobs-prod" }
+        },
+        "Regions": { "eu-west-2": { "Table": "orders-london" } }
+      },
+      "Resources": {
+        "Queue": {
+          "Type": "AWS::SQS::Queue",
+          "Properties": { "QueueName": { "Fn::FindInMap": ["Queues", { "Ref": "Stage" }, "Name"] } }
+        },
+        "Table": {
+          "Type": "AWS::DynamoDB::Table",
+          "Properties": { "TableName": { "Fn::FindInMap": ["Regions", { "Ref": "AWS::Region" }, "Table"] } }
+        }
+      }
+    }))
+    .unwrap();
+    let mut unresolved = template.clone();
+    let parameters = [("Stage".to_string(), "prod".to_string())];
+
+    resolve(&mut template, &context(Some("eu-west-2"), &parameters));
+    resolve(&mut unresolved, &context(None, &[]));
+
+    assert_eq!(*template.resources[0].properties, Property::Sqs { queue_name: "jobs-prod".into() });
+    assert!(matches!(&*template.resources[1].properties, Property::DynamoDb { table_name, .. } if table_name.as_str() == Some("orders-london")));
+    assert!(matches!(
+      &*unresolved.resources[1].properties,
+      Property::DynamoDb { table_name, .. } if *table_name == json!({ "Fn::FindInMap": ["Regions", { "Ref": "AWS::Region" }, "Table"] })
+    ));
+  }
+
+  #[test]
+  fn test_resolve_pseudo_parameters() {
+    let mut template: Template = serde_json::from_value(json!({
+      "Parameters": { "Stage": { "Type": "String", "Default": "dev" } },
+      "Resources": {
+        "Queue": {
+          "Type": "AWS::SQS::Queue",
+          "Properties": { "QueueName": { "Fn::Sub": "${AWS::StackName}-jobs-${AWS::Region}" } }
+        },
+        "Table": {
+          "Type": "AWS::DynamoDB::Table",
+          "Properties": { "TableName": { "Fn::Join": ["-", [{ "Ref": "AWS::AccountId" }, "orders"]] } }
+        },
+        "Other": {
+          "Type": "AWS::SQS::Queue",
+          "Properties": { "QueueName": { "Fn::Sub": "${Stage}-jobs" } }
+        }
+      }
+    }))
+    .unwrap();
+
+    resolve(&mut template, &context(Some("eu-west-2"), &[]));
+
+    assert_eq!(*template.resources[0].properties, Property::Sqs { queue_name: "app-jobs-eu-west-2".into() });
+    assert!(matches!(&*template.resources[1].properties, Property::DynamoDb { table_name, .. } if table_name.as_str() == Some("123456789012-orders")));
+    assert_eq!(*template.resources[2].properties, Property::Sqs { queue_name: json!({ "Fn::Sub": "${Stage}-jobs" }) });
+  }
+
+  #[test]
+  fn test_resolve_only_read_properties_of_untyped_resources() {
+    let mut template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Role": {
+          "Type": "AWS::IAM::Role",
+          "Properties": {
+            "AssumeRolePolicyDocument": {},
+            "RoleName": { "Fn::Sub": "${AWS::StackName}-role" },
+            "Policies": [{ "PolicyDocument": { "Statement": { "Resource": { "Fn::Sub": "arn:aws:sqs:${AWS::Region}:*" } } } }]
+          }
+        },
+        "Logs": {
+          "Type": "AWS::Logs::LogGroup",
+          "Properties": { "LogGroupName": { "Fn::Sub": "/${AWS::StackName}" } }
+        }
+      }
+    }))
+    .unwrap();
+
+    resolve(&mut template, &context(Some("eu-west-2"), &[]));
+
+    assert_eq!(
+      template.resources[0].properties.untyped(),
+      Some(&json!({
+        "AssumeRolePolicyDocument": {},
+        "RoleName": { "Fn::Sub": "${AWS::StackName}-role" },
+        "Policies": [{ "PolicyDocument": { "Statement": { "Resource": "arn:aws:sqs:eu-west-2:*" } } }]
+      }))
+    );
+    assert!(matches!(&*template.resources[1].properties, Property::Unparsed(_)));
+    assert!(!may_resolve(&json!({ "Fn::GetAtt": ["Queue", "Arn"], "Ref": "AWS::NoValue" }), &context(None, &[])));
+  }
+
+  fn context<'a>(region: Option<&'a str>, parameters: &'a [(String, String)]) -> Context<'a> {
+    Context {
+      stack_name: "app",
+      region,
+      account_id: Some("123456789012"),
+      parameters,
+    }
+  }
+}
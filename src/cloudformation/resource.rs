@@ -1,50 +1,422 @@
-use serde::Deserialize;
-use serde_json::from_value;
-use crate::cloudformation::property::Property;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::value::RawValue;
+use serde_path_to_error::Segment;
+use serde_json::{from_str, json};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use crate::cloudformation::property::{Property, Unparsed};
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
 pub struct Resource {
   pub name: Name,
   pub typ: ResourceType,
-  pub properties: Property,
+  pub properties: Arc<Property>,
+  pub metadata: serde_json::Value,
+  pub depends_on: Vec<String>,
+  pub aliases: Vec<String>,
+  pub tags: Vec<(String, String)>,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+impl Resource {
+  pub fn cdk_path(&self) -> Option<&str> {
+    self.metadata.get("aws:cdk:path")?.as_str()
+  }
+}
+
+// The value names match `Display`, so `--only` completes the same spellings
+// that `FromStr` accepts in `cloudmaid.toml`.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Hash, Default, ValueEnum)]
 pub enum ResourceType {
   Lambda,
   Sqs,
+  #[value(name = "apigateway")]
   ApiGateway,
+  #[value(name = "eventsourcemapping")]
   EventSourceMapping,
+  #[value(name = "dynamodb")]
+  DynamoDb,
+  #[value(name = "eventrule")]
+  EventRule,
+  #[value(name = "statemachine")]
+  StateMachine,
+  Parameter,
+  #[value(name = "ssmparameter")]
+  SsmParameter,
+  Secret,
+  Bucket,
+  #[value(name = "gluejob")]
+  GlueJob,
+  #[value(name = "gluecrawler")]
+  GlueCrawler,
+  #[value(name = "gluedatabase")]
+  GlueDatabase,
+  #[value(name = "gluetrigger")]
+  GlueTrigger,
+  Firehose,
+  Kinesis,
+  Redshift,
+  #[value(name = "filesystem")]
+  FileSystem,
+  #[value(name = "accesspoint")]
+  AccessPoint,
+  Pipeline,
+  #[value(name = "codebuild")]
+  CodeBuild,
+  #[value(name = "batchjobqueue")]
+  BatchJobQueue,
+  #[value(name = "batchjobdefinition")]
+  BatchJobDefinition,
+  #[value(name = "batchcomputeenvironment")]
+  BatchComputeEnvironment,
+  #[value(name = "iotrule")]
+  IotRule,
+  Sns,
+  #[value(name = "sesreceiptrule")]
+  SesReceiptRule,
+  #[value(name = "sesidentity")]
+  SesIdentity,
+  #[value(name = "usageplan")]
+  UsagePlan,
+  #[value(name = "apikey")]
+  ApiKey,
+  #[value(name = "functionurl")]
+  FunctionUrl,
+  External,
+  // A logical ID referenced but not defined in the template; see `--show-missing`.
+  Missing,
+  #[default]
   Other,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
-pub struct Name(pub String);
+impl std::fmt::Display for ResourceType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let id = match self {
+      ResourceType::Lambda => "lambda",
+      ResourceType::Sqs => "sqs",
+      ResourceType::ApiGateway => "apigateway",
+      ResourceType::EventSourceMapping => "eventsourcemapping",
+      ResourceType::DynamoDb => "dynamodb",
+      ResourceType::EventRule => "eventrule",
+      ResourceType::StateMachine => "statemachine",
+      ResourceType::Parameter => "parameter",
+      ResourceType::SsmParameter => "ssmparameter",
+      ResourceType::Secret => "secret",
+      ResourceType::Bucket => "bucket",
+      ResourceType::GlueJob => "gluejob",
+      ResourceType::GlueCrawler => "gluecrawler",
+      ResourceType::GlueDatabase => "gluedatabase",
+      ResourceType::GlueTrigger => "gluetrigger",
+      ResourceType::Firehose => "firehose",
+      ResourceType::Kinesis => "kinesis",
+      ResourceType::Redshift => "redshift",
+      ResourceType::FileSystem => "filesystem",
+      ResourceType::AccessPoint => "accesspoint",
+      ResourceType::Pipeline => "pipeline",
+      ResourceType::CodeBuild => "codebuild",
+      ResourceType::BatchJobQueue => "batchjobqueue",
+      ResourceType::BatchJobDefinition => "batchjobdefinition",
+      ResourceType::BatchComputeEnvironment => "batchcomputeenvironment",
+      ResourceType::IotRule => "iotrule",
+      ResourceType::Sns => "sns",
+      ResourceType::SesReceiptRule => "sesreceiptrule",
+      ResourceType::SesIdentity => "sesidentity",
+      ResourceType::UsagePlan => "usageplan",
+      ResourceType::ApiKey => "apikey",
+      ResourceType::FunctionUrl => "functionurl",
+      ResourceType::External => "external",
+      ResourceType::Missing => "missing",
+      ResourceType::Other => "other",
+    };
+    write!(f, "{}", id)
+  }
+}
+
+impl ResourceType {
+  // `Other` is everything without a dedicated kind. It has no shape, so it is
+  // never part of the graph; which of the rest are drawn is up to the
+  // `KeepPolicy` in `RenderOptions`.
+  pub fn is_supported(&self) -> bool {
+    !matches!(self, ResourceType::Other)
+  }
+}
+
+// Serialized with the same spelling as `--only` and `cloudmaid.toml`.
+impl Serialize for ResourceType {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl std::str::FromStr for ResourceType {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "lambda" => Ok(ResourceType::Lambda),
+      "sqs" => Ok(ResourceType::Sqs),
+      "apigateway" => Ok(ResourceType::ApiGateway),
+      "eventsourcemapping" => Ok(ResourceType::EventSourceMapping),
+      "dynamodb" => Ok(ResourceType::DynamoDb),
+      "eventrule" => Ok(ResourceType::EventRule),
+      "statemachine" => Ok(ResourceType::StateMachine),
+      "parameter" => Ok(ResourceType::Parameter),
+      "ssmparameter" => Ok(ResourceType::SsmParameter),
+      "secret" => Ok(ResourceType::Secret),
+      "bucket" => Ok(ResourceType::Bucket),
+      "gluejob" => Ok(ResourceType::GlueJob),
+      "gluecrawler" => Ok(ResourceType::GlueCrawler),
+      "gluedatabase" => Ok(ResourceType::GlueDatabase),
+      "gluetrigger" => Ok(ResourceType::GlueTrigger),
+      "firehose" => Ok(ResourceType::Firehose),
+      "kinesis" => Ok(ResourceType::Kinesis),
+      "redshift" => Ok(ResourceType::Redshift),
+      "filesystem" => Ok(ResourceType::FileSystem),
+      "accesspoint" => Ok(ResourceType::AccessPoint),
+      "pipeline" => Ok(ResourceType::Pipeline),
+      "codebuild" => Ok(ResourceType::CodeBuild),
+      "batchjobqueue" => Ok(ResourceType::BatchJobQueue),
+      "batchjobdefinition" => Ok(ResourceType::BatchJobDefinition),
+      "batchcomputeenvironment" => Ok(ResourceType::BatchComputeEnvironment),
+      "iotrule" => Ok(ResourceType::IotRule),
+      "sns" => Ok(ResourceType::Sns),
+      "sesreceiptrule" => Ok(ResourceType::SesReceiptRule),
+      "sesidentity" => Ok(ResourceType::SesIdentity),
+      "usageplan" => Ok(ResourceType::UsagePlan),
+      "apikey" => Ok(ResourceType::ApiKey),
+      "functionurl" => Ok(ResourceType::FunctionUrl),
+      "external" => Ok(ResourceType::External),
+      "missing" => Ok(ResourceType::Missing),
+      "other" => Ok(ResourceType::Other),
+      other => Err(format!("unknown resource type `{}`", other)),
+    }
+  }
+}
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+// Logical IDs are copied into every node and edge that mentions a resource, so
+// they are shared rather than cloned.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Name(pub Arc<str>);
+
+impl From<&str> for Name {
+  fn from(name: &str) -> Self {
+    Name(name.into())
+  }
+}
+
+impl From<String> for Name {
+  fn from(name: String) -> Self {
+    Name(name.into())
+  }
+}
+
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-pub struct ResourceContentsRaw {
+pub(crate) struct ResourceContentsRaw {
   #[serde(rename = "Type")]
   pub typ: String,
-  pub properties: serde_json::Value,
+  // Left as text until the type is known; see `Unparsed`. Some resources,
+  // such as `AWS::CloudFormation::WaitConditionHandle`, have none at all.
+  #[serde(default)]
+  pub properties: Option<Box<RawValue>>,
+  #[serde(default)]
+  pub metadata: serde_json::Value,
+  #[serde(default)]
+  pub depends_on: serde_json::Value,
+}
+
+impl ResourceContentsRaw {
+  // Missing properties read the same as an empty block.
+  pub fn properties(&self) -> &str {
+    self.properties.as_deref().map_or("{}", RawValue::get)
+  }
+
+  // `DependsOn` is either a single logical ID or a list of them.
+  pub fn depends_on(&self) -> Vec<String> {
+    match &self.depends_on {
+      serde_json::Value::String(name) => vec![name.clone()],
+      serde_json::Value::Array(names) => names.iter().filter_map(|name| name.as_str().map(str::to_string)).collect(),
+      _ => Vec::new(),
+    }
+  }
+
+  // `Tags` is a list of `Key` and `Value` pairs on most types but a plain map
+  // on SAM's and a few others. Values that are still intrinsic functions are
+  // left out, and properties without tags are not parsed at all.
+  pub fn tags(&self) -> Vec<(String, String)> {
+    #[derive(Deserialize)]
+    struct Tagged {
+      #[serde(rename = "Tags", default)]
+      tags: serde_json::Value,
+    }
+
+    if !self.properties().contains("\"Tags\"") {
+      return Vec::new();
+    }
+    let Ok(Tagged { tags }) = from_str(self.properties()) else {
+      return Vec::new();
+    };
+
+    let text = |value: &serde_json::Value| value.as_str().map(str::to_string);
+    match tags {
+      serde_json::Value::Array(tags) => tags.iter().filter_map(|tag| Some((text(tag.get("Key")?)?, text(tag.get("Value")?)?))).collect(),
+      serde_json::Value::Object(tags) => tags.iter().filter_map(|(key, value)| Some((key.clone(), text(value)?))).collect(),
+      _ => Vec::new(),
+    }
+  }
 }
 
-pub fn determine_resource_type(raw_type: &str) -> ResourceType {
+/// Resource types drawn as one of cloudmaid's own types, such as a third-party
+/// queue drawn as `sqs`, by the `[types]` table of `cloudmaid.toml`.
+pub type TypeMappings = BTreeMap<String, ResourceType>;
+
+pub(crate) fn determine_resource_type(raw_type: &str, type_mappings: &TypeMappings) -> ResourceType {
+  if let Some(typ) = type_mappings.get(raw_type) {
+    return typ.clone();
+  }
+
   match raw_type {
-    "AWS::Lambda::Function" => ResourceType::Lambda,
+    "AWS::Lambda::Function" | "AWS::Serverless::Function" => ResourceType::Lambda,
     "AWS::SQS::Queue" => ResourceType::Sqs,
-    "AWS::ApiGateway::Method" => ResourceType::ApiGateway,
+    "AWS::ApiGateway::Method" | "AWS::ApiGateway::RestApi" | "AWS::Serverless::Api" => ResourceType::ApiGateway,
     "AWS::Lambda::EventSourceMapping" => ResourceType::EventSourceMapping,
+    "AWS::DynamoDB::Table" | "AWS::Serverless::SimpleTable" => ResourceType::DynamoDb,
+    "AWS::Events::Rule" => ResourceType::EventRule,
+    "AWS::StepFunctions::StateMachine" | "AWS::Serverless::StateMachine" => ResourceType::StateMachine,
+    "AWS::S3::Bucket" => ResourceType::Bucket,
+    "AWS::Glue::Job" => ResourceType::GlueJob,
+    "AWS::Glue::Crawler" => ResourceType::GlueCrawler,
+    "AWS::Glue::Database" => ResourceType::GlueDatabase,
+    "AWS::Glue::Trigger" => ResourceType::GlueTrigger,
+    "AWS::KinesisFirehose::DeliveryStream" => ResourceType::Firehose,
+    "AWS::Kinesis::Stream" => ResourceType::Kinesis,
+    "AWS::Redshift::Cluster" | "AWS::RedshiftServerless::Workgroup" => ResourceType::Redshift,
+    "AWS::EFS::FileSystem" => ResourceType::FileSystem,
+    "AWS::EFS::AccessPoint" => ResourceType::AccessPoint,
+    "AWS::CodePipeline::Pipeline" => ResourceType::Pipeline,
+    "AWS::CodeBuild::Project" => ResourceType::CodeBuild,
+    "AWS::Batch::JobQueue" => ResourceType::BatchJobQueue,
+    "AWS::Batch::JobDefinition" => ResourceType::BatchJobDefinition,
+    "AWS::Batch::ComputeEnvironment" => ResourceType::BatchComputeEnvironment,
+    "AWS::IoT::TopicRule" => ResourceType::IotRule,
+    "AWS::SNS::Topic" => ResourceType::Sns,
+    "AWS::SES::ReceiptRule" => ResourceType::SesReceiptRule,
+    "AWS::SES::EmailIdentity" => ResourceType::SesIdentity,
+    "AWS::ApiGateway::UsagePlan" => ResourceType::UsagePlan,
+    "AWS::ApiGateway::ApiKey" => ResourceType::ApiKey,
+    "AWS::Lambda::Url" => ResourceType::FunctionUrl,
     _ => ResourceType::Other,
   }
 }
 
-pub fn parse_properties(
+// The variant each drawn type's properties are read into. The rest keep their
+// properties as plain JSON.
+fn variant(rt: &ResourceType) -> Option<&'static str> {
+  match rt {
+    ResourceType::Lambda => Some("Lambda"),
+    ResourceType::Sqs => Some("Sqs"),
+    ResourceType::ApiGateway => Some("ApiGateway"),
+    ResourceType::EventSourceMapping => Some("EventSourceMapping"),
+    ResourceType::DynamoDb => Some("DynamoDb"),
+    ResourceType::EventRule => Some("EventRule"),
+    _ => None,
+  }
+}
+
+pub(crate) fn parse_properties(
   rt: ResourceType,
   properties: serde_json::Value,
 ) -> Result<Property, serde_json::Error> {
+  Ok(read_properties(&rt, properties).0)
+}
+
+/// Why a resource's properties did not fit its type's variant.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Mismatch {
+  pub reason: String,
+  // The value at fault is an intrinsic function, which may well fit once
+  // pseudo parameters, parameters and mappings are resolved.
+  pub intrinsic: bool,
+}
+
+/// Reads properties into their type's variant. Properties that do not fit,
+/// such as a `RestApi` rather than a `Method`, are kept as they are, along with
+/// why they did not fit.
+pub(crate) fn read_properties(rt: &ResourceType, properties: serde_json::Value) -> (Property, Option<Mismatch>) {
+  let Some(variant) = variant(rt) else {
+    return (Property::Other(properties), None);
+  };
+
+  // The variant is the first step of the path, and the field at fault any
+  // after it.
+  let mut tagged = json!({ variant: properties });
+  match serde_path_to_error::deserialize(&tagged) {
+    Ok(property) => (property, None),
+    Err(e) => {
+      let field: Vec<String> = e.path().iter().skip(1).map(ToString::to_string).collect();
+      let reason = match field.is_empty() {
+        true => e.inner().to_string(),
+        false => format!("`{}`: {}", field.join("."), e.inner()),
+      };
+      let at_fault = e.path().iter().skip(1).try_fold(&tagged[variant], |value, segment| match segment {
+        Segment::Map { key } => value.get(key),
+        Segment::Seq { index } => value.get(index),
+        _ => None,
+      });
+      let intrinsic = at_fault.is_some_and(is_intrinsic);
+      (Property::Other(tagged[variant].take()), Some(Mismatch { reason, intrinsic }))
+    }
+  }
+}
+
+// `{ "Ref": ... }` or `{ "Fn::...": ... }`.
+fn is_intrinsic(value: &serde_json::Value) -> bool {
+  value.as_object().is_some_and(|map| map.len() == 1 && map.keys().all(|key| key == "Ref" || key.starts_with("Fn::")))
+}
+
+pub(crate) fn parse_raw_properties(
+  rt: ResourceType,
+  properties: Option<Box<RawValue>>,
+) -> Result<(Property, Option<Mismatch>), serde_json::Error> {
+  let properties = match properties {
+    Some(properties) => properties,
+    None => RawValue::from_string("{}".to_string())?,
+  };
   match rt {
-    ResourceType::Other => Ok(Property::Other(properties)),
-    _ => from_value(properties),
+    ResourceType::Other => Ok((Property::Unparsed(Unparsed::new(properties)), None)),
+    _ => Ok(read_properties(&rt, from_str(properties.get())?)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_mismatched_properties_name_the_field() {
+    let (property, mismatch) = read_properties(&ResourceType::EventRule, json!({ "Targets": { "Ref": "Targets" }, "State": "ENABLED" }));
+    let mismatch = mismatch.unwrap();
+    assert_eq!(property, Property::Other(json!({ "Targets": { "Ref": "Targets" }, "State": "ENABLED" })));
+    assert!(mismatch.reason.starts_with("`Targets`: invalid type"));
+    assert!(mismatch.intrinsic);
+
+    let (_, mismatch) = read_properties(&ResourceType::EventRule, json!({ "Targets": "rule" }));
+    assert!(!mismatch.unwrap().intrinsic);
+
+    // A name built by an intrinsic is still a name.
+    let (property, reason) = read_properties(&ResourceType::Sqs, json!({ "QueueName": { "Ref": "Name" }, "DelaySeconds": 5 }));
+    assert_eq!(property, Property::Sqs { queue_name: json!({ "Ref": "Name" }) });
+    assert_eq!(reason, None);
+
+    let (_, reason) = read_properties(&ResourceType::ApiGateway, json!({ "AuthorizationType": "NONE" }));
+    assert_eq!(reason.unwrap().reason, "missing field `HttpMethod`");
+
+    let (_, reason) = read_properties(&ResourceType::Sqs, json!({ "QueueName": "jobs" }));
+    assert_eq!(reason, None);
+
+    // CDK leaves table names to CloudFormation.
+    let (property, reason) = read_properties(&ResourceType::DynamoDb, json!({ "BillingMode": "PAY_PER_REQUEST" }));
+    assert!(matches!(property, Property::DynamoDb { table_name: serde_json::Value::Null, .. }));
+    assert_eq!(reason, None);
   }
 }
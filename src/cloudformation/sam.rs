@@ -0,0 +1,244 @@
+use serde_json::value::{RawValue, to_raw_value};
+use serde_json::{Map, Value, json};
+use tracing::debug;
+
+use crate::cloudformation::resource::ResourceContentsRaw;
+
+const SERVERLESS_FUNCTION: &str = "AWS::Serverless::Function";
+const IMPLICIT_REST_API: &str = "ServerlessRestApi";
+const IMPLICIT_HTTP_API: &str = "ServerlessHttpApi";
+
+// Mirrors what the SAM transform does: function `Events` and `FunctionUrlConfig`
+// become the implicit resources (event source mappings, API methods, function
+// URLs) that the plain CloudFormation pipeline already knows how to draw edges
+// for.
+pub fn expand(key: String, mut raw: ResourceContentsRaw) -> Vec<(String, ResourceContentsRaw)> {
+  if raw.typ != SERVERLESS_FUNCTION {
+    return vec![(key, raw)];
+  }
+
+  let mut properties: Value = serde_json::from_str(raw.properties()).unwrap_or_default();
+  let events = properties.as_object_mut().and_then(|properties| properties.remove("Events"));
+  let url = properties.as_object_mut().and_then(|properties| properties.remove("FunctionUrlConfig"));
+  raw.properties = Some(text(&properties));
+
+  let mut implicit = match events {
+    Some(Value::Object(events)) => expand_events(&key, events),
+    _ => Vec::new(),
+  };
+  if let Some(url) = url {
+    implicit.push((format!("{}Url", key), function_url(&key, &url)));
+  }
+
+  let mut expanded = vec![(key, raw)];
+  expanded.extend(implicit);
+  expanded
+}
+
+fn expand_events(function: &str, events: Map<String, Value>) -> Vec<(String, ResourceContentsRaw)> {
+  events
+    .into_iter()
+    .filter_map(|(event_name, event)| {
+      let typ = event.get("Type")?.as_str()?;
+      let properties = event.get("Properties").cloned().unwrap_or(json!({}));
+      let name = format!("{}{}", function, event_name);
+
+      // A topic subscription or bucket notification joins a source to the
+      // function just as a mapping does, so it is drawn as one.
+      match typ {
+        "SQS" => Some((name, event_source_mapping(function, properties.get("Queue")?))),
+        "DynamoDB" | "Kinesis" => Some((name, event_source_mapping(function, properties.get("Stream")?))),
+        "SNS" => Some((name, event_source_mapping(function, properties.get("Topic")?))),
+        "S3" => Some((name, event_source_mapping(function, properties.get("Bucket")?))),
+        "Api" => Some((name, api_method(function, &properties, "RestApiId", IMPLICIT_REST_API))),
+        "HttpApi" => Some((name, api_method(function, &properties, "ApiId", IMPLICIT_HTTP_API))),
+        _ => {
+          debug!("{}: skipping event {}, as {} events are not drawn", function, event_name, typ);
+          None
+        }
+      }
+    })
+    .collect()
+}
+
+fn event_source_mapping(function: &str, source_arn: &Value) -> ResourceContentsRaw {
+  ResourceContentsRaw {
+    typ: "AWS::Lambda::EventSourceMapping".to_string(),
+    properties: Some(text(&json!({
+      "EventSourceArn": source_arn,
+      "FunctionName": { "Ref": function },
+    }))),
+    metadata: Value::Null,
+    depends_on: Value::Null,
+  }
+}
+
+// Events without an API of their own go to the one SAM creates implicitly.
+fn api_method(function: &str, properties: &Value, api_key: &str, implicit_api: &str) -> ResourceContentsRaw {
+  let http_method = properties
+    .get("Method")
+    .and_then(Value::as_str)
+    .map(|method| method.to_uppercase())
+    .unwrap_or_else(|| "ANY".to_string());
+  let rest_api_id = properties.get(api_key).cloned().unwrap_or(json!({ "Ref": implicit_api }));
+
+  ResourceContentsRaw {
+    typ: "AWS::ApiGateway::Method".to_string(),
+    properties: Some(text(&json!({
+      "HttpMethod": http_method,
+      "ResourcePath": properties.get("Path").cloned().unwrap_or(Value::Null),
+      "RestApiId": rest_api_id,
+      "Integration": {
+        "Type": "AWS_PROXY",
+        "Uri": {
+          "Fn::Sub": format!(
+            "arn:${{AWS::Partition}}:apigateway:${{AWS::Region}}:lambda:path/2015-03-31/functions/${{{}.Arn}}/invocations",
+            function
+          )
+        }
+      }
+    }))),
+    metadata: Value::Null,
+    depends_on: Value::Null,
+  }
+}
+
+fn function_url(function: &str, config: &Value) -> ResourceContentsRaw {
+  ResourceContentsRaw {
+    typ: "AWS::Lambda::Url".to_string(),
+    properties: Some(text(&json!({
+      "TargetFunctionArn": { "Ref": function },
+      "AuthType": config.get("AuthType").cloned().unwrap_or(json!("AWS_IAM")),
+    }))),
+    metadata: Value::Null,
+    depends_on: Value::Null,
+  }
+}
+
+fn text(value: &Value) -> Box<RawValue> {
+  to_raw_value(value).expect("a Value always serializes")
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ast::ast::AST;
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::template::Template;
+
+  #[test]
+  fn test_sam_function_events() {
+    let json_data = r#"
+    {
+        "Transform": "AWS::Serverless-2016-10-31",
+        "Resources": {
+            "MyQueue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": { "QueueName": "my-queue" }
+            },
+            "MyTable": {
+                "Type": "AWS::Serverless::SimpleTable",
+                "Properties": { "TableName": "my-table" }
+            },
+            "MyFunction": {
+                "Type": "AWS::Serverless::Function",
+                "Properties": {
+                    "Handler": "index.handler",
+                    "Runtime": "nodejs20.x",
+                    "Events": {
+                        "Queue": {
+                            "Type": "SQS",
+                            "Properties": { "Queue": { "Fn::GetAtt": ["MyQueue", "Arn"] } }
+                        },
+                        "Get": {
+                            "Type": "Api",
+                            "Properties": { "Path": "/items", "Method": "get" }
+                        },
+                        "Schedule": {
+                            "Type": "Schedule",
+                            "Properties": { "Schedule": "rate(1 hour)" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    "#;
+
+    let template: Template = serde_json::from_str(json_data).unwrap();
+    let names: Vec<&str> = template.resources.iter().map(|r| &*r.name.0).collect();
+
+    assert_eq!(names, vec!["MyQueue", "MyTable", "MyFunction", "MyFunctionQueue", "MyFunctionGet"]);
+
+    let mermaid_output = AST::from(template).to_mermaid();
+    let expected_output = "```mermaid\nflowchart LR\nMyFunctionGet[[MyFunctionGet]] -->|invokes| MyFunction([MyFunction])\nMyQueue((\"my-queue\")) -.->|triggers| MyFunction([MyFunction])\n```";
+
+    assert_eq!(mermaid_output, expected_output);
+  }
+
+  #[test]
+  fn test_function_urls() {
+    let template: Template = serde_json::from_value(serde_json::json!({
+      "Transform": "AWS::Serverless-2016-10-31",
+      "Resources": {
+        "Webhook": {
+          "Type": "AWS::Serverless::Function",
+          "Properties": { "FunctionName": "webhook", "FunctionUrlConfig": { "AuthType": "NONE" } }
+        },
+        "Status": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "status" } },
+        "StatusUrl": {
+          "Type": "AWS::Lambda::Url",
+          "Properties": { "TargetFunctionArn": { "Fn::GetAtt": ["Status", "Arn"] }, "AuthType": "AWS_IAM" }
+        }
+      }
+    }))
+    .unwrap();
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "StatusUrl[[StatusUrl]] -->|invokes| Status([\"status\"])\n",
+      "WebhookUrl[[WebhookUrl]] -->|invokes| Webhook([\"webhook\"])\n",
+      "```",
+    );
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_topic_bucket_and_http_api_events() {
+    let template: Template = serde_json::from_value(serde_json::json!({
+      "Transform": "AWS::Serverless-2016-10-31",
+      "Resources": {
+        "Alerts": { "Type": "AWS::SNS::Topic", "Properties": { "TopicName": "alerts" } },
+        "Uploads": { "Type": "AWS::S3::Bucket", "Properties": { "BucketName": "uploads" } },
+        "Handler": {
+          "Type": "AWS::Serverless::Function",
+          "Properties": {
+            "FunctionName": "handler",
+            "Events": {
+              "Alert": { "Type": "SNS", "Properties": { "Topic": { "Ref": "Alerts" } } },
+              "Upload": { "Type": "S3", "Properties": { "Bucket": { "Ref": "Uploads" }, "Events": "s3:ObjectCreated:*" } },
+              "Get": { "Type": "HttpApi", "Properties": { "Path": "/items", "Method": "get" } },
+              "Nightly": { "Type": "Schedule", "Properties": { "Schedule": "rate(1 day)" } }
+            }
+          }
+        }
+      }
+    }))
+    .unwrap();
+
+    let get = template.resource("HandlerGet").unwrap();
+    let Property::ApiGateway { rest_api_id, .. } = &*get.properties else {
+      panic!("expected a method, got {:?}", get.properties);
+    };
+    assert_eq!(rest_api_id, &serde_json::json!({ "Ref": "ServerlessHttpApi" }));
+
+    let expected_output = concat!(
+      "```mermaid\nflowchart LR\n",
+      "Alerts((\"alerts\")) -.->|subscribes| Handler([\"handler\"])\n",
+      "HandlerGet[[HandlerGet]] -->|invokes| Handler([\"handler\"])\n",
+      "Uploads[(\"uploads\")] -.->|triggers| Handler([\"handler\"])\n",
+      "```",
+    );
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+}
@@ -0,0 +1,7 @@
+use crate::cloudformation::template::Template;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stack {
+  pub name: String,
+  pub template: Template,
+}
@@ -1,61 +1,229 @@
-use serde::de::{MapAccess, Visitor};
+use serde::de::{DeserializeSeed, IgnoredAny, MapAccess, Visitor};
 use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, OnceLock};
+use tracing::{debug, warn};
 
+use crate::cloudformation::output::Output;
+use crate::cloudformation::parameter::Parameter;
 use crate::cloudformation::resource::{
-  Name, Resource, ResourceContentsRaw, determine_resource_type, parse_properties,
+  Name, Resource, ResourceContentsRaw, ResourceType, TypeMappings, determine_resource_type, parse_raw_properties,
 };
+use crate::cloudformation::{alias, sam};
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
-#[serde(rename_all = "PascalCase")]
+pub type Mappings = BTreeMap<String, BTreeMap<String, BTreeMap<String, serde_json::Value>>>;
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct Template {
-  #[serde(deserialize_with = "deserialize_resources")]
+  pub format_version: Option<String>,
+  pub description: Option<String>,
+  pub metadata: BTreeMap<String, serde_json::Value>,
+  pub transform: Vec<serde_json::Value>,
   pub resources: Vec<Resource>,
+  pub conditions: BTreeMap<String, serde_json::Value>,
+  pub mappings: Mappings,
+  pub parameters: BTreeMap<String, Parameter>,
+  pub outputs: BTreeMap<String, Output>,
+  pub(crate) names: Names,
+}
+
+/// Where each resource is in `resources`, by logical ID, built on the first
+/// lookup. Resources are only added or dropped before a template is drawn, so
+/// an index from before that is noticed by its length and not used.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Names(OnceLock<(usize, HashMap<Arc<str>, usize>)>);
+
+// Two templates with the same resources are equal however far either has
+// been looked into.
+impl PartialEq for Names {
+  fn eq(&self, _: &Self) -> bool {
+    true
+  }
 }
 
-fn deserialize_resources<'de, D>(deserializer: D) -> Result<Vec<Resource>, D::Error>
+// Errors say where in the template they happened, such as `at
+// Resources.Queue: missing field `Type``, so that the one broken resource in a
+// stack of hundreds can be found.
+pub fn deserialize<'de, D>(deserializer: D, type_mappings: &TypeMappings) -> Result<Template, String>
 where
   D: Deserializer<'de>,
+  D::Error: std::fmt::Display,
 {
-  struct ResourcesVisitor;
+  let mut track = serde_path_to_error::Track::new();
+  TemplateSeed(type_mappings)
+    .deserialize(serde_path_to_error::Deserializer::new(deserializer, &mut track))
+    .map_err(|e| match track.path() {
+      path if path.iter().next().is_some() => format!("at {}: {}", path, e),
+      _ => e.to_string(),
+    })
+}
+
+/// Deserializes a template, typing its resources with the given mappings
+/// before the built-in ones. A plain `Deserialize` uses only the built-in ones.
+pub struct TemplateSeed<'a>(pub &'a TypeMappings);
+
+impl<'de> Deserialize<'de> for Template {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Template, D::Error> {
+    TemplateSeed(&TypeMappings::new()).deserialize(deserializer)
+  }
+}
+
+impl<'de> DeserializeSeed<'de> for TemplateSeed<'_> {
+  type Value = Template;
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Template, D::Error> {
+    deserializer.deserialize_map(self)
+  }
+}
+
+impl<'de> Visitor<'de> for TemplateSeed<'_> {
+  type Value = Template;
 
-  impl<'de> Visitor<'de> for ResourcesVisitor {
-    type Value = Vec<Resource>;
+  fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    formatter.write_str("a template")
+  }
+
+  fn visit_map<M>(self, mut access: M) -> Result<Template, M::Error>
+  where
+    M: MapAccess<'de>,
+  {
+    let mut template = Template::default();
+    let mut resources = None;
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-      formatter.write_str("a map of resources")
+    while let Some(key) = access.next_key::<String>()? {
+      match key.as_str() {
+        "AWSTemplateFormatVersion" => template.format_version = access.next_value()?,
+        "Description" => template.description = access.next_value()?,
+        "Metadata" => template.metadata = access.next_value()?,
+        "Transform" => template.transform = transforms(access.next_value()?),
+        "Resources" => resources = Some(access.next_value_seed(Resources(self.0))?),
+        "Conditions" => template.conditions = access.next_value()?,
+        "Mappings" => template.mappings = access.next_value()?,
+        "Parameters" => template.parameters = access.next_value()?,
+        "Outputs" => template.outputs = access.next_value()?,
+        _ => {
+          access.next_value::<IgnoredAny>()?;
+        }
+      }
     }
 
-    fn visit_map<M>(self, mut access: M) -> Result<Vec<Resource>, M::Error>
-    where
-      M: MapAccess<'de>,
-    {
-      let mut resources = Vec::new();
+    template.resources = resources.ok_or_else(|| serde::de::Error::missing_field("Resources"))?;
+    Ok(template)
+  }
+}
+
+const SAM_TRANSFORM: &str = "AWS::Serverless-2016-10-31";
+
+impl Template {
+  // The SAM transform adds resources of its own, such as `ServerlessRestApi`
+  // and each function's role, which may be referenced without being defined.
+  pub fn is_sam(&self) -> bool {
+    self.transform.iter().any(|transform| transform == SAM_TRANSFORM)
+  }
+
+  /// The resource with the given logical ID.
+  pub fn resource(&self, name: &str) -> Option<&Resource> {
+    let (length, names) = self.names.0.get_or_init(|| {
+      let names = self.resources.iter().enumerate().map(|(index, resource)| (resource.name.0.clone(), index)).collect();
+      (self.resources.len(), names)
+    });
+    if *length != self.resources.len() {
+      return self.resources.iter().find(|resource| *resource.name.0 == *name);
+    }
+    names.get(name).map(|&index| &self.resources[index]).filter(|resource| *resource.name.0 == *name)
+  }
+}
+
+// A single transform may be given on its own rather than in a list.
+fn transforms(transform: serde_json::Value) -> Vec<serde_json::Value> {
+  match transform {
+    serde_json::Value::Array(transforms) => transforms,
+    serde_json::Value::Null => Vec::new(),
+    transform => vec![transform],
+  }
+}
+
+struct Resources<'a>(&'a TypeMappings);
+
+impl<'de> DeserializeSeed<'de> for Resources<'_> {
+  type Value = Vec<Resource>;
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Vec<Resource>, D::Error> {
+    deserializer.deserialize_map(self)
+  }
+}
+
+impl<'de> Visitor<'de> for Resources<'_> {
+  type Value = Vec<Resource>;
 
-      while let Some((key, raw_value)) = access.next_entry::<String, ResourceContentsRaw>()? {
-        let typ = determine_resource_type(&raw_value.typ);
+  fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    formatter.write_str("a map of resources")
+  }
 
-        let properties = parse_properties(typ.clone(), raw_value.properties)
-          .map_err(|_| serde::de::Error::custom("Failed to parse properties"))?;
+  fn visit_map<M>(self, mut access: M) -> Result<Vec<Resource>, M::Error>
+  where
+    M: MapAccess<'de>,
+  {
+    let mut resources = Vec::new();
+    let mut collapse = alias::Collapse::default();
 
-        resources.push(Resource {
-          name: Name(key),
-          typ,
-          properties,
-        });
+    // Each resource is parsed as soon as it is read, so only the JSON of the
+    // one being read is held rather than that of the whole template.
+    while let Some(key) = access.next_key::<String>()? {
+      let raw_value: ResourceContentsRaw = access.next_value()?;
+      for (key, raw_value) in sam::expand(key, raw_value) {
+        collapse.note(&key, &raw_value);
+        resources.push(resource(key, raw_value, self.0).map_err(serde::de::Error::custom)?);
       }
-      Ok(resources)
+    }
+
+    collapse.apply(&mut resources).map_err(serde::de::Error::custom)?;
+    Ok(resources)
+  }
+}
+
+const UNTYPED: [&str; 2] = ["AWS::ApiGateway::RestApi", "AWS::Serverless::Api"];
+
+fn resource(key: String, raw_value: ResourceContentsRaw, type_mappings: &TypeMappings) -> Result<Resource, String> {
+  let typ = determine_resource_type(&raw_value.typ, type_mappings);
+  match typ {
+    ResourceType::Other => debug!("{}: skipped, {} is not drawn", key, raw_value.typ),
+    _ => debug!("{}: recognized {} as {}", key, raw_value.typ, typ),
+  }
+  let depends_on = raw_value.depends_on();
+  let tags = raw_value.tags();
+
+  let (properties, mismatch) =
+    parse_raw_properties(typ.clone(), raw_value.properties).map_err(|e| format!("properties of {}: {}", key, e))?;
+  // REST APIs are drawn like their methods but have none of a method's
+  // properties, so only other types are expected to fit their variant.
+  if let Some(mismatch) = mismatch
+    && !UNTYPED.contains(&raw_value.typ.as_str())
+  {
+    // An intrinsic usually fits once it is resolved, which comes later.
+    match mismatch.intrinsic {
+      true => debug!("{}: properties of {} do not fit {} until resolved: {}", key, raw_value.typ, typ, mismatch.reason),
+      false => warn!("{}: properties of {} do not fit {} and are kept untyped: {}", key, raw_value.typ, typ, mismatch.reason),
     }
   }
 
-  deserializer.deserialize_map(ResourcesVisitor)
+  Ok(Resource {
+    name: Name::from(key),
+    typ,
+    properties: properties.into(),
+    metadata: raw_value.metadata,
+    depends_on,
+    aliases: Vec::new(),
+    tags,
+  })
 }
 
 #[cfg(test)]
 mod test {
   use serde_json::json;
 
-  use crate::cloudformation::property::Property;
-  use crate::cloudformation::resource::{Name, ResourceType};
+  use crate::cloudformation::property::{Property, Unparsed};
+  use crate::cloudformation::resource::Name;
 
   use super::*;
 
@@ -86,9 +254,9 @@ mod test {
     "#;
 
     let expected_resources = vec![Resource {
-      name: Name("myresource1".to_string()),
+      name: Name::from("myresource1"),
       typ: ResourceType::Other,
-      properties: Property::Other(json!({
+      properties: Property::Unparsed(Unparsed::from(json!({
         "AssumeRolePolicyDocument": {
           "Statement": [
             {
@@ -101,7 +269,9 @@ mod test {
           ],
           "Version": "2012-10-17"
         }
-      })),
+      })))
+      .into(),
+      ..Default::default()
     }];
 
     let template: Template = serde_json::from_str(json_data).unwrap();
@@ -139,16 +309,143 @@ mod test {
       "#;
 
     let expected_resources = vec![Resource {
-      name: Name("myLambdaFunction".to_string()),
+      name: Name::from("myLambdaFunction"),
       typ: ResourceType::Lambda,
       properties: Property::Lambda {
-        function_name: "undefined-sample-core-adoption-update".to_string(),
+        function_name: "undefined-sample-core-adoption-update".into(),
         architectures: vec!["arm64".to_string()],
-      },
+        environment: json!({
+          "Variables": {
+            "account": "202468521054",
+            "region": "eu-west-2",
+            "EVENT_BUS_NAME": {
+              "Fn::ImportValue": "undefined-sample-eventbus-export-name"
+            }
+          }
+        }),
+        role: serde_json::Value::Null,
+        file_system_configs: serde_json::Value::Null,
+      }.into(),
+      ..Default::default()
     }];
 
     let template: Template = serde_json::from_str(json_data).unwrap();
 
     assert_eq!(template.resources, expected_resources);
   }
+
+  #[test]
+  fn test_skipped_types_are_left_unparsed() {
+    let json_data = r#"{ "Resources": { "Logs": { "Type": "AWS::Logs::LogGroup", "Properties": { "RetentionInDays": 7 } } } }"#;
+
+    let streamed: Template = serde_json::from_str(json_data).unwrap();
+    let from_value: Template = serde_json::from_value(serde_json::from_str(json_data).unwrap()).unwrap();
+
+    for template in [streamed, from_value] {
+      assert!(matches!(&*template.resources[0].properties, Property::Unparsed(_)));
+      assert_eq!(template.resources[0].properties.untyped(), Some(&json!({ "RetentionInDays": 7 })));
+    }
+  }
+
+  #[test]
+  fn test_resources_without_properties() {
+    let json_data = r#"
+      {
+        "Resources": {
+          "Handle": { "Type": "AWS::CloudFormation::WaitConditionHandle" },
+          "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } }
+        }
+      }
+    "#;
+
+    let template: Template = serde_json::from_str(json_data).unwrap();
+
+    assert_eq!(template.resources.len(), 2);
+    assert_eq!(template.resources[0].properties.untyped(), Some(&json!({})));
+  }
+
+  #[test]
+  fn test_template_sections() {
+    let json_data = r#"
+      {
+        "AWSTemplateFormatVersion": "2010-09-09",
+        "Description": "Order processing",
+        "Metadata": { "AWS::CloudFormation::Interface": { "ParameterGroups": [] } },
+        "Transform": "AWS::Serverless-2016-10-31",
+        "Conditions": { "IsProd": { "Fn::Equals": [{ "Ref": "Stage" }, "prod"] } },
+        "Resources": {
+          "Queue": { "Type": "AWS::SQS::Queue", "Condition": "IsProd", "Properties": { "QueueName": "jobs" } }
+        },
+        "Outputs": { "QueueUrl": { "Description": "Where jobs go", "Value": { "Ref": "Queue" } } }
+      }
+    "#;
+
+    let template: Template = serde_json::from_str(json_data).unwrap();
+
+    assert_eq!(template.format_version.as_deref(), Some("2010-09-09"));
+    assert_eq!(template.description.as_deref(), Some("Order processing"));
+    assert!(template.metadata.contains_key("AWS::CloudFormation::Interface"));
+    assert_eq!(template.transform, vec![json!("AWS::Serverless-2016-10-31")]);
+    assert!(template.conditions.contains_key("IsProd"));
+    assert_eq!(template.resources.len(), 1);
+    assert_eq!(template.outputs["QueueUrl"].description.as_deref(), Some("Where jobs go"));
+
+    let listed: Template =
+      serde_json::from_value(json!({ "Transform": ["AWS::LanguageExtensions", "AWS::Serverless-2016-10-31"], "Resources": {} }))
+        .unwrap();
+    assert_eq!(listed.transform.len(), 2);
+  }
+
+  #[test]
+  fn test_errors_say_where_they_happened() {
+    let error = |template: serde_json::Value| deserialize(template, &TypeMappings::new()).unwrap_err();
+
+    assert_eq!(
+      error(json!({ "Resources": { "Queue": { "Type": "AWS::SQS::Queue" }, "Broken": { "Properties": {} } } })),
+      "at Resources.Broken: missing field `Type`"
+    );
+    assert_eq!(
+      error(json!({ "Parameters": { "Stage": { "Default": "dev" } }, "Resources": {} })),
+      "at Parameters.Stage: missing field `Type`"
+    );
+    assert_eq!(
+      error(json!({ "Mappings": { "Queues": { "dev": "jobs" } }, "Resources": {} })),
+      "at Mappings.Queues.dev: invalid type: string \"jobs\", expected a map"
+    );
+
+    let mut streamed = serde_json::Deserializer::from_str("{\n  \"Resources\": {\n    \"Broken\": {}\n  }\n}");
+    let streamed = deserialize(&mut streamed, &TypeMappings::new()).unwrap_err();
+    assert!(streamed.starts_with("at Resources.Broken: missing field `Type` at line "));
+    assert_eq!(streamed.matches(" at line ").count(), 1);
+  }
+
+  #[test]
+  fn test_type_mappings_are_given_per_template() {
+    let template = json!({ "Resources": { "Jobs": { "Type": "Custom::Queue", "Properties": { "QueueName": "jobs" } } } });
+    let mappings = TypeMappings::from([("Custom::Queue".to_string(), ResourceType::Sqs)]);
+
+    let mapped = deserialize(template.clone(), &mappings).unwrap();
+    let unmapped = deserialize(template, &TypeMappings::new()).unwrap();
+
+    assert_eq!(*mapped.resources[0].properties, Property::Sqs { queue_name: "jobs".into() });
+    assert_eq!(unmapped.resources[0].typ, ResourceType::Other);
+  }
+
+  #[test]
+  fn test_resources_by_logical_id() {
+    let mut template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Table": { "Type": "AWS::DynamoDB::Table", "Properties": {} }
+      }
+    }))
+    .unwrap();
+
+    assert_eq!(template.resource("Table").map(|resource| &*resource.name.0), Some("Table"));
+    assert!(template.resource("Missing").is_none());
+
+    template.resources.remove(0);
+    assert_eq!(template.resource("Table").map(|resource| &*resource.name.0), Some("Table"));
+    assert!(template.resource("Queue").is_none());
+  }
 }
@@ -0,0 +1,159 @@
+use serde_json::{Map, Value as JsonValue};
+use serde_yaml::Value as YamlValue;
+use serde_yaml::value::TaggedValue;
+
+use crate::cloudformation::resource::TypeMappings;
+use crate::cloudformation::template::{self, Template};
+
+pub fn from_str(contents: &str) -> Result<Template, serde_yaml::Error> {
+  let value: YamlValue = serde_yaml::from_str(contents)?;
+
+  template::deserialize(to_json(value), &TypeMappings::new()).map_err(serde::de::Error::custom)
+}
+
+pub fn is_yaml_path(path: &str) -> bool {
+  path.ends_with(".yaml") || path.ends_with(".yml")
+}
+
+pub fn to_json(value: YamlValue) -> JsonValue {
+  match value {
+    YamlValue::Null => JsonValue::Null,
+    YamlValue::Bool(b) => JsonValue::Bool(b),
+    YamlValue::Number(n) => number_to_json(&n),
+    YamlValue::String(s) => JsonValue::String(s),
+    YamlValue::Sequence(items) => JsonValue::Array(items.into_iter().map(to_json).collect()),
+    YamlValue::Mapping(mapping) => {
+      let mut map = Map::new();
+      for (key, value) in mapping {
+        map.insert(key_to_string(key), to_json(value));
+      }
+      JsonValue::Object(map)
+    }
+    YamlValue::Tagged(tagged) => expand_intrinsic(*tagged),
+  }
+}
+
+// Short-form intrinsics (`!Ref`, `!GetAtt`, `!Sub`, ...) are rewritten into the
+// long form used by JSON templates so the rest of the pipeline only sees one shape.
+fn expand_intrinsic(tagged: TaggedValue) -> JsonValue {
+  let tag = tagged.tag.to_string();
+  let name = tag.trim_start_matches('!');
+  let value = tagged.value;
+
+  let (key, body) = match name {
+    "Ref" | "Condition" => (name.to_string(), to_json(value)),
+    "GetAtt" => ("Fn::GetAtt".to_string(), get_att_to_json(value)),
+    _ => (format!("Fn::{}", name), to_json(value)),
+  };
+
+  let mut map = Map::new();
+  map.insert(key, body);
+  JsonValue::Object(map)
+}
+
+fn get_att_to_json(value: YamlValue) -> JsonValue {
+  match value {
+    YamlValue::String(s) => match s.split_once('.') {
+      Some((resource, attribute)) => JsonValue::Array(vec![
+        JsonValue::String(resource.to_string()),
+        JsonValue::String(attribute.to_string()),
+      ]),
+      None => JsonValue::Array(vec![JsonValue::String(s)]),
+    },
+    other => to_json(other),
+  }
+}
+
+fn number_to_json(n: &serde_yaml::Number) -> JsonValue {
+  if let Some(i) = n.as_i64() {
+    JsonValue::from(i)
+  } else if let Some(u) = n.as_u64() {
+    JsonValue::from(u)
+  } else {
+    n.as_f64().map(JsonValue::from).unwrap_or(JsonValue::Null)
+  }
+}
+
+fn key_to_string(key: YamlValue) -> String {
+  match key {
+    YamlValue::String(s) => s,
+    YamlValue::Bool(b) => b.to_string(),
+    YamlValue::Number(n) => n.to_string(),
+    other => serde_yaml::to_string(&other).unwrap_or_default().trim().to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::{Name, Resource, ResourceType};
+
+  use super::*;
+
+  #[test]
+  fn test_short_form_intrinsics() {
+    let yaml = r#"
+Ref: !Ref MyQueue
+GetAtt: !GetAtt MyQueue.Arn
+GetAttList: !GetAtt [MyQueue, Arn]
+Sub: !Sub "arn:${AWS::Partition}:sqs:${MyQueue}"
+Join: !Join ["", ["a", !Ref MyQueue]]
+"#;
+    let value: YamlValue = serde_yaml::from_str(yaml).unwrap();
+
+    assert_eq!(
+      to_json(value),
+      json!({
+        "Ref": { "Ref": "MyQueue" },
+        "GetAtt": { "Fn::GetAtt": ["MyQueue", "Arn"] },
+        "GetAttList": { "Fn::GetAtt": ["MyQueue", "Arn"] },
+        "Sub": { "Fn::Sub": "arn:${AWS::Partition}:sqs:${MyQueue}" },
+        "Join": { "Fn::Join": ["", ["a", { "Ref": "MyQueue" }]] }
+      })
+    );
+  }
+
+  #[test]
+  fn test_deserialize_yaml_template() {
+    let yaml = r#"
+AWSTemplateFormatVersion: "2010-09-09"
+Resources:
+  MyQueue:
+    Type: AWS::SQS::Queue
+    Properties:
+      QueueName: my-queue
+  MyEventSourceMapping:
+    Type: AWS::Lambda::EventSourceMapping
+    Properties:
+      EventSourceArn: !GetAtt MyQueue.Arn
+      FunctionName: !Ref MyLambda
+"#;
+
+    let template = from_str(yaml).unwrap();
+
+    assert_eq!(
+      template.resources,
+      vec![
+        Resource {
+          name: Name::from("MyQueue"),
+          typ: ResourceType::Sqs,
+          properties: Property::Sqs {
+            queue_name: "my-queue".into(),
+          }.into(),
+          ..Default::default()
+        },
+        Resource {
+          name: Name::from("MyEventSourceMapping"),
+          typ: ResourceType::EventSourceMapping,
+          properties: Property::EventSourceMapping {
+            event_source_arn: json!({ "Fn::GetAtt": ["MyQueue", "Arn"] }),
+            function_name: json!({ "Ref": "MyLambda" }),
+          }.into(),
+          ..Default::default()
+        },
+      ]
+    );
+  }
+}
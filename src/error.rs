@@ -0,0 +1,78 @@
+use std::io;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, CloudmaidError>;
+
+// Every message names what it is about (a file, a resource or a stack) so it
+// can be acted on without a backtrace.
+#[derive(Debug, Error)]
+pub enum CloudmaidError {
+  #[error("{}: {source}", path.display())]
+  Io { path: PathBuf, source: io::Error },
+  #[error("{0}: no such file or directory")]
+  NotFound(String),
+  #[error("{0}: no files match")]
+  NoMatch(String),
+  #[error("{path}: {message}")]
+  Parse { path: String, message: String },
+  #[error("{}: {message}", path.display())]
+  Config { path: PathBuf, message: String },
+  #[error("resource {0} not found")]
+  UnknownResource(String),
+  #[error("{0}")]
+  Usage(String),
+  #[error("stack {stack}: {message}")]
+  Aws { stack: String, message: String },
+  #[error("{0}")]
+  Render(String),
+  #[error("watching inputs: {0}")]
+  Watch(#[from] notify::Error),
+}
+
+impl CloudmaidError {
+  pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+    CloudmaidError::Io {
+      path: path.into(),
+      source,
+    }
+  }
+
+  pub fn parse(path: impl std::fmt::Display, message: impl std::fmt::Display) -> Self {
+    CloudmaidError::Parse {
+      path: path.to_string(),
+      message: message.to_string(),
+    }
+  }
+
+  // Exit codes follow sysexits(3), so scripts can tell a bad template from a
+  // missing file or a broken install.
+  pub fn exit_code(&self) -> i32 {
+    match self {
+      CloudmaidError::Usage(_) | CloudmaidError::UnknownResource(_) => 64,
+      CloudmaidError::Parse { .. } => 65,
+      CloudmaidError::NotFound(_) | CloudmaidError::NoMatch(_) => 66,
+      CloudmaidError::Aws { .. } => 69,
+      CloudmaidError::Render(_) => 70,
+      CloudmaidError::Io { .. } | CloudmaidError::Watch(_) => 74,
+      CloudmaidError::Config { .. } => 78,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_messages_and_exit_codes() {
+    let missing = CloudmaidError::io("stacks/app.yaml", io::Error::from(io::ErrorKind::PermissionDenied));
+    let parse = CloudmaidError::parse("app.json", "resource Queue: missing field `QueueName`");
+
+    assert_eq!(missing.to_string(), "stacks/app.yaml: permission denied");
+    assert_eq!(parse.to_string(), "app.json: resource Queue: missing field `QueueName`");
+    assert_eq!(CloudmaidError::UnknownResource("Worker".to_string()).to_string(), "resource Worker not found");
+    assert_eq!((parse.exit_code(), CloudmaidError::NotFound(String::new()).exit_code(), missing.exit_code()), (65, 66, 74));
+  }
+}
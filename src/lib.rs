@@ -1,3 +1,43 @@
+//! CloudFormation templates and CDK cloud assemblies to Mermaid and PlantUML
+//! diagrams.
+//!
+//! Start from [`prelude`], which holds what embedding it usually takes:
+//!
+//! ```
+//! use cloudmaid::prelude::*;
+//!
+//! let template: Template = serde_json::from_str(r#"{
+//!   "Resources": {
+//!     "Jobs": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+//!     "Worker": {
+//!       "Type": "AWS::Lambda::Function",
+//!       "Properties": {
+//!         "FunctionName": "worker",
+//!         "Architectures": ["arm64"],
+//!         "Environment": { "Variables": { "QUEUE": { "Ref": "Jobs" } } }
+//!       }
+//!     }
+//!   }
+//! }"#)?;
+//!
+//! let diagram = Cloudmaid::builder()
+//!   .stack(Stack { name: "app".to_string(), template })
+//!   .only([ResourceType::Lambda, ResourceType::Sqs])
+//!   .build()
+//!   .run()?;
+//!
+//! assert_eq!(diagram.graph.nodes().len(), 2);
+//! assert!(diagram.rendered.starts_with("```mermaid"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
 pub mod cloudformation;
 pub mod ast;
-pub mod cli;
\ No newline at end of file
+pub mod cli;
+pub mod error;
+pub(crate) mod terraform;
+pub mod render;
+pub mod builder;
+pub mod prelude;
+
+pub use builder::{Builder, Cloudmaid, Diagram};
@@ -1,34 +1,615 @@
-use serde_json::from_str;
 use std::fs;
-use clap::Parser;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use clap::{CommandFactory, FromArgMatches};
+use rayon::prelude::*;
+use tracing::{debug, error, info, warn};
 
-use cloudmaid::ast::ast::AST;
-use cloudmaid::cloudformation::template::Template;
-use cloudmaid::cli::parse::Args;
+use cloudmaid::ast::AST;
+use cloudmaid::ast::analysis::{self, Analysis, Listing, Stats};
+use cloudmaid::ast::cache::ReferenceCache;
+use cloudmaid::ast::diff::Diff;
+use cloudmaid::ast::node::Node;
+use cloudmaid::ast::split;
+use cloudmaid::cloudformation::stack::Stack;
+use cloudmaid::cli::{config, inject, inputs, watch};
+use cloudmaid::cli::parse::{Args, Command};
+use cloudmaid::Cloudmaid;
+use cloudmaid::error::{CloudmaidError, Result};
+use cloudmaid::render::{Format, RenderOptions, docs, image, mermaid_block, share, summary, validate};
+
+const STDOUT: &str = "-";
 
 fn main() {
-  let args = Args::parse();
-
-  match fs::read_to_string(args.input_file) {
-    Ok(contents) => {
-      let cloudformation_template: Template = from_str(&contents.to_string()).unwrap();
-      let ast = AST::from(cloudformation_template);
-      let mermaid = ast.to_mermaid();
-
-      if fs::metadata(&args.output_file).is_ok() {
-        match fs::remove_file(&args.output_file) {
-          Ok(_) => println!("Deleted existing {}", &args.output_file),
-          Err(e) => println!("Error deleting file: {}", e),
+  let args = parse_args();
+  tracing_subscriber::fmt()
+    .with_max_level(args.log_level())
+    .with_writer(std::io::stderr)
+    .with_ansi(std::io::stderr().is_terminal())
+    .with_target(false)
+    .without_time()
+    .init();
+
+  let result = match &args.command {
+    Some(command) => run(command, &args),
+    None => render_inputs(&args),
+  };
+
+  if let Err(e) = result {
+    eprintln!("error: {}", e);
+    std::process::exit(e.exit_code());
+  }
+}
+
+fn render_inputs(args: &Args) -> Result<()> {
+  // Other parts' resources are told apart as ghosts, which only flowcharts draw.
+  if args.render.split_by.is_some() && !matches!(args.format, Format::Mermaid | Format::Svg | Format::Png) {
+    return Err(CloudmaidError::Usage("--split-by needs a flowchart: mermaid, svg or png".to_string()));
+  }
+
+  let paths = resolve_inputs(&[], args)?;
+
+  if args.render.share {
+    return share(&paths, args);
+  }
+
+  if !args.render.watch {
+    return generate(&paths, args);
+  }
+
+  // A broken template mid-edit should not end the session.
+  if let Err(e) = generate(&paths, args) {
+    error!("{}", e);
+  }
+  info!("Watching for changes, press Ctrl-C to stop");
+  watch::watch(&paths, || {
+    if let Err(e) = generate(&paths, args) {
+      error!("{}", e);
+    }
+  })
+}
+
+fn parse_args() -> Args {
+  let matches = Args::command().get_matches();
+  let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+  if let Some(Command::Render(render)) = &args.command {
+    args.render = render.clone();
+  }
+
+  let path = match (&args.config, args.no_config) {
+    (Some(path), _) => Some(PathBuf::from(path)),
+    (None, false) => std::env::current_dir().ok().and_then(|dir| config::discover(&dir)),
+    (None, true) => None,
+  };
+
+  if let Some(path) = path
+    && let Err(e) = config::load(&path).and_then(|config| config.apply(&mut args, &matches))
+  {
+    eprintln!("error: {}", e);
+    std::process::exit(e.exit_code());
+  }
+
+  // Watch mode renders the same inputs on every save, so unchanged stacks and
+  // resources are kept from one render to the next.
+  args.cache = args.render.watch.then(ReferenceCache::default);
+  args
+}
+
+fn generate(paths: &[PathBuf], args: &Args) -> Result<()> {
+  if let Some(document) = &args.render.inject {
+    return inject(paths, args, document);
+  }
+
+  let Some(output_dir) = &args.render.output_dir else {
+    return render(paths, args, args.render.output_file.as_deref().unwrap_or(STDOUT));
+  };
+
+  let names = inputs::output_names(&args.output_name(), paths)?;
+  fs::create_dir_all(output_dir).map_err(|e| CloudmaidError::io(output_dir, e))?;
+
+  // One bad template should not stop the rest of the batch; the first
+  // failure still decides the exit code.
+  let pool = rayon::ThreadPoolBuilder::new()
+    .num_threads(args.render.jobs.unwrap_or_default())
+    .build()
+    .map_err(|e| CloudmaidError::Render(e.to_string()))?;
+  let results: Vec<Result<()>> = pool.install(|| {
+    paths
+      .par_iter()
+      .zip(&names)
+      .map(|(path, name)| {
+        let written = load_stacks(path, args).and_then(|stacks| {
+          let output = Path::new(output_dir).join(name);
+          write_diagram(&stacks, args, &output.display().to_string())
+        });
+        if let Err(e) = &written {
+          error!("{}", e);
         }
+        written
+      })
+      .collect()
+  });
+
+  let failed: Vec<String> = paths
+    .iter()
+    .zip(&results)
+    .filter(|(_, result)| result.is_err())
+    .map(|(path, _)| path.display().to_string())
+    .collect();
+  match failed.is_empty() {
+    true => info!("Rendered {} template(s) to {}", paths.len(), output_dir),
+    false => warn!(
+      "Rendered {} of {} template(s) to {}; failed: {}",
+      paths.len() - failed.len(),
+      paths.len(),
+      output_dir,
+      failed.join(", ")
+    ),
+  }
+  let failure = results.into_iter().find_map(Result::err);
+
+  if let Some(index) = &args.render.index {
+    render(paths, args, index)?;
+  }
+
+  failure.map_or(Ok(()), Err)
+}
+
+fn inject(paths: &[PathBuf], args: &Args, document: &str) -> Result<()> {
+  if args.format.is_image() {
+    return Err(CloudmaidError::Usage("--inject needs a text format, not svg or png".to_string()));
+  }
+
+  let stacks = load(paths, args)?;
+  let ast = build(&stacks, args);
+  for cycle in analysis::cycles(&ast) {
+    warn!("cycle detected: {}", analysis::describe(&cycle));
+  }
+
+  let diagram = args.format.renderer(args.render_options(document, title(&stacks))).render(&ast);
+  validate::flowchart(&diagram)?;
+  match inject::write(Path::new(document), &diagram)? {
+    true => info!("Diagram injected into {}", document),
+    false => info!("{} is up to date", document),
+  }
+  Ok(())
+}
+
+// The links carry the whole diagram, so nothing is written locally.
+fn share(paths: &[PathBuf], args: &Args) -> Result<()> {
+  if !args.format.is_mermaid() {
+    return Err(CloudmaidError::Usage("--share needs a mermaid format, not plantuml, json or graphml".to_string()));
+  }
+
+  let stacks = load(paths, args)?;
+  let mut options = args.render_options(STDOUT, title(&stacks));
+  options.raw = true;
+  let diagram = args.format.renderer(options).render(&build(&stacks, args));
+  validate::flowchart(&diagram)?;
+
+  println!("{}", share::edit_url(&diagram));
+  println!("{}", share::image_url(&diagram));
+  Ok(())
+}
+
+fn run(command: &Command, args: &Args) -> Result<()> {
+  if args.render.split_by.is_some() && !matches!(command, Command::Render(_)) {
+    return Err(CloudmaidError::Usage("--split-by only applies to `render`".to_string()));
+  }
+
+  match command {
+    Command::Render(_) => render_inputs(args),
+    Command::Completions { shell } => {
+      clap_complete::generate(*shell, &mut Args::command(), "cloudmaid", &mut std::io::stdout());
+      Ok(())
+    }
+    Command::List {
+      inputs,
+      json,
+      output_file,
+    } => {
+      let stacks = load(&resolve_inputs(inputs, args)?, args)?;
+
+      let listing = Listing::new(&stacks, |node| args.keeps(node));
+      match json {
+        true => write_output(output_file, &listing.to_json()),
+        false => write_output(output_file, listing.to_text().trim_end()),
+      }
+    }
+    Command::Validate { inputs } => {
+      let mut failure = None;
+      for path in &resolve_inputs(inputs, args)? {
+        match load_stacks(path, args) {
+          Ok(stacks) => {
+            let resources: usize = stacks.iter().map(|stack| stack.template.resources.len()).sum();
+            println!("{}: ok ({} resources)", path.display(), resources);
+            let ast = build(&stacks, args);
+            for cycle in analysis::cycles(&ast) {
+              println!("{}: warning: cycle detected: {}", path.display(), analysis::describe(&cycle));
+            }
+            let diagram = args.format.renderer(args.render_options(STDOUT, title(&stacks))).render(&ast);
+            if let Err(e) = validate::flowchart(&diagram) {
+              println!("{}: error: {}", path.display(), e);
+              failure.get_or_insert(e.exit_code());
+            }
+          }
+          Err(e) => {
+            // Read errors already name the file.
+            println!("error: {}", e);
+            failure.get_or_insert(e.exit_code());
+          }
+        }
+      }
+
+      // Failures are already reported inline with the passing files.
+      if let Some(code) = failure {
+        std::process::exit(code);
+      }
+      Ok(())
+    }
+    Command::Diff { old, new, output_file } => {
+      let (old, new) = (load_ast(old, args)?, load_ast(new, args)?);
+
+      let diff = Diff::new(&old, &new);
+      let diagram = mermaid_block(&diff.to_flowchart(), &args.render_options(output_file, None));
+      validate::flowchart(&diagram)?;
+      write_output(output_file, &diagram)
+    }
+    Command::Focus {
+      inputs,
+      resource,
+      depth,
+      output_file,
+    } => {
+      let mut ast = expand_and_load(inputs, args)?;
+      let seed = find(&ast, resource)?;
+
+      ast.focus(&seed, *depth);
+      write_ast(&ast, None, args, output_file)
+    }
+    Command::Path {
+      inputs,
+      from,
+      to,
+      limit,
+      output_file,
+      diagram,
+    } => {
+      let ast = expand_and_load(inputs, args)?;
+      let (source, target) = (find(&ast, from)?, find(&ast, to)?);
+
+      let paths = ast.paths(&source, &target, *limit);
+      if paths.len() == *limit {
+        info!("Listing the {} shortest paths; --limit shows more", limit);
+      }
+      let lines: Vec<String> = paths.iter().map(|path| analysis::describe_path(path)).collect();
+      match lines.is_empty() {
+        true => write_output(output_file, &format!("No path from {} to {}", from, to))?,
+        false => write_output(output_file, &lines.join("\n"))?,
+      }
+
+      if let Some(diagram) = diagram {
+        let mut options = args.render_options(diagram, None);
+        options.highlight = paths.into_iter().flatten().map(|node| node.name.0.to_string()).collect();
+        write_rendered(&ast, options, args, diagram)?;
+      }
+      Ok(())
+    }
+    Command::BlastRadius {
+      inputs,
+      resource,
+      direction,
+      output_file,
+    } => {
+      let mut ast = expand_and_load(inputs, args)?;
+      let seed = find(&ast, resource)?;
+
+      let affected = ast.blast_radius(&seed, *direction);
+      ast.retain(|node| affected.contains(node));
+
+      let mut options = args.render_options(output_file, None);
+      options.highlight = vec![seed.name.0.to_string()];
+      write_rendered(&ast, options, args, output_file)
+    }
+    Command::Stats {
+      inputs,
+      json,
+      output_file,
+    } => {
+      let stacks = load(&resolve_inputs(inputs, args)?, args)?;
+
+      let stats = Stats::new(&build(&stacks, args), &stacks);
+      match json {
+        true => write_output(output_file, &stats.to_json()),
+        false => write_output(output_file, stats.to_text().trim_end()),
+      }
+    }
+    Command::Tui { inputs, output_file } => {
+      let ast = expand_and_load(inputs, args)?;
+      let output_file = match output_file {
+        Some(output_file) => output_file.clone(),
+        None => format!("explorer.{}", args.format.extension()),
+      };
+
+      explore(ast, args, &output_file)
+    }
+    Command::Docs { inputs, output_dir } => {
+      let stacks = load(&resolve_inputs(inputs, args)?, args)?;
+      let ast = build(&stacks, args);
+
+      fs::create_dir_all(output_dir).map_err(|e| CloudmaidError::io(output_dir, e))?;
+      let pages = docs::pages(&ast, &args.render_options(output_dir, title(&stacks)));
+      for page in &pages {
+        let path = Path::new(output_dir).join(&page.file_name);
+        fs::write(&path, &page.content).map_err(|e| CloudmaidError::io(&path, e))?;
       }
+      info!("Wrote {} page(s) to {}", pages.len(), output_dir);
+      Ok(())
+    }
+    Command::Summary {
+      inputs,
+      base,
+      output_file,
+      append,
+    } => {
+      let stacks = load(&resolve_inputs(inputs, args)?, args)?;
+      let ast = build(&stacks, args);
 
-      match fs::write(&args.output_file, mermaid) {
-        Ok(_) => println!("Mermaid written to {}", &args.output_file),
-        Err(e) => println!("Error writing to file: {}", e),
+      let diff = match base {
+        Some(base) => Some(Diff::new(&load_ast(base, args)?, &ast)),
+        None => None,
+      };
+      let listing = Listing::new(&stacks, |node| args.keeps(node));
+      let document = summary::summary(&ast, &listing, diff.as_ref(), &args.render_options(output_file, title(&stacks)));
+      match append {
+        true => append_output(output_file, &document),
+        false => write_output(output_file, document.trim_end()),
       }
     }
-    Err(e) => {
-      println!("Error reading file: {}", e);
+    Command::Analyze { inputs, output_file } => {
+      let stacks = load(&resolve_inputs(inputs, args)?, args)?;
+
+      let mut analysis = Analysis::new(&build(&stacks, args), &stacks);
+      analysis.orphans.retain(|orphan| args.keeps(&orphan.node));
+      write_output(output_file, analysis.to_text().trim_end())
+    }
+  }
+}
+
+// Every command takes its inputs the same way: from the command line, or
+// failing that from `cloudmaid.toml`. Live stacks from `--stack-name` are
+// enough on their own.
+fn resolve_inputs(inputs: &[String], args: &Args) -> Result<Vec<PathBuf>> {
+  let inputs = match inputs.is_empty() {
+    true => args.inputs(),
+    false => inputs.to_vec(),
+  };
+  if inputs.is_empty() && args.render.stack_name.is_empty() {
+    return Err(CloudmaidError::Usage("no input given: pass a template or set `input` in cloudmaid.toml".to_string()));
+  }
+  inputs::expand(&inputs)
+}
+
+fn find(ast: &AST, resource: &str) -> Result<Node> {
+  ast.find(resource).cloned().ok_or_else(|| CloudmaidError::UnknownResource(resource.to_string()))
+}
+
+fn expand_and_load(inputs: &[String], args: &Args) -> Result<AST> {
+  let stacks = load(&resolve_inputs(inputs, args)?, args)?;
+  Ok(build(&stacks, args))
+}
+
+fn load_ast(path: &str, args: &Args) -> Result<AST> {
+  let stacks = load_stacks(Path::new(path), args)?;
+  Ok(build(&stacks, args))
+}
+
+fn render(paths: &[PathBuf], args: &Args, output_file: &str) -> Result<()> {
+  write_diagram(&load(paths, args)?, args, output_file)
+}
+
+fn write_diagram(stacks: &[Stack], args: &Args, output_file: &str) -> Result<()> {
+  let ast = build(stacks, args);
+  let Some(by) = &args.render.split_by else {
+    return write_ast(&ast, title(stacks), args, output_file);
+  };
+
+  for cycle in analysis::cycles(&ast) {
+    warn!("cycle detected: {}", analysis::describe(&cycle));
+  }
+  for part in split::split(&ast, stacks, by) {
+    let output_file = part_file(output_file, &part.name);
+    let mut options = args.render_options(&output_file, title(stacks));
+    if !args.no_title {
+      options.title = Some(match options.title {
+        Some(title) => format!("{} ({})", title, part.name),
+        None => part.name.clone(),
+      });
     }
+    options.ghosts = part.ghosts;
+    write_rendered(&part.ast, options, args, &output_file)?;
   }
+  Ok(())
+}
+
+// `diagram.md` split by team is `diagram-orders.md`, `diagram-ingest.md` and so
+// on; parts written to stdout follow one another.
+fn part_file(output_file: &str, part: &str) -> String {
+  if output_file == STDOUT {
+    return STDOUT.to_string();
+  }
+
+  let part: String = part.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' }).collect();
+  let path = Path::new(output_file);
+  let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+  let name = match path.extension() {
+    Some(extension) => format!("{}-{}.{}", stem, part, extension.to_string_lossy()),
+    None => format!("{}-{}", stem, part),
+  };
+  path.with_file_name(name).display().to_string()
+}
+
+fn title(stacks: &[Stack]) -> Option<&str> {
+  match stacks {
+    [stack] => Some(stack.name.as_str()),
+    _ => None,
+  }
+}
+
+fn write_ast(ast: &AST, title: Option<&str>, args: &Args, output_file: &str) -> Result<()> {
+  for cycle in analysis::cycles(ast) {
+    warn!("cycle detected: {}", analysis::describe(&cycle));
+  }
+
+  write_rendered(ast, args.render_options(output_file, title), args, output_file)
+}
+
+fn write_rendered(ast: &AST, options: RenderOptions, args: &Args, output_file: &str) -> Result<()> {
+  let diagram = args.format.renderer(options).render(ast);
+  validate::flowchart(&diagram)?;
+
+  if !args.format.is_image() {
+    return write_output(output_file, &diagram);
+  }
+
+  image::write(&diagram, Path::new(output_file), args.format.extension())?;
+  info!("Diagram written to {}", output_file);
+  Ok(())
+}
+
+fn write_output(output_file: &str, diagram: &str) -> Result<()> {
+  if output_file == STDOUT {
+    println!("{}", diagram);
+    return Ok(());
+  }
+
+  if fs::metadata(output_file).is_ok() {
+    fs::remove_file(output_file).map_err(|e| CloudmaidError::io(output_file, e))?;
+    info!("Deleted existing {}", output_file);
+  }
+
+  fs::write(output_file, diagram).map_err(|e| CloudmaidError::io(output_file, e))?;
+  info!("Diagram written to {}", output_file);
+  Ok(())
+}
+
+fn append_output(output_file: &str, document: &str) -> Result<()> {
+  if output_file == STDOUT {
+    println!("{}", document);
+    return Ok(());
+  }
+
+  let mut file = fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(output_file)
+    .map_err(|e| CloudmaidError::io(output_file, e))?;
+  writeln!(file, "{}", document).map_err(|e| CloudmaidError::io(output_file, e))?;
+  info!("Summary appended to {}", output_file);
+  Ok(())
+}
+
+fn load(paths: &[PathBuf], args: &Args) -> Result<Vec<Stack>> {
+  let mut stacks = Vec::new();
+
+  for path in paths {
+    let loaded = load_stacks(path, args)?;
+    debug!("{}: loaded {} stack(s)", path.display(), loaded.len());
+    stacks.extend(loaded);
+  }
+
+  for stack_name in &args.render.stack_name {
+    let mut fetched = [fetch_stack(stack_name, args)?];
+    resolve_intrinsics(&mut fetched, args);
+    stacks.extend(fetched);
+  }
+
+  Ok(stacks)
+}
+
+fn resolve_intrinsics(stacks: &mut [Stack], args: &Args) {
+  cloudmaid(args).resolve(stacks);
+}
+
+fn build(stacks: &[Stack], args: &Args) -> AST {
+  cloudmaid(args).graph(stacks)
+}
+
+// The binary is a thin layer over the library: flags map one to one onto the
+// builder, and reading live stacks and writing files stay here.
+fn cloudmaid(args: &Args) -> Cloudmaid {
+  let mut builder = Cloudmaid::builder()
+    .format(args.format)
+    .keep(args.keep_policy())
+    .nested_stacks(args.nested_stacks)
+    .show_depends_on(args.show_depends_on)
+    .show_missing(args.show_missing)
+    .show_parameters(args.show_parameters)
+    .show_permissions(args.show_permissions)
+    .show_aliases(args.show_aliases)
+    .collapse_apis(args.collapse_apis)
+    .layers(args.layers);
+
+  for (key, value) in &args.parameter {
+    builder = builder.parameter(key, value);
+  }
+  for (url, path) in &args.template_map {
+    builder = builder.template_map(url, path);
+  }
+  for (raw_type, typ) in &args.type_mappings {
+    builder = builder.type_mapping(raw_type, typ.clone());
+  }
+  if let Some(region) = &args.region {
+    builder = builder.region(region);
+  }
+  if let Some(account_id) = &args.account_id {
+    builder = builder.account_id(account_id);
+  }
+  if let Some(direction) = args.edge_direction {
+    builder = builder.edge_direction(direction);
+  }
+  if let Some(cache) = &args.cache {
+    builder = builder.cache(cache.clone());
+  }
+  builder.build()
+}
+
+// Anything logged while the explorer owns the terminal would garble it, so the
+// export is written without the usual progress messages.
+#[cfg(feature = "tui")]
+fn explore(ast: AST, args: &Args, output_file: &str) -> Result<()> {
+  cloudmaid::cli::tui::run(ast, |subgraph| {
+    let diagram = args.format.renderer(args.render_options(output_file, None)).render(subgraph);
+    validate::flowchart(&diagram)?;
+    match args.format.is_image() {
+      true => image::write(&diagram, Path::new(output_file), args.format.extension()),
+      false => fs::write(output_file, diagram).map_err(|e| CloudmaidError::io(output_file, e)),
+    }
+  })
+}
+
+#[cfg(not(feature = "tui"))]
+fn explore(_ast: AST, _args: &Args, _output_file: &str) -> Result<()> {
+  Err(CloudmaidError::Usage("cloudmaid was built without the `tui` feature; build with `--features tui`".to_string()))
+}
+
+#[cfg(feature = "aws")]
+fn fetch_stack(stack_name: &str, args: &Args) -> Result<Stack> {
+  cloudmaid::cloudformation::live::fetch(stack_name, args.profile.as_deref(), args.region.as_deref(), &args.type_mappings)
+}
+
+#[cfg(not(feature = "aws"))]
+fn fetch_stack(stack_name: &str, _args: &Args) -> Result<Stack> {
+  Err(CloudmaidError::Usage(format!(
+    "cannot fetch {}: cloudmaid was built without the `aws` feature",
+    stack_name
+  )))
+}
+
+// Stacks read from a template or assembly, with their intrinsics resolved
+// however they end up rendered.
+fn load_stacks(path: &Path, args: &Args) -> Result<Vec<Stack>> {
+  let mut stacks = cloudmaid(args).read(path)?;
+  resolve_intrinsics(&mut stacks, args);
+  Ok(stacks)
 }
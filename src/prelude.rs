@@ -0,0 +1,13 @@
+//! What embedding the converter usually takes, importable in one line.
+pub use crate::ast::AST;
+pub use crate::ast::edge::{Edge, Relationship};
+pub use crate::ast::graph::Graph;
+pub use crate::ast::node::Node;
+pub use crate::builder::{Builder, Cloudmaid, Diagram};
+pub use crate::cloudformation::resource::{Resource, ResourceType};
+pub use crate::cloudformation::stack::Stack;
+pub use crate::cloudformation::template::Template;
+pub use crate::error::{CloudmaidError, Result};
+pub use crate::render::mermaid::MermaidRenderer;
+pub use crate::render::plantuml::PlantUmlRenderer;
+pub use crate::render::{Format, KeepPolicy, RenderOptions, Renderer};
@@ -0,0 +1,249 @@
+pub mod mermaid;
+pub mod plantuml;
+pub mod image;
+pub mod json;
+pub mod graphml;
+pub mod architecture;
+pub mod icons;
+pub mod sequence;
+pub mod c4;
+pub mod state_diagram;
+pub mod er;
+pub mod mindmap;
+pub mod docs;
+pub mod summary;
+pub mod share;
+pub mod validate;
+
+use clap::ValueEnum;
+use std::sync::Arc;
+
+use crate::ast::ast::AST;
+use crate::ast::node::{DEFAULT_LABEL, Node};
+use crate::cloudformation::resource::ResourceType;
+use crate::render::architecture::ArchitectureRenderer;
+use crate::render::c4::C4Renderer;
+use crate::render::er::ErRenderer;
+use crate::render::graphml::GraphMlRenderer;
+use crate::render::json::JsonRenderer;
+use crate::render::mermaid::MermaidRenderer;
+use crate::render::mindmap::MindmapRenderer;
+use crate::render::plantuml::PlantUmlRenderer;
+use crate::render::sequence::SequenceRenderer;
+use crate::render::state_diagram::StateDiagramRenderer;
+
+pub trait Renderer {
+  fn render(&self, ast: &AST) -> String;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+  Mermaid,
+  #[value(name = "plantuml")]
+  PlantUml,
+  Svg,
+  Png,
+  Json,
+  #[value(name = "graphml")]
+  GraphMl,
+  Architecture,
+  Sequence,
+  C4,
+  #[value(name = "statediagram")]
+  StateDiagram,
+  #[value(name = "erdiagram")]
+  ErDiagram,
+  Mindmap,
+}
+
+// Which icons flowchart nodes get: iconify's `logos` pack through mermaid's
+// icon shape, or FontAwesome classes in front of the label.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Icons {
+  Logos,
+  FontAwesome,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderOptions {
+  pub raw: bool,
+  pub title: Option<String>,
+  pub header: Option<String>,
+  pub label_format: Option<String>,
+  pub highlight: Vec<String>,
+  // Resources drawn only because they connect to the part of a split graph
+  // being shown; they belong to another part's diagram.
+  pub ghosts: Vec<Node>,
+  pub keep: KeepPolicy,
+  // Where a sequence diagram starts: a resource, or an API route such as
+  // `GET /orders` once APIs are collapsed.
+  pub entry: Option<String>,
+  // The one resource a diagram of its insides is drawn for, such as the state
+  // machine behind `statediagram`.
+  pub resource: Option<String>,
+  pub icons: Option<Icons>,
+  // Icons chosen in the config file for particular types, in place of the
+  // set's own.
+  pub type_icons: Vec<(ResourceType, String)>,
+}
+
+pub type Predicate = Arc<dyn Fn(&Node) -> bool + Send + Sync>;
+
+// Which nodes are drawn: `allow` (every type when empty), minus `deny`, minus
+// whatever the predicate rejects. Edges go with either endpoint.
+#[derive(Clone, Default)]
+pub struct KeepPolicy {
+  pub allow: Vec<ResourceType>,
+  pub deny: Vec<ResourceType>,
+  pub predicate: Option<Predicate>,
+}
+
+impl KeepPolicy {
+  pub fn keeps(&self, node: &Node) -> bool {
+    (self.allow.is_empty() || self.allow.contains(&node.typ))
+      && !self.deny.contains(&node.typ)
+      && self.predicate.as_ref().is_none_or(|predicate| predicate(node))
+  }
+
+  // Event source mappings are drawn as the edge from their source to the
+  // function rather than as a node, so `allow`, which picks the nodes to draw,
+  // does not leave them out; denying the type or rejecting one by name does.
+  pub fn keeps_resource(&self, node: &Node) -> bool {
+    match node.typ {
+      ResourceType::EventSourceMapping => {
+        !self.deny.contains(&node.typ) && self.predicate.as_ref().is_none_or(|predicate| predicate(node))
+      }
+      _ => self.keeps(node),
+    }
+  }
+}
+
+impl std::fmt::Debug for KeepPolicy {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("KeepPolicy")
+      .field("allow", &self.allow)
+      .field("deny", &self.deny)
+      .field("predicate", &self.predicate.as_ref().map(|_| ".."))
+      .finish()
+  }
+}
+
+// Closures cannot be compared, so two policies are only equal when they share
+// the same predicate.
+impl PartialEq for KeepPolicy {
+  fn eq(&self, other: &Self) -> bool {
+    let predicates = match (&self.predicate, &other.predicate) {
+      (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+      (a, b) => a.is_none() && b.is_none(),
+    };
+    self.allow == other.allow && self.deny == other.deny && predicates
+  }
+}
+
+impl RenderOptions {
+  pub fn label_format(&self) -> &str {
+    self.label_format.as_deref().unwrap_or(DEFAULT_LABEL)
+  }
+}
+
+impl Format {
+  pub fn renderer(&self, options: RenderOptions) -> Box<dyn Renderer> {
+    match self {
+      Format::Mermaid => Box::new(MermaidRenderer { options }),
+      Format::PlantUml => Box::new(PlantUmlRenderer { options }),
+      Format::Json => Box::new(JsonRenderer),
+      Format::GraphMl => Box::new(GraphMlRenderer),
+      Format::Architecture => Box::new(ArchitectureRenderer { options }),
+      Format::Sequence => Box::new(SequenceRenderer { options }),
+      Format::C4 => Box::new(C4Renderer { options }),
+      Format::StateDiagram => Box::new(StateDiagramRenderer { options }),
+      Format::ErDiagram => Box::new(ErRenderer { options }),
+      Format::Mindmap => Box::new(MindmapRenderer { options }),
+      Format::Svg | Format::Png => {
+        let mut options = options;
+        options.raw = true;
+        Box::new(MermaidRenderer { options })
+      }
+    }
+  }
+
+  pub fn extension(&self) -> &'static str {
+    match self {
+      Format::Mermaid | Format::Architecture | Format::Sequence | Format::C4 | Format::StateDiagram | Format::ErDiagram | Format::Mindmap => "md",
+      Format::PlantUml => "puml",
+      Format::Svg => "svg",
+      Format::Png => "png",
+      Format::Json => "json",
+      Format::GraphMl => "graphml",
+    }
+  }
+
+  // Formats whose output is mermaid source, images included since they are
+  // rendered from it.
+  pub fn is_mermaid(&self) -> bool {
+    !matches!(self, Format::PlantUml | Format::Json | Format::GraphMl)
+  }
+
+  pub fn is_image(&self) -> bool {
+    matches!(self, Format::Svg | Format::Png)
+  }
+}
+
+pub fn mermaid_block(body: &str, options: &RenderOptions) -> String {
+  let body = format!("{}{}", preamble(options), body);
+
+  match options.raw {
+    true => body,
+    false => format!("```mermaid\n{}```", body),
+  }
+}
+
+// Mermaid only honours front-matter on the very first line, so the header
+// comment goes after it.
+fn preamble(options: &RenderOptions) -> String {
+  let mut result = String::new();
+
+  if let Some(title) = &options.title {
+    result.push_str(&format!("---\ntitle: {}\n---\n", title));
+  }
+
+  if let Some(header) = &options.header {
+    result.push_str(&format!("%% {}\n", header));
+  }
+
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::Name;
+
+  use super::*;
+
+  #[test]
+  fn test_keep_policy() {
+    let node = |name: &str, typ: ResourceType| Node {
+      name: Name::from(name),
+      typ,
+      properties: Property::Other(serde_json::Value::Null).into(),
+    };
+    let (api, worker, canary) = (node("Api", ResourceType::ApiGateway), node("Worker", ResourceType::Lambda), node("Canary", ResourceType::Lambda));
+    let mapping = node("Mapping", ResourceType::EventSourceMapping);
+
+    let predicate: Predicate = Arc::new(|node: &Node| node.name.0.as_ref() != "Canary");
+    let keep = KeepPolicy {
+      deny: vec![ResourceType::Sqs],
+      predicate: Some(predicate.clone()),
+      ..Default::default()
+    };
+    assert!(keep.keeps(&worker) && !keep.keeps(&canary));
+    assert!(KeepPolicy { allow: vec![ResourceType::Lambda], ..Default::default() }.keeps(&canary));
+    assert!(!KeepPolicy { allow: vec![ResourceType::Lambda], ..Default::default() }.keeps(&api));
+    assert_eq!(keep.clone(), keep);
+    assert_ne!(KeepPolicy { predicate: Some(Arc::new(|_: &Node| true)), ..Default::default() }, keep);
+
+    assert!(KeepPolicy { allow: vec![ResourceType::Lambda], ..Default::default() }.keeps_resource(&mapping));
+    assert!(!KeepPolicy { deny: vec![ResourceType::EventSourceMapping], ..Default::default() }.keeps_resource(&mapping));
+  }
+}
@@ -0,0 +1,119 @@
+use crate::ast::ast::AST;
+use crate::ast::node::{Node, sanitize_id};
+use crate::cloudformation::resource::ResourceType;
+use crate::render::icons::logo;
+use crate::render::{RenderOptions, Renderer, mermaid_block};
+
+// Icons come from the iconify `logos` pack, which Mermaid Live and most docs
+// sites register; mermaid falls back to a blank icon when it is missing.
+pub struct ArchitectureRenderer {
+  pub options: RenderOptions,
+}
+
+impl Renderer for ArchitectureRenderer {
+  fn render(&self, ast: &AST) -> String {
+    let mut result = String::from("architecture-beta\n");
+
+    for subgraph in &ast.subgraphs {
+      result.push_str(&format!(
+        "group {}(logos:aws-cloudformation)[{}]\n",
+        id(&subgraph.name),
+        label(&subgraph.name)
+      ));
+    }
+
+    for node in ast.nodes() {
+      let group = match ast.group_of(node) {
+        Some(group) => format!(" in {}", id(group)),
+        None => String::new(),
+      };
+      result.push_str(&format!(
+        "service {}({})[{}]{}\n",
+        id(&node.name.0),
+        icon(node),
+        label(&node.label(self.options.label_format()).replace('\n', " ")),
+        group
+      ));
+    }
+
+    for edge in ast.edges.iter().chain(ast.cross_stack_edges.iter()) {
+      result.push_str(&format!("{}:R --> L:{}\n", id(&edge.from.name.0), id(&edge.to.name.0)));
+    }
+
+    mermaid_block(&result, &self.options)
+  }
+}
+
+// Built-in architecture icons stand in where the `logos` pack has none.
+fn icon(node: &Node) -> &'static str {
+  logo(&node.typ).unwrap_or(match node.typ {
+    ResourceType::SesReceiptRule | ResourceType::SesIdentity | ResourceType::External => "internet",
+    ResourceType::SsmParameter | ResourceType::Secret | ResourceType::FileSystem | ResourceType::AccessPoint => "disk",
+    _ => "server",
+  })
+}
+
+fn id(name: &str) -> String {
+  sanitize_id(name)
+}
+
+fn label(name: &str) -> String {
+  name.replace(['[', ']'], "")
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ast::edge::Edge;
+  use serde_json::json;
+
+  use crate::ast::subgraph::Subgraph;
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::Name;
+
+  use super::*;
+
+  #[test]
+  fn test_render_architecture() {
+    let api_node = Node {
+      name: Name::from("MyApi"),
+      typ: ResourceType::ApiGateway,
+      properties: Property::ApiGateway {
+        http_method: "GET".to_string(),
+        integration: json!({}),
+        rest_api_id: serde_json::Value::Null,
+        resource_id: serde_json::Value::Null,
+        resource_path: None,
+      }.into(),
+    };
+    let lambda_node = Node {
+      name: Name::from("MyLambda"),
+      typ: ResourceType::Lambda,
+      properties: Property::lambda("my-lambda").into(),
+    };
+    let ast = AST {
+      edges: vec![Edge::new(api_node.clone(), lambda_node.clone())],
+      subgraphs: vec![Subgraph {
+        name: "my-stack".to_string(),
+        nodes: vec![api_node, lambda_node],
+        ..Default::default()
+      }],
+      ..Default::default()
+    };
+
+    let renderer = ArchitectureRenderer {
+      options: RenderOptions {
+        raw: true,
+        ..Default::default()
+      },
+    };
+    let expected_output = concat!(
+      "architecture-beta\n",
+      "group my_stack(logos:aws-cloudformation)[my-stack]\n",
+      "service MyApi(logos:aws-api-gateway)[MyApi] in my_stack\n",
+      "service MyLambda(logos:aws-lambda)[my-lambda] in my_stack\n",
+      "MyApi:R --> L:MyLambda\n",
+    );
+
+    assert_eq!(renderer.render(&ast), expected_output);
+  }
+}
@@ -0,0 +1,146 @@
+use crate::ast::ast::AST;
+use crate::ast::ids::Ids;
+use crate::ast::node::{Node, Shape, sanitize_id};
+use crate::ast::subgraph::Subgraph;
+use crate::render::{RenderOptions, Renderer, mermaid_block};
+
+// A mermaid `C4Container` diagram: each stack is a system boundary, construct
+// groups inside it are container boundaries, and every resource is a
+// container whose technology is its type. Stores and queues get C4's own
+// shapes, and resources outside the template are external systems.
+pub struct C4Renderer {
+  pub options: RenderOptions,
+}
+
+impl Renderer for C4Renderer {
+  fn render(&self, ast: &AST) -> String {
+    let ids = ast.ids();
+    let mut result = String::from("C4Container\n");
+    let mut declared: Vec<&Node> = Vec::new();
+
+    for subgraph in &ast.subgraphs {
+      boundary(subgraph, None, self.options.label_format(), &ids, &mut declared, &mut result);
+    }
+
+    for node in ast.nodes() {
+      if !declared.contains(&node) {
+        result.push_str(&format!("{}\n", container(node, &ids.of(node), self.options.label_format())));
+        declared.push(node);
+      }
+    }
+
+    for edge in ast.edges.iter().chain(ast.cross_stack_edges.iter()) {
+      let label = edge.label().unwrap_or_else(|| edge.relationship.to_string());
+      result.push_str(&format!("Rel({}, {}, \"{}\")\n", ids.of(&edge.from), ids.of(&edge.to), text(&label)));
+    }
+
+    mermaid_block(&result, &self.options)
+  }
+}
+
+// Construct names repeat across a CDK app, so boundaries are keyed by their
+// full path, as in the flowchart.
+fn boundary<'a>(
+  subgraph: &'a Subgraph,
+  parent: Option<&str>,
+  label_format: &str,
+  ids: &Ids,
+  declared: &mut Vec<&'a Node>,
+  result: &mut String,
+) {
+  let (id, kind) = match parent {
+    Some(parent) => (format!("{}_{}", parent, sanitize_id(&subgraph.name)), "Container_Boundary"),
+    None => (sanitize_id(&subgraph.name), "System_Boundary"),
+  };
+  result.push_str(&format!("{}({}, \"{}\") {{\n", kind, id, text(&subgraph.name)));
+
+  for child in &subgraph.subgraphs {
+    boundary(child, Some(&id), label_format, ids, declared, result);
+  }
+
+  for node in &subgraph.nodes {
+    if !declared.contains(&node) {
+      result.push_str(&format!("{}\n", container(node, &ids.of(node), label_format)));
+      declared.push(node);
+    }
+  }
+
+  result.push_str("}\n");
+}
+
+fn container(node: &Node, id: &str, label_format: &str) -> String {
+  let label = text(&node.label(label_format));
+  match node.shape() {
+    Some(Shape::Rectangle) => format!("System_Ext({}, \"{}\")", id, label),
+    shape => {
+      let element = match shape {
+        Some(Shape::Cylinder) => "ContainerDb",
+        Some(Shape::Circle) => "ContainerQueue",
+        _ => "Container",
+      };
+      format!("{}({}, \"{}\", \"{}\")", element, id, label, node.typ)
+    }
+  }
+}
+
+// C4 strings are double quoted with no way to escape a quote.
+fn text(text: &str) -> String {
+  text.replace('"', "'").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::construct;
+  use crate::cloudformation::stack::Stack;
+  use crate::cloudformation::template::Template;
+  use crate::render::Format;
+
+  use super::*;
+
+  #[test]
+  fn test_c4_containers_in_boundaries() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" }, "Metadata": { "aws:cdk:path": "app/Jobs/Queue" } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        },
+        "Worker": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": { "FunctionName": "worker", "Environment": { "Variables": { "TABLE": { "Ref": "Table" } } } },
+          "Metadata": { "aws:cdk:path": "app/Jobs/Worker" }
+        },
+        "Table": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "results" }, "Metadata": { "aws:cdk:path": "app/Table" } }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template: template.clone(),
+    }];
+    let mut ast = AST::from(template);
+    construct::group(&mut ast, &stacks);
+
+    let options = RenderOptions {
+      raw: true,
+      ..Default::default()
+    };
+    let expected_output = concat!(
+      "C4Container\n",
+      "System_Boundary(app, \"app\") {\n",
+      "Container_Boundary(app_Jobs, \"Jobs\") {\n",
+      "ContainerQueue(Queue, \"jobs\", \"sqs\")\n",
+      "Container(Worker, \"worker\", \"lambda\")\n",
+      "}\n",
+      "ContainerDb(Table, \"results\", \"dynamodb\")\n",
+      "}\n",
+      "Rel(Queue, Worker, \"triggers\")\n",
+      "Rel(Worker, Table, \"configures\")\n",
+    );
+
+    assert_eq!(Format::C4.renderer(options).render(&ast), expected_output);
+  }
+}
@@ -0,0 +1,203 @@
+use serde_json::Value;
+
+use crate::ast::ast::AST;
+use crate::ast::edge::Edge;
+use crate::ast::graph::Indexed;
+use crate::ast::ids::Ids;
+use crate::ast::node::Node;
+use crate::render::{Format, RenderOptions};
+
+/// One markdown file of the `docs` wiki: a page per resource, or the index
+/// linking to them.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Page {
+  pub file_name: String,
+  pub content: String,
+}
+
+// Property values longer than this are cut short; the template has the rest.
+const SUMMARY_LENGTH: usize = 80;
+
+/// A page for every resource drawn, with its type, a summary of its
+/// properties, the edges in and out and a flowchart of its neighbours, and an
+/// `index.md` listing them by type.
+pub fn pages(ast: &AST, options: &RenderOptions) -> Vec<Page> {
+  let mut nodes = ast.all_nodes();
+  nodes.sort_by(|a, b| (a.typ.to_string(), &a.name.0).cmp(&(b.typ.to_string(), &b.name.0)));
+
+  let ids = ast.ids();
+  let indexed = ast.indexed();
+  let mut pages = vec![index(ast, &nodes, &ids, options)];
+  pages.extend(nodes.iter().map(|node| Page {
+    file_name: file_name(&ids, node),
+    content: page(ast, &indexed, node, &ids, options),
+  }));
+  pages
+}
+
+fn index(ast: &AST, nodes: &[&Node], ids: &Ids, options: &RenderOptions) -> Page {
+  let mut content = format!("# {}\n", options.title.as_deref().unwrap_or("Resources"));
+  let mut typ = None;
+
+  for node in nodes {
+    if typ != Some(&node.typ) {
+      content.push_str(&format!("\n## {}\n\n", node.typ));
+      typ = Some(&node.typ);
+    }
+    content.push_str(&format!("- [{}]({}){}\n", node.name.0, file_name(ids, node), physical_name(node)));
+  }
+
+  let edges = ast.edges.len() + ast.cross_stack_edges.len();
+  content.push_str(&format!("\n{}, {}.\n", count(nodes.len(), "resource"), count(edges, "edge")));
+
+  Page {
+    file_name: "index.md".to_string(),
+    content,
+  }
+}
+
+fn page(ast: &AST, indexed: &Indexed, node: &Node, ids: &Ids, options: &RenderOptions) -> String {
+  let mut content = format!("# {}\n\n[Index](index.md)\n\n", node.name.0);
+  content.push_str(&format!("- Type: `{}`\n", node.typ));
+  if node.get_name() != &*node.name.0 {
+    content.push_str(&format!("- Physical name: `{}`\n", node.get_name()));
+  }
+  if let Some(group) = ast.group_of(node) {
+    content.push_str(&format!("- Group: {}\n", group));
+  }
+
+  let properties = serde_json::to_value(&*node.properties).unwrap_or_default();
+  if let Some(properties) = properties.as_object().filter(|properties| !properties.is_empty()) {
+    content.push_str("\n## Properties\n\n");
+    for (key, value) in properties {
+      content.push_str(&format!("- `{}`: {}\n", key, summary(value)));
+    }
+  }
+
+  let edges: Vec<&Edge> = ast.edges.iter().chain(ast.cross_stack_edges.iter()).collect();
+  for (heading, incoming) in [("Inbound", true), ("Outbound", false)] {
+    let connected: Vec<&&Edge> = edges.iter().filter(|edge| match incoming {
+      true => edge.to == *node,
+      false => edge.from == *node,
+    }).collect();
+    if connected.is_empty() {
+      continue;
+    }
+
+    content.push_str(&format!("\n## {}\n\n", heading));
+    for edge in connected {
+      let other = if incoming { &edge.from } else { &edge.to };
+      let relationship = edge.label().unwrap_or_else(|| edge.relationship.to_string());
+      content.push_str(&format!("- {} [{}]({})\n", relationship, other.name.0, file_name(ids, other)));
+    }
+  }
+
+  let keep = indexed.neighbourhood(node, 1);
+  let mut neighbourhood = ast.clone();
+  neighbourhood.retain(|node| keep.contains(node));
+  if !neighbourhood.edges.is_empty() || !neighbourhood.cross_stack_edges.is_empty() {
+    let options = RenderOptions {
+      raw: false,
+      title: None,
+      header: None,
+      highlight: vec![node.name.0.to_string()],
+      ..options.clone()
+    };
+    content.push_str(&format!("\n## Diagram\n\n{}\n", Format::Mermaid.renderer(options).render(&neighbourhood)));
+  }
+
+  content
+}
+
+// Named after the node's ID in the diagrams, so resources sharing a logical ID
+// across stacks get a page each.
+fn file_name(ids: &Ids, node: &Node) -> String {
+  format!("{}.md", ids.of(node))
+}
+
+// `1 edge`, `2 edges`.
+fn count(count: usize, noun: &str) -> String {
+  match count {
+    1 => format!("1 {}", noun),
+    count => format!("{} {}s", count, noun),
+  }
+}
+
+fn physical_name(node: &Node) -> String {
+  match node.get_name() == &*node.name.0 {
+    true => String::new(),
+    false => format!(" (`{}`)", node.get_name()),
+  }
+}
+
+// Scalars as they are, and intrinsic functions and other structures as
+// compact JSON, cut to one line.
+fn summary(value: &Value) -> String {
+  let text = match value {
+    Value::String(text) => text.clone(),
+    value => value.to_string(),
+  };
+  let text = text.replace('\n', " ").replace('`', "'");
+
+  match text.chars().count() > SUMMARY_LENGTH {
+    true => format!("`{}…`", text.chars().take(SUMMARY_LENGTH).collect::<String>()),
+    false => format!("`{}`", text),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_pages_per_resource() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker", "Role": { "Fn::GetAtt": ["WorkerRole", "Arn"] } } }
+      }
+    }))
+    .unwrap();
+    let ast = AST::from(template);
+    let options = RenderOptions {
+      title: Some("jobs-stack".to_string()),
+      ..Default::default()
+    };
+
+    let pages = pages(&ast, &options);
+    let names: Vec<&str> = pages.iter().map(|page| page.file_name.as_str()).collect();
+    assert_eq!(names, ["index.md", "Worker.md", "Queue.md"]);
+
+    let expected_index = concat!(
+      "# jobs-stack\n",
+      "\n## lambda\n\n",
+      "- [Worker](Worker.md) (`worker`)\n",
+      "\n## sqs\n\n",
+      "- [Queue](Queue.md) (`jobs`)\n",
+      "\n2 resources, 1 edge.\n",
+    );
+    assert_eq!(pages[0].content, expected_index);
+
+    let expected_page = concat!(
+      "# Worker\n\n[Index](index.md)\n\n",
+      "- Type: `lambda`\n",
+      "- Physical name: `worker`\n",
+      "\n## Properties\n\n",
+      "- `FunctionName`: `worker`\n",
+      "- `Role`: `{\"Fn::GetAtt\":[\"WorkerRole\",\"Arn\"]}`\n",
+      "\n## Inbound\n\n",
+      "- triggers [Queue](Queue.md)\n",
+      "\n## Diagram\n\n",
+    );
+    assert!(pages[1].content.starts_with(expected_page), "{}", pages[1].content);
+    assert!(pages[1].content.contains("```mermaid\nflowchart LR\n"));
+  }
+}
@@ -0,0 +1,164 @@
+use serde_json::Value;
+
+use crate::ast::ast::AST;
+use crate::ast::node::{Node, sanitize_id};
+use crate::cloudformation::resource::ResourceType;
+use crate::render::{RenderOptions, Renderer, mermaid_block};
+
+// The DynamoDB tables as a mermaid `erDiagram`: each table is an entity with
+// its defined attributes, the partition and sort key marked `PK`, and each
+// secondary index is an entity of its own hanging off the table, labelled with
+// what it projects.
+pub struct ErRenderer {
+  pub options: RenderOptions,
+}
+
+impl Renderer for ErRenderer {
+  fn render(&self, ast: &AST) -> String {
+    let ids = ast.ids();
+    let mut result = String::from("erDiagram\n");
+
+    for node in ast.all_nodes().into_iter().filter(|node| node.typ == ResourceType::DynamoDb) {
+      table(node, &ids.of(node), &mut result);
+    }
+
+    mermaid_block(&result, &self.options)
+  }
+}
+
+fn table(node: &Node, id: &str, result: &mut String) {
+  let properties = serde_json::to_value(&*node.properties).unwrap_or_default();
+  let types = attribute_types(&properties);
+  let keys = key_schema(&properties["KeySchema"]);
+
+  result.push_str(&format!("  {} {{\n", id));
+  for (attribute, typ) in &types {
+    match keys.iter().find(|(name, _)| name == attribute) {
+      Some((_, role)) => result.push_str(&format!("    {} {} PK \"{}\"\n", typ, sanitize_id(attribute), role)),
+      None => result.push_str(&format!("    {} {}\n", typ, sanitize_id(attribute))),
+    }
+  }
+  result.push_str("  }\n");
+
+  for (kind, key) in [("GSI", "GlobalSecondaryIndexes"), ("LSI", "LocalSecondaryIndexes")] {
+    for index in properties[key].as_array().into_iter().flatten() {
+      let Some(name) = index.get("IndexName").and_then(Value::as_str) else {
+        continue;
+      };
+      let index_id = format!("{}_{}", id, sanitize_id(name));
+
+      result.push_str(&format!("  {} {{\n", index_id));
+      for (attribute, role) in key_schema(&index["KeySchema"]) {
+        let typ = types.iter().find(|(name, _)| *name == attribute).map_or("string", |(_, typ)| typ);
+        result.push_str(&format!("    {} {} \"{}\"\n", typ, sanitize_id(&attribute), role));
+      }
+      result.push_str("  }\n");
+
+      let label = match index.pointer("/Projection/ProjectionType").and_then(Value::as_str) {
+        Some(projection) => format!("{} {} ({})", kind, name, projection.to_lowercase().replace('_', " ")),
+        None => format!("{} {}", kind, name),
+      };
+      result.push_str(&format!("  {} ||--o| {} : \"{}\"\n", id, index_id, label));
+    }
+  }
+}
+
+// Only attributes used in a key are defined, and the rest of an item is not in
+// the template at all.
+fn attribute_types(properties: &Value) -> Vec<(String, &'static str)> {
+  properties["AttributeDefinitions"]
+    .as_array()
+    .into_iter()
+    .flatten()
+    .filter_map(|definition| {
+      let name = definition.get("AttributeName")?.as_str()?;
+      let typ = match definition.get("AttributeType")?.as_str()? {
+        "N" => "number",
+        "B" => "binary",
+        _ => "string",
+      };
+      Some((name.to_string(), typ))
+    })
+    .collect()
+}
+
+fn key_schema(schema: &Value) -> Vec<(String, &'static str)> {
+  schema
+    .as_array()
+    .into_iter()
+    .flatten()
+    .filter_map(|key| {
+      let name = key.get("AttributeName")?.as_str()?;
+      let role = match key.get("KeyType")?.as_str()? {
+        "RANGE" => "sort key",
+        _ => "partition key",
+      };
+      Some((name.to_string(), role))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::subgraph::Subgraph;
+  use crate::cloudformation::template::Template;
+  use crate::render::Format;
+
+  use super::*;
+
+  #[test]
+  fn test_tables_as_er_diagram() {
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Orders": {
+          "Type": "AWS::DynamoDB::Table",
+          "Properties": {
+            "TableName": "orders",
+            "AttributeDefinitions": [
+              { "AttributeName": "pk", "AttributeType": "S" },
+              { "AttributeName": "createdAt", "AttributeType": "N" },
+              { "AttributeName": "customer-id", "AttributeType": "S" }
+            ],
+            "KeySchema": [{ "AttributeName": "pk", "KeyType": "HASH" }, { "AttributeName": "createdAt", "KeyType": "RANGE" }],
+            "GlobalSecondaryIndexes": [{
+              "IndexName": "ByCustomer",
+              "KeySchema": [{ "AttributeName": "customer-id", "KeyType": "HASH" }, { "AttributeName": "createdAt", "KeyType": "RANGE" }],
+              "Projection": { "ProjectionType": "KEYS_ONLY" }
+            }]
+          }
+        }
+      }
+    }))
+    .unwrap();
+    let ast = AST {
+      subgraphs: vec![Subgraph {
+        name: "app".to_string(),
+        nodes: vec![Node::from(&template.resources[0])],
+        ..Default::default()
+      }],
+      ..Default::default()
+    };
+
+    let options = RenderOptions {
+      raw: true,
+      ..Default::default()
+    };
+    let expected_output = concat!(
+      "erDiagram\n",
+      "  Orders {\n",
+      "    string pk PK \"partition key\"\n",
+      "    number createdAt PK \"sort key\"\n",
+      "    string customer_id\n",
+      "  }\n",
+      "  Orders_ByCustomer {\n",
+      "    string customer_id \"partition key\"\n",
+      "    number createdAt \"sort key\"\n",
+      "  }\n",
+      "  Orders ||--o| Orders_ByCustomer : \"GSI ByCustomer (keys only)\"\n",
+    );
+
+    assert_eq!(Format::ErDiagram.renderer(options).render(&ast), expected_output);
+  }
+}
@@ -0,0 +1,94 @@
+use crate::ast::ast::AST;
+use crate::render::Renderer;
+
+pub struct GraphMlRenderer;
+
+impl Renderer for GraphMlRenderer {
+  fn render(&self, ast: &AST) -> String {
+    let mut result = String::from(concat!(
+      "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+      "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+      "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+      "  <key id=\"type\" for=\"node\" attr.name=\"type\" attr.type=\"string\"/>\n",
+      "  <key id=\"group\" for=\"node\" attr.name=\"group\" attr.type=\"string\"/>\n",
+      "  <key id=\"relationship\" for=\"edge\" attr.name=\"relationship\" attr.type=\"string\"/>\n",
+      "  <key id=\"cross_stack\" for=\"edge\" attr.name=\"cross_stack\" attr.type=\"boolean\"/>\n",
+      "  <graph id=\"cloudmaid\" edgedefault=\"directed\">\n",
+    ));
+
+    for node in ast.nodes() {
+      result.push_str(&format!("    <node id=\"{}\">\n", escape(&node.name.0)));
+      result.push_str(&format!("      <data key=\"label\">{}</data>\n", escape(node.get_name())));
+      result.push_str(&format!("      <data key=\"type\">{}</data>\n", node.typ));
+      if let Some(group) = ast.group_of(node) {
+        result.push_str(&format!("      <data key=\"group\">{}</data>\n", escape(group)));
+      }
+      result.push_str("    </node>\n");
+    }
+
+    let edges = ast
+      .edges
+      .iter()
+      .map(|edge| (edge, false))
+      .chain(ast.cross_stack_edges.iter().map(|edge| (edge, true)));
+
+    for (index, (edge, cross_stack)) in edges.enumerate() {
+      result.push_str(&format!(
+        "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">\n      <data key=\"relationship\">{}</data>\n      <data key=\"cross_stack\">{}</data>\n    </edge>\n",
+        index,
+        escape(&edge.from.name.0),
+        escape(&edge.to.name.0),
+        edge.relationship,
+        cross_stack
+      ));
+    }
+
+    result.push_str("  </graph>\n</graphml>\n");
+    result
+  }
+}
+
+fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ast::edge::Edge;
+  use crate::ast::node::Node;
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::{Name, ResourceType};
+
+  use super::*;
+
+  #[test]
+  fn test_render_graphml() {
+    let sqs_node = Node {
+      name: Name::from("MyQueue"),
+      typ: ResourceType::Sqs,
+      properties: Property::Sqs {
+        queue_name: "my-queue".into(),
+      }.into(),
+    };
+    let lambda_node = Node {
+      name: Name::from("MyLambda"),
+      typ: ResourceType::Lambda,
+      properties: Property::lambda("a&b").into(),
+    };
+    let ast = AST {
+      edges: vec![Edge::new(sqs_node, lambda_node)],
+      ..Default::default()
+    };
+
+    let output = GraphMlRenderer.render(&ast);
+
+    assert!(output.contains("    <node id=\"MyQueue\">\n      <data key=\"label\">my-queue</data>\n      <data key=\"type\">sqs</data>\n    </node>\n"));
+    assert!(output.contains("<data key=\"label\">a&amp;b</data>"));
+    assert!(output.contains("    <edge id=\"e0\" source=\"MyQueue\" target=\"MyLambda\">\n      <data key=\"relationship\">triggers</data>\n      <data key=\"cross_stack\">false</data>\n    </edge>\n"));
+  }
+}
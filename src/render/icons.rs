@@ -0,0 +1,85 @@
+use crate::ast::ids::Ids;
+use crate::ast::node::Node;
+use crate::cloudformation::resource::ResourceType;
+use crate::render::Icons;
+
+// The iconify `logos` pack has icons for the better known services only; the
+// rest keep their shape, or a generic icon where one is needed.
+pub fn logo(typ: &ResourceType) -> Option<&'static str> {
+  match typ {
+    ResourceType::Lambda | ResourceType::EventSourceMapping | ResourceType::FunctionUrl => Some("logos:aws-lambda"),
+    ResourceType::Sqs => Some("logos:aws-sqs"),
+    ResourceType::ApiGateway | ResourceType::UsagePlan | ResourceType::ApiKey => Some("logos:aws-api-gateway"),
+    ResourceType::DynamoDb => Some("logos:aws-dynamodb"),
+    ResourceType::EventRule => Some("logos:aws-eventbridge"),
+    ResourceType::IotRule => Some("logos:aws-iot"),
+    ResourceType::Sns => Some("logos:aws-sns"),
+    ResourceType::StateMachine => Some("logos:aws-step-functions"),
+    ResourceType::Bucket => Some("logos:aws-s3"),
+    ResourceType::Firehose | ResourceType::Kinesis => Some("logos:aws-kinesis"),
+    ResourceType::Redshift => Some("logos:aws-redshift"),
+    ResourceType::GlueJob | ResourceType::GlueCrawler | ResourceType::GlueDatabase | ResourceType::GlueTrigger => Some("logos:aws-glue"),
+    _ => None,
+  }
+}
+
+// FontAwesome classes, named as in both versions 4 and 5, which mermaid draws
+// without any icon pack registered as long as the page loads FontAwesome.
+pub fn font_awesome(typ: &ResourceType) -> &'static str {
+  match typ {
+    ResourceType::Lambda | ResourceType::GlueJob | ResourceType::CodeBuild => "fa:fa-bolt",
+    ResourceType::Sqs | ResourceType::BatchJobQueue => "fa:fa-inbox",
+    ResourceType::ApiGateway | ResourceType::FunctionUrl => "fa:fa-globe",
+    ResourceType::EventSourceMapping => "fa:fa-random",
+    ResourceType::DynamoDb | ResourceType::GlueDatabase | ResourceType::Redshift => "fa:fa-database",
+    ResourceType::EventRule | ResourceType::IotRule | ResourceType::GlueTrigger => "fa:fa-filter",
+    ResourceType::Sns => "fa:fa-bullhorn",
+    ResourceType::SesReceiptRule | ResourceType::SesIdentity => "fa:fa-envelope",
+    ResourceType::StateMachine | ResourceType::Pipeline => "fa:fa-sitemap",
+    ResourceType::Parameter | ResourceType::SsmParameter => "fa:fa-cog",
+    ResourceType::Secret | ResourceType::ApiKey => "fa:fa-key",
+    ResourceType::UsagePlan => "fa:fa-tag",
+    ResourceType::Bucket => "fa:fa-archive",
+    ResourceType::Firehose | ResourceType::Kinesis => "fa:fa-signal",
+    ResourceType::FileSystem | ResourceType::AccessPoint => "fa:fa-folder",
+    ResourceType::GlueCrawler => "fa:fa-search",
+    ResourceType::BatchJobDefinition => "fa:fa-file",
+    ResourceType::BatchComputeEnvironment => "fa:fa-server",
+    ResourceType::External => "fa:fa-cloud",
+    ResourceType::Missing | ResourceType::Other => "fa:fa-question",
+  }
+}
+
+/// Lines that give each node of a flowchart its icon, redeclaring nodes that
+/// were already drawn: with `logos` as an icon shape, and with FontAwesome as
+/// the same shape with the icon in front of the label. `overrides` replace the
+/// icon for a type, in whichever form the set expects.
+pub fn declarations(nodes: &[&Node], ids: &Ids, icons: Icons, overrides: &[(ResourceType, String)], label_format: &str) -> String {
+  let mut result = String::new();
+
+  for node in nodes {
+    let icon = match overrides.iter().find(|(typ, _)| *typ == node.typ) {
+      Some((_, icon)) => Some(icon.as_str()),
+      None => match icons {
+        Icons::Logos => logo(&node.typ),
+        Icons::FontAwesome => Some(font_awesome(&node.typ)),
+      },
+    };
+    let Some(icon) = icon else {
+      continue;
+    };
+
+    match icons {
+      Icons::Logos => {
+        let label = node.label(label_format).replace('"', "#quot;").replace('\n', "<br/>");
+        result.push_str(&format!("{}@{{ icon: \"{}\", label: \"{}\", pos: \"b\" }}\n", ids.of(node), icon, label));
+      }
+      Icons::FontAwesome if node.shape().is_some() => {
+        result.push_str(&format!("{}\n", node.to_mermaid_as(&ids.of(node), &format!("{} {}", icon, label_format))));
+      }
+      Icons::FontAwesome => {}
+    }
+  }
+
+  result
+}
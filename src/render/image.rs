@@ -0,0 +1,43 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::{self, Command};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{CloudmaidError, Result};
+
+const MMDC: &str = "mmdc";
+
+// `--output-dir` renders its images in parallel, so each call needs a source
+// file of its own.
+static SOURCES: AtomicUsize = AtomicUsize::new(0);
+
+// Images are produced by handing the raw mermaid source to mermaid-cli, which
+// ships a headless browser and therefore a real mermaid layout.
+pub fn write(mermaid: &str, output: &Path, extension: &str) -> Result<()> {
+  let count = SOURCES.fetch_add(1, Ordering::Relaxed);
+  let source = env::temp_dir().join(format!("cloudmaid-{}-{}.mmd", process::id(), count));
+  fs::write(&source, mermaid).map_err(|e| CloudmaidError::io(&source, e))?;
+
+  let status = Command::new(MMDC)
+    .arg("--input")
+    .arg(&source)
+    .arg("--output")
+    .arg(output)
+    .arg("--outputFormat")
+    .arg(extension)
+    .arg("--quiet")
+    .status();
+  fs::remove_file(&source).map_err(|e| CloudmaidError::io(&source, e))?;
+
+  match status {
+    Ok(status) if status.success() => Ok(()),
+    Ok(status) => Err(CloudmaidError::Render(format!("{} exited with {}", MMDC, status))),
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Err(CloudmaidError::Render(format!(
+      "{} not found on PATH; install it with `npm install -g @mermaid-js/mermaid-cli`",
+      MMDC
+    ))),
+    Err(e) => Err(CloudmaidError::Render(format!("{}: {}", MMDC, e))),
+  }
+}
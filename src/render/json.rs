@@ -0,0 +1,99 @@
+use serde_json::{Value, json};
+
+use crate::ast::ast::AST;
+use crate::render::Renderer;
+
+pub struct JsonRenderer;
+
+impl Renderer for JsonRenderer {
+  fn render(&self, ast: &AST) -> String {
+    let mut nodes = ast.nodes();
+    nodes.sort_by(|a, b| a.name.0.cmp(&b.name.0));
+
+    let nodes: Vec<Value> = nodes
+      .into_iter()
+      .map(|node| {
+        json!({
+          "id": node.name.0,
+          "type": node.typ.to_string(),
+          "label": node.get_name(),
+          "group": ast.group_of(node),
+          "properties": node.properties,
+        })
+      })
+      .collect();
+
+    let edges: Vec<Value> = ast
+      .edges
+      .iter()
+      .map(|edge| (edge, false))
+      .chain(ast.cross_stack_edges.iter().map(|edge| (edge, true)))
+      .map(|(edge, cross_stack)| {
+        json!({
+          "source": edge.from.name.0,
+          "target": edge.to.name.0,
+          "relationship": edge.relationship.to_string(),
+          "label": edge.label(),
+          "cross_stack": cross_stack,
+        })
+      })
+      .collect();
+
+    serde_json::to_string_pretty(&json!({ "nodes": nodes, "edges": edges })).unwrap_or_default()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ast::edge::Edge;
+  use crate::ast::node::Node;
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::{Name, ResourceType};
+
+  use super::*;
+
+  #[test]
+  fn test_render_json() {
+    let sqs_node = Node {
+      name: Name::from("MyQueue"),
+      typ: ResourceType::Sqs,
+      properties: Property::Sqs {
+        queue_name: "my-queue".into(),
+      }.into(),
+    };
+    let lambda_node = Node {
+      name: Name::from("MyLambda"),
+      typ: ResourceType::Lambda,
+      properties: Property::lambda("my-lambda").into(),
+    };
+    let ast = AST {
+      edges: vec![Edge::new(sqs_node, lambda_node)],
+      ..Default::default()
+    };
+
+    let document: Value = serde_json::from_str(&JsonRenderer.render(&ast)).unwrap();
+
+    assert_eq!(
+      document,
+      json!({
+        "nodes": [
+          {
+            "id": "MyLambda",
+            "type": "lambda",
+            "label": "my-lambda",
+            "group": null,
+            "properties": { "FunctionName": "my-lambda", "Architectures": ["arm64"] }
+          },
+          {
+            "id": "MyQueue",
+            "type": "sqs",
+            "label": "my-queue",
+            "group": null,
+            "properties": { "QueueName": "my-queue" }
+          }
+        ],
+        "edges": [{ "source": "MyQueue", "target": "MyLambda", "relationship": "triggers", "label": "triggers", "cross_stack": false }]
+      })
+    );
+  }
+}
@@ -0,0 +1,161 @@
+use crate::ast::ast::AST;
+use crate::cloudformation::resource::ResourceType;
+use crate::render::{RenderOptions, Renderer, icons, mermaid_block};
+
+pub struct MermaidRenderer {
+  pub options: RenderOptions,
+}
+
+impl Renderer for MermaidRenderer {
+  fn render(&self, ast: &AST) -> String {
+    let ids = ast.ids();
+    let mut flowchart = ast.to_flowchart_with(self.options.label_format());
+
+    if let Some(icons) = self.options.icons {
+      flowchart.push_str(&icons::declarations(&ast.all_nodes(), &ids, icons, &self.options.type_icons, self.options.label_format()));
+    }
+
+    let highlighted: Vec<String> = ast
+      .nodes()
+      .into_iter()
+      .filter(|node| self.options.highlight.iter().any(|name| *name == *node.name.0))
+      .map(|node| ids.of(node).into_owned())
+      .collect();
+    if !highlighted.is_empty() {
+      flowchart.push_str("classDef highlight fill:#ffe08a,stroke:#d4a017,stroke-width:3px\n");
+      flowchart.push_str(&format!("class {} highlight\n", highlighted.join(",")));
+    }
+
+    let ghosts: Vec<String> = ast
+      .nodes()
+      .into_iter()
+      .filter(|node| self.options.ghosts.contains(node))
+      .map(|node| ids.of(node).into_owned())
+      .collect();
+    if !ghosts.is_empty() {
+      flowchart.push_str("classDef ghost fill:#fafafa,stroke:#adb5bd,stroke-dasharray:4,color:#6c757d\n");
+      flowchart.push_str(&format!("class {} ghost\n", ghosts.join(",")));
+    }
+
+    let missing: Vec<String> = ast
+      .nodes()
+      .into_iter()
+      .filter(|node| node.typ == ResourceType::Missing)
+      .map(|node| ids.of(node).into_owned())
+      .collect();
+    if !missing.is_empty() {
+      flowchart.push_str("classDef missing fill:#f8d7da,stroke:#dc3545,stroke-dasharray:4\n");
+      flowchart.push_str(&format!("class {} missing\n", missing.join(",")));
+    }
+
+    mermaid_block(&flowchart, &self.options)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ast::edge::Edge;
+  use crate::ast::node::Node;
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::{Name, ResourceType};
+  use crate::render::{Icons, validate};
+
+  use super::*;
+
+  #[test]
+  fn test_render_raw_without_fences() {
+    let renderer = MermaidRenderer {
+      options: RenderOptions {
+        raw: true,
+        ..Default::default()
+      },
+    };
+
+    assert_eq!(renderer.render(&AST::default()), "flowchart LR\n");
+  }
+
+  #[test]
+  fn test_render_highlight() {
+    let node = Node {
+      name: Name::from("MyQueue"),
+      typ: ResourceType::Sqs,
+      properties: Property::Sqs {
+        queue_name: "my-queue".into(),
+      }.into(),
+    };
+    let ast = AST {
+      edges: vec![Edge::new(node.clone(), node)],
+      ..Default::default()
+    };
+    let renderer = MermaidRenderer {
+      options: RenderOptions {
+        raw: true,
+        highlight: vec!["MyQueue".to_string()],
+        ..Default::default()
+      },
+    };
+    let expected_output = concat!(
+      "flowchart LR\n",
+      "MyQueue((\"my-queue\")) --> MyQueue((\"my-queue\"))\n",
+      "classDef highlight fill:#ffe08a,stroke:#d4a017,stroke-width:3px\n",
+      "class MyQueue highlight\n",
+    );
+
+    assert_eq!(renderer.render(&ast), expected_output);
+  }
+
+  #[test]
+  fn test_render_title_and_header() {
+    let renderer = MermaidRenderer {
+      options: RenderOptions {
+        raw: false,
+        title: Some("my-stack".to_string()),
+        header: Some("Generated by cloudmaid".to_string()),
+        ..Default::default()
+      },
+    };
+    let expected_output = "```mermaid\n---\ntitle: my-stack\n---\n%% Generated by cloudmaid\nflowchart LR\n```";
+
+    assert_eq!(renderer.render(&AST::default()), expected_output);
+  }
+
+  #[test]
+  fn test_render_icons() {
+    let queue = Node {
+      name: Name::from("MyQueue"),
+      typ: ResourceType::Sqs,
+      properties: Property::Sqs {
+        queue_name: "my-queue".into(),
+      }.into(),
+    };
+    let secret = Node {
+      name: Name::from("MySecret"),
+      typ: ResourceType::Secret,
+      properties: Property::default().into(),
+    };
+    let ast = AST {
+      edges: vec![Edge::new(secret, queue)],
+      ..Default::default()
+    };
+    let render = |icons, type_icons| {
+      let options = RenderOptions {
+        raw: true,
+        icons: Some(icons),
+        type_icons,
+        ..Default::default()
+      };
+      MermaidRenderer { options }.render(&ast)
+    };
+
+    let logos = render(Icons::Logos, vec![(ResourceType::Secret, "logos:aws-secrets-manager".to_string())]);
+    assert!(logos.ends_with(concat!(
+      "MySecret@{ icon: \"logos:aws-secrets-manager\", label: \"MySecret\", pos: \"b\" }\n",
+      "MyQueue@{ icon: \"logos:aws-sqs\", label: \"my-queue\", pos: \"b\" }\n",
+    )));
+    assert!(validate::flowchart(&logos).is_ok());
+
+    let font_awesome = render(Icons::FontAwesome, Vec::new());
+    assert!(font_awesome.ends_with("MySecret>\"fa:fa-key MySecret\"]\nMyQueue((\"fa:fa-inbox my-queue\"))\n"));
+    assert!(validate::flowchart(&font_awesome).is_ok());
+  }
+}
@@ -0,0 +1,161 @@
+use crate::ast::ast::AST;
+use crate::ast::ids::Ids;
+use crate::ast::node::{Node, logical_prefix};
+use crate::ast::subgraph::Subgraph;
+use crate::render::{RenderOptions, Renderer, mermaid_block};
+
+// The structure of the stacks as a mermaid `mindmap`, where the flowchart shows
+// what talks to what. Construct paths give the branches; resources outside any
+// construct, as in templates not written with the CDK, are grouped by the first
+// word of their logical ID (`OrdersTable` and `OrdersQueue` under `Orders`).
+pub struct MindmapRenderer {
+  pub options: RenderOptions,
+}
+
+impl Renderer for MindmapRenderer {
+  fn render(&self, ast: &AST) -> String {
+    let label_format = self.options.label_format();
+    let loose: Vec<&Node> = ast.all_nodes().into_iter().filter(|node| ast.group_of(node).is_none()).collect();
+    let ids = ast.ids();
+    let mut result = String::from("mindmap\n");
+
+    // A single stack is the root itself rather than its only branch.
+    let (root, subgraphs, nodes) = match (&ast.subgraphs[..], &self.options.title) {
+      ([stack], None) if loose.is_empty() => (stack.name.as_str(), &stack.subgraphs[..], &stack.nodes[..]),
+      (_, Some(title)) => (title.as_str(), &ast.subgraphs[..], &[][..]),
+      _ => ("Resources", &ast.subgraphs[..], &[][..]),
+    };
+    result.push_str(&format!("  root(({}))\n", text(root)));
+
+    for subgraph in subgraphs {
+      branch(subgraph, 2, label_format, &ids, &mut result);
+    }
+    leaves(nodes, 2, label_format, &ids, &mut result);
+
+    for (prefix, nodes) in by_prefix(&loose) {
+      match prefix {
+        Some(prefix) => {
+          result.push_str(&format!("    {}\n", text(prefix)));
+          leaves(nodes, 3, label_format, &ids, &mut result);
+        }
+        None => leaves(nodes, 2, label_format, &ids, &mut result),
+      }
+    }
+
+    mermaid_block(&result, &self.options)
+  }
+}
+
+fn branch(subgraph: &Subgraph, depth: usize, label_format: &str, ids: &Ids, result: &mut String) {
+  result.push_str(&format!("{}{}\n", "  ".repeat(depth), text(&subgraph.name)));
+  for child in &subgraph.subgraphs {
+    branch(child, depth + 1, label_format, ids, result);
+  }
+  leaves(&subgraph.nodes, depth + 1, label_format, ids, result);
+}
+
+fn leaves<'a>(nodes: impl IntoIterator<Item = &'a Node>, depth: usize, label_format: &str, ids: &Ids, result: &mut String) {
+  for node in nodes {
+    let label = node.label(label_format).replace('\n', ", ").replace('"', "'");
+    result.push_str(&format!("{}{}(\"{}\")\n", "  ".repeat(depth), ids.of(node), label));
+  }
+}
+
+// Only a word shared by more than one resource is worth a branch; the rest are
+// left as leaves of the root, in the order they were drawn.
+fn by_prefix<'a>(nodes: &[&'a Node]) -> Vec<(Option<&'a str>, Vec<&'a Node>)> {
+  let mut groups: Vec<(Option<&str>, Vec<&Node>)> = Vec::new();
+
+  for &node in nodes {
+    let prefix = logical_prefix(&node.name.0).filter(|prefix| {
+      nodes.iter().filter(|other| logical_prefix(&other.name.0) == Some(prefix)).count() > 1
+    });
+    match groups.iter_mut().find(|(group, _)| *group == prefix) {
+      Some((_, group)) => group.push(node),
+      None => groups.push((prefix, vec![node])),
+    }
+  }
+
+  groups
+}
+
+// Brackets would open a shape in the middle of the text.
+fn text(text: &str) -> String {
+  text.replace(['(', ')', '[', ']', '{', '}'], " ").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ast::edge::Edge;
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::{Name, ResourceType};
+  use crate::render::Format;
+
+  use super::*;
+
+  fn node(name: &str, typ: ResourceType) -> Node {
+    Node {
+      name: Name::from(name),
+      typ,
+      properties: Property::Other(serde_json::Value::Null).into(),
+    }
+  }
+
+  #[test]
+  fn test_construct_hierarchy_as_mindmap() {
+    let (queue, worker) = (node("JobsQueue", ResourceType::Sqs), node("WorkerFunction", ResourceType::Lambda));
+    let ast = AST {
+      edges: vec![Edge::new(queue.clone(), worker.clone())],
+      subgraphs: vec![Subgraph {
+        name: "App".to_string(),
+        subgraphs: vec![Subgraph {
+          name: "Jobs".to_string(),
+          nodes: vec![queue, worker],
+          ..Default::default()
+        }],
+        ..Default::default()
+      }],
+      ..Default::default()
+    };
+    let options = RenderOptions {
+      raw: true,
+      ..Default::default()
+    };
+
+    let expected_output = concat!(
+      "mindmap\n",
+      "  root((App))\n",
+      "    Jobs\n",
+      "      JobsQueue(\"JobsQueue\")\n",
+      "      WorkerFunction(\"WorkerFunction\")\n",
+    );
+    assert_eq!(Format::Mindmap.renderer(options).render(&ast), expected_output);
+  }
+
+  #[test]
+  fn test_logical_id_prefixes_as_mindmap() {
+    let ast = AST {
+      edges: vec![
+        Edge::new(node("OrdersQueue", ResourceType::Sqs), node("OrdersHandler", ResourceType::Lambda)),
+        Edge::new(node("OrdersHandler", ResourceType::Lambda), node("Audit", ResourceType::DynamoDb)),
+      ],
+      ..Default::default()
+    };
+    let options = RenderOptions {
+      raw: true,
+      title: Some("shop (prod)".to_string()),
+      ..Default::default()
+    };
+
+    let expected_output = concat!(
+      "---\ntitle: shop (prod)\n---\n",
+      "mindmap\n",
+      "  root((shop  prod))\n",
+      "    Orders\n",
+      "      OrdersQueue(\"OrdersQueue\")\n",
+      "      OrdersHandler(\"OrdersHandler\")\n",
+      "    Audit(\"Audit\")\n",
+    );
+    assert_eq!(Format::Mindmap.renderer(options).render(&ast), expected_output);
+  }
+}
@@ -0,0 +1,156 @@
+use crate::ast::ast::AST;
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::ids::Ids;
+use crate::ast::node::{Node, Shape};
+use crate::ast::subgraph::Subgraph;
+use crate::render::{RenderOptions, Renderer};
+
+pub struct PlantUmlRenderer {
+  pub options: RenderOptions,
+}
+
+impl Renderer for PlantUmlRenderer {
+  fn render(&self, ast: &AST) -> String {
+    let mut result = String::from("@startuml\n");
+
+    if let Some(header) = &self.options.header {
+      result.push_str(&format!("' {}\n", header));
+    }
+
+    if let Some(title) = &self.options.title {
+      result.push_str(&format!("title {}\n", title));
+    }
+    let ids = ast.ids();
+    let mut declared: Vec<&Node> = Vec::new();
+
+    for subgraph in &ast.subgraphs {
+      rectangle(subgraph, 0, self.options.label_format(), &ids, &mut declared, &mut result);
+    }
+
+    for edge in ast.edges.iter().chain(ast.cross_stack_edges.iter()) {
+      for node in [&edge.from, &edge.to] {
+        if !declared.contains(&node) {
+          result.push_str(&format!("{}\n", declaration(node, &ids, self.options.label_format())));
+          declared.push(node);
+        }
+      }
+    }
+
+    for edge in &ast.edges {
+      let arrow = match edge.relationship {
+        Relationship::DependsOn => "-[#999,dotted]->",
+        Relationship::Grants => "-[dotted]->",
+        _ if edge.is_async() => "..>",
+        _ => "-->",
+      };
+      result.push_str(&format!("{} {} {}{}\n", ids.of(&edge.from), arrow, ids.of(&edge.to), label(edge)));
+    }
+
+    for edge in &ast.cross_stack_edges {
+      result.push_str(&format!("{} -[bold]-> {}{}\n", ids.of(&edge.from), ids.of(&edge.to), label(edge)));
+    }
+
+    result.push_str("@enduml");
+    result
+  }
+}
+
+fn rectangle<'a>(
+  subgraph: &'a Subgraph,
+  depth: usize,
+  label_format: &str,
+  ids: &Ids,
+  declared: &mut Vec<&'a Node>,
+  result: &mut String,
+) {
+  let indent = "  ".repeat(depth);
+  result.push_str(&format!("{}rectangle \"{}\" {{\n", indent, subgraph.name));
+
+  for child in &subgraph.subgraphs {
+    rectangle(child, depth + 1, label_format, ids, declared, result);
+  }
+
+  for node in &subgraph.nodes {
+    if !declared.contains(&node) {
+      result.push_str(&format!("{}  {}\n", indent, declaration(node, ids, label_format)));
+      declared.push(node);
+    }
+  }
+
+  result.push_str(&format!("{}}}\n", indent));
+}
+
+fn label(edge: &Edge) -> String {
+  match edge.label() {
+    Some(label) => format!(" : {}", label),
+    None => String::new(),
+  }
+}
+
+fn declaration(node: &Node, ids: &Ids, label_format: &str) -> String {
+  format!(
+    "{} \"{}\" as {}",
+    element(node.shape()),
+    node.label(label_format).replace('\n', "\\n"),
+    ids.of(node)
+  )
+}
+
+fn element(shape: Option<Shape>) -> &'static str {
+  match shape {
+    Some(Shape::Stadium) => "component",
+    Some(Shape::Circle) => "queue",
+    Some(Shape::Subroutine) => "boundary",
+    Some(Shape::Rhombus) => "agent",
+    Some(Shape::Cylinder) => "database",
+    Some(Shape::Trapezoid) => "process",
+    Some(Shape::Rounded) => "node",
+    Some(Shape::Hexagon) => "hexagon",
+    Some(Shape::Parallelogram) => "card",
+    Some(Shape::Asymmetric) => "file",
+    Some(Shape::Rectangle) => "cloud",
+    None => "rectangle",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::{Name, ResourceType};
+
+  use super::*;
+
+  #[test]
+  fn test_render_plantuml() {
+    let api_node = Node {
+      name: Name::from("myapi"),
+      typ: ResourceType::ApiGateway,
+      properties: Property::ApiGateway {
+        http_method: "POST".to_string(),
+        integration: json!({}),
+        rest_api_id: serde_json::Value::Null,
+        resource_id: serde_json::Value::Null,
+        resource_path: None,
+      }.into(),
+    };
+    let lambda_node = Node {
+      name: Name::from("mylambda"),
+      typ: ResourceType::Lambda,
+      properties: Property::lambda("my-lambda").into(),
+    };
+    let ast = AST {
+      edges: vec![Edge::new(api_node, lambda_node)],
+      ..Default::default()
+    };
+
+    let expected_output = "@startuml\nboundary \"myapi\" as myapi\ncomponent \"my-lambda\" as mylambda\nmyapi --> mylambda : invokes\n@enduml";
+
+    let renderer = PlantUmlRenderer {
+      options: RenderOptions::default(),
+    };
+
+    assert_eq!(renderer.render(&ast), expected_output);
+  }
+}
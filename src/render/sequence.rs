@@ -0,0 +1,200 @@
+use tracing::warn;
+
+use crate::ast::ast::AST;
+use crate::ast::edge::{Edge, Relationship};
+use crate::ast::node::Node;
+use crate::cloudformation::resource::ResourceType;
+use crate::render::{RenderOptions, Renderer, mermaid_block};
+
+// One flow through the graph, told as the messages passed along it. The walk
+// starts at `RenderOptions::entry` and follows edges depth first, so each call
+// is followed by whatever it sets off before the next call is made.
+pub struct SequenceRenderer {
+  pub options: RenderOptions,
+}
+
+impl Renderer for SequenceRenderer {
+  fn render(&self, ast: &AST) -> String {
+    // Permissions and deployment order say nothing about what calls what.
+    let edges: Vec<&Edge> = ast
+      .edges
+      .iter()
+      .chain(ast.cross_stack_edges.iter())
+      .filter(|edge| !matches!(edge.relationship, Relationship::Grants | Relationship::DependsOn))
+      .collect();
+
+    let mut messages = Vec::new();
+    let mut visited = Vec::new();
+    let mut client = None;
+
+    match self.entry(ast, &edges) {
+      Some(Entry::Route(index)) => {
+        let edge = edges[index];
+        client = Some((&edge.from, edge.detail.clone()));
+        messages.push(edge);
+        visited.extend([&edge.from, &edge.to]);
+        walk(&edge.to, &edges, &mut visited, &mut messages);
+      }
+      Some(Entry::Node(node)) => {
+        if matches!(node.typ, ResourceType::ApiGateway | ResourceType::FunctionUrl) {
+          client = Some((node, None));
+        }
+        visited.push(node);
+        walk(node, &edges, &mut visited, &mut messages);
+      }
+      None => {}
+    }
+
+    let ids = ast.ids();
+    let mut result = String::from("sequenceDiagram\n");
+    if client.is_some() {
+      result.push_str("actor Client\n");
+    }
+
+    let mut participants: Vec<&Node> = Vec::new();
+    for node in client.iter().map(|(node, _)| *node).chain(messages.iter().flat_map(|edge| [&edge.from, &edge.to])) {
+      if !participants.contains(&node) {
+        result.push_str(&format!("participant {} as {}\n", ids.of(node), text(&node.label(self.options.label_format()))));
+        participants.push(node);
+      }
+    }
+
+    if let Some((api, route)) = &client {
+      result.push_str(&format!("Client->>{}: {}\n", ids.of(api), text(route.as_deref().unwrap_or("request"))));
+    }
+    for edge in &messages {
+      let arrow = if edge.is_async() { "-)" } else { "->>" };
+      let label = edge.label().unwrap_or_else(|| edge.relationship.to_string());
+      result.push_str(&format!("{}{}{}: {}\n", ids.of(&edge.from), arrow, ids.of(&edge.to), text(&label)));
+    }
+
+    mermaid_block(&result, &self.options)
+  }
+}
+
+enum Entry<'a> {
+  // An edge out of a collapsed API, picked by its method and path.
+  Route(usize),
+  Node(&'a Node),
+}
+
+impl SequenceRenderer {
+  // Without an entry point, or with one that is not in the graph, the flow
+  // starts at the first resource nothing leads into.
+  fn entry<'a>(&self, ast: &'a AST, edges: &[&'a Edge]) -> Option<Entry<'a>> {
+    if let Some(entry) = &self.options.entry {
+      let route = edges
+        .iter()
+        .position(|edge| edge.detail.as_deref().is_some_and(|route| route.eq_ignore_ascii_case(entry)));
+      if let Some(index) = route {
+        return Some(Entry::Route(index));
+      }
+      if let Some(node) = ast.find(entry) {
+        return Some(Entry::Node(node));
+      }
+      warn!("entry point {} is neither a resource nor an API route, starting from the first source", entry);
+    }
+
+    let nodes = ast.nodes();
+    let source = nodes.iter().find(|node| !edges.iter().any(|edge| edge.to == ***node));
+    source.or(nodes.first()).map(|node| Entry::Node(node))
+  }
+}
+
+fn walk<'a>(node: &Node, edges: &[&'a Edge], visited: &mut Vec<&'a Node>, messages: &mut Vec<&'a Edge>) {
+  for edge in edges.iter().filter(|edge| edge.from == *node) {
+    if messages.iter().any(|message| std::ptr::eq(*message, *edge)) {
+      continue;
+    }
+    messages.push(edge);
+    if !visited.contains(&&edge.to) {
+      visited.push(&edge.to);
+      walk(&edge.to, edges, visited, messages);
+    }
+  }
+}
+
+// `#` starts an entity code and `;` ends a statement in sequence diagrams.
+fn text(text: &str) -> String {
+  text.replace('#', "#35;").replace(';', "#59;").replace('\n', "<br/>")
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::api::consolidate;
+  use crate::cloudformation::stack::Stack;
+  use crate::cloudformation::template::Template;
+  use crate::render::Format;
+
+  use super::*;
+
+  fn render(ast: &AST, entry: Option<&str>) -> String {
+    let options = RenderOptions {
+      raw: true,
+      entry: entry.map(str::to_string),
+      ..Default::default()
+    };
+    Format::Sequence.renderer(options).render(ast)
+  }
+
+  #[test]
+  fn test_sequence_from_route_and_event_source() {
+    let uri = |function: &str| json!({ "Uri": { "Fn::Sub": format!("arn:aws:apigateway:${{AWS::Region}}:lambda:path/2015-03-31/functions/${{{}.Arn}}/invocations", function) } });
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Api": { "Type": "AWS::ApiGateway::RestApi", "Properties": { "Name": "orders" } },
+        "CreateOrder": {
+          "Type": "AWS::ApiGateway::Method",
+          "Properties": { "HttpMethod": "POST", "RestApiId": { "Ref": "Api" }, "ResourcePath": "/orders", "Integration": uri("Create") }
+        },
+        "GetOrder": {
+          "Type": "AWS::ApiGateway::Method",
+          "Properties": { "HttpMethod": "GET", "RestApiId": { "Ref": "Api" }, "ResourcePath": "/orders/{id}", "Integration": uri("Get") }
+        },
+        "Create": {
+          "Type": "AWS::Lambda::Function",
+          "Properties": { "FunctionName": "create", "Environment": { "Variables": { "QUEUE": { "Ref": "Jobs" } } } }
+        },
+        "Get": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "get" } },
+        "Jobs": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+        "Mapping": {
+          "Type": "AWS::Lambda::EventSourceMapping",
+          "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Jobs", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+        },
+        "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker" } }
+      }
+    }))
+    .unwrap();
+    let stacks = vec![Stack {
+      name: "app".to_string(),
+      template: template.clone(),
+    }];
+    let mut ast = AST::from(template);
+    consolidate(&mut ast, &stacks);
+
+    let expected_output = concat!(
+      "sequenceDiagram\n",
+      "actor Client\n",
+      "participant Api as Api\n",
+      "participant Create as create\n",
+      "participant Jobs as jobs\n",
+      "participant Worker as worker\n",
+      "Client->>Api: POST /orders\n",
+      "Api->>Create: invokes POST /orders\n",
+      "Create->>Jobs: configures\n",
+      "Jobs-)Worker: triggers\n",
+    );
+    assert_eq!(render(&ast, Some("post /orders")), expected_output);
+
+    let expected_output = concat!(
+      "sequenceDiagram\n",
+      "participant Jobs as jobs\n",
+      "participant Worker as worker\n",
+      "Jobs-)Worker: triggers\n",
+    );
+    assert_eq!(render(&ast, Some("jobs")), expected_output);
+    assert!(render(&ast, None).starts_with("sequenceDiagram\nactor Client\nparticipant Api as Api\n"));
+  }
+}
@@ -0,0 +1,57 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use serde_json::json;
+use std::io::Write;
+
+const EDITOR: &str = "https://mermaid.live/edit#pako:";
+const IMAGE: &str = "https://mermaid.ink/img/pako:";
+
+// Both sites read the editor's state from the URL as zlib-compressed JSON in
+// URL-safe base64, the same encoding the editor's "Copy link" produces.
+pub fn edit_url(mermaid: &str) -> String {
+  format!("{}{}", EDITOR, pako(mermaid))
+}
+
+pub fn image_url(mermaid: &str) -> String {
+  format!("{}{}", IMAGE, pako(mermaid))
+}
+
+fn pako(mermaid: &str) -> String {
+  let state = json!({
+    "code": mermaid,
+    "mermaid": json!({ "theme": "default" }).to_string(),
+    "autoSync": true,
+    "updateDiagram": true,
+  });
+
+  let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+  encoder.write_all(state.to_string().as_bytes()).expect("writing to a Vec cannot fail");
+  URL_SAFE_NO_PAD.encode(encoder.finish().expect("writing to a Vec cannot fail"))
+}
+
+#[cfg(test)]
+mod tests {
+  use flate2::read::ZlibDecoder;
+  use serde_json::Value;
+  use std::io::Read;
+
+  use super::*;
+
+  #[test]
+  fn test_share_urls_round_trip() {
+    let mermaid = "flowchart LR\nworker([worker]) -->|writes| jobs((jobs))\n";
+
+    let url = edit_url(mermaid);
+    let encoded = url.strip_prefix(EDITOR).unwrap();
+    assert!(!encoded.contains(['+', '/', '=']));
+
+    let mut state = String::new();
+    ZlibDecoder::new(URL_SAFE_NO_PAD.decode(encoded).unwrap().as_slice()).read_to_string(&mut state).unwrap();
+    let state: Value = serde_json::from_str(&state).unwrap();
+    assert_eq!(state["code"], mermaid);
+
+    assert_eq!(image_url(mermaid), format!("{}{}", IMAGE, encoded));
+  }
+}
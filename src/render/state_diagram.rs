@@ -0,0 +1,255 @@
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use crate::ast::ast::AST;
+use crate::ast::node::{Node, sanitize_id};
+use crate::ast::state_machine::definition;
+use crate::cloudformation::resource::ResourceType;
+use crate::render::{RenderOptions, Renderer, mermaid_block};
+
+// The states of one state machine, picked by `RenderOptions::resource`, as a
+// mermaid `stateDiagram-v2`. Parallel branches and Map iterations become
+// composite states, Choice rules become labelled transitions, and `Catch`
+// clauses become transitions labelled with the errors they catch.
+pub struct StateDiagramRenderer {
+  pub options: RenderOptions,
+}
+
+impl Renderer for StateDiagramRenderer {
+  fn render(&self, ast: &AST) -> String {
+    let mut result = String::from("stateDiagram-v2\n");
+
+    match self.state_machine(ast).and_then(|node| definition(node.properties.untyped()?)) {
+      Some(definition) => machine(&definition, 0, &mut result),
+      None => warn!("--format statediagram needs --resource naming a state machine with a definition"),
+    }
+
+    mermaid_block(&result, &self.options)
+  }
+}
+
+impl StateDiagramRenderer {
+  fn state_machine<'a>(&self, ast: &'a AST) -> Option<&'a Node> {
+    let name = self.options.resource.as_deref()?;
+    let nodes = ast.all_nodes();
+    let machines = || nodes.iter().filter(|node| node.typ == ResourceType::StateMachine);
+    machines()
+      .find(|node| &*node.name.0 == name)
+      .or_else(|| machines().find(|node| node.get_name() == name))
+      .copied()
+  }
+}
+
+// `StartAt` and `States`, at the top level or inside a branch or iterator.
+fn machine(definition: &Value, depth: usize, result: &mut String) {
+  let indent = "  ".repeat(depth);
+  let Some(states) = definition.get("States").and_then(Value::as_object) else {
+    return;
+  };
+
+  if let Some(start) = definition.get("StartAt").and_then(Value::as_str) {
+    result.push_str(&format!("{}[*] --> {}\n", indent, sanitize_id(start)));
+  }
+  for (name, state) in states {
+    state_of(name, state, depth, result);
+  }
+}
+
+fn state_of(name: &str, state: &Value, depth: usize, result: &mut String) {
+  let indent = "  ".repeat(depth);
+  let id = sanitize_id(name);
+  if id != name {
+    result.push_str(&format!("{}state \"{}\" as {}\n", indent, text(name), id));
+  }
+
+  match state.get("Type").and_then(Value::as_str).unwrap_or_default() {
+    "Choice" => {
+      result.push_str(&format!("{}state {} <<choice>>\n", indent, id));
+      for rule in state.get("Choices").and_then(Value::as_array).into_iter().flatten() {
+        if let Some(next) = rule.get("Next").and_then(Value::as_str) {
+          transition(&id, next, Some(&condition(rule)), depth, result);
+        }
+      }
+      if let Some(default) = state.get("Default").and_then(Value::as_str) {
+        transition(&id, default, Some("default"), depth, result);
+      }
+    }
+    "Parallel" => {
+      result.push_str(&format!("{}state {} {{\n", indent, id));
+      for (index, branch) in state.get("Branches").and_then(Value::as_array).into_iter().flatten().enumerate() {
+        if index > 0 {
+          result.push_str(&format!("{}  --\n", indent));
+        }
+        machine(branch, depth + 1, result);
+      }
+      result.push_str(&format!("{}}}\n", indent));
+    }
+    // `ItemProcessor` replaced `Iterator`, which older definitions still use.
+    "Map" => {
+      result.push_str(&format!("{}state {} {{\n", indent, id));
+      if let Some(processor) = state.get("ItemProcessor").or(state.get("Iterator")) {
+        machine(processor, depth + 1, result);
+      }
+      result.push_str(&format!("{}}}\n", indent));
+    }
+    _ => {}
+  }
+
+  if let Some(next) = state.get("Next").and_then(Value::as_str) {
+    transition(&id, next, None, depth, result);
+  }
+  let ends = matches!(state.get("Type").and_then(Value::as_str), Some("Succeed" | "Fail"));
+  if ends || state.get("End").and_then(Value::as_bool) == Some(true) {
+    result.push_str(&format!("{}{} --> [*]\n", indent, id));
+  }
+
+  for catcher in state.get("Catch").and_then(Value::as_array).into_iter().flatten() {
+    if let Some(next) = catcher.get("Next").and_then(Value::as_str) {
+      let errors: Vec<&str> = catcher.get("ErrorEquals").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str).collect();
+      transition(&id, next, Some(&format!("catch {}", errors.join(", "))), depth, result);
+    }
+  }
+}
+
+fn transition(from: &str, to: &str, label: Option<&str>, depth: usize, result: &mut String) {
+  let indent = "  ".repeat(depth);
+  match label {
+    Some(label) => result.push_str(&format!("{}{} --> {}: {}\n", indent, from, sanitize_id(to), text(label))),
+    None => result.push_str(&format!("{}{} --> {}\n", indent, from, sanitize_id(to))),
+  }
+}
+
+// `$.status StringEquals done`, with `And`, `Or` and `Not` spelled out. JSONata
+// rules carry their `Condition` as text already.
+fn condition(rule: &Value) -> String {
+  let Some(rule) = rule.as_object() else {
+    return String::new();
+  };
+  if let Some(condition) = rule.get("Condition").and_then(Value::as_str) {
+    return condition.to_string();
+  }
+
+  let nested = |key: &str, separator: &str| -> Option<String> {
+    let rules = rule.get(key)?.as_array()?;
+    Some(rules.iter().map(condition).collect::<Vec<_>>().join(separator))
+  };
+  if let Some(all) = nested("And", " and ") {
+    return all;
+  }
+  if let Some(any) = nested("Or", " or ") {
+    return any;
+  }
+  if let Some(not) = rule.get("Not") {
+    return format!("not {}", condition(not));
+  }
+
+  comparison(rule)
+}
+
+fn comparison(rule: &Map<String, Value>) -> String {
+  let variable = rule.get("Variable").and_then(Value::as_str).unwrap_or_default();
+  let test = rule.iter().find(|(key, _)| !matches!(key.as_str(), "Variable" | "Next" | "Comment" | "Assign" | "Output"));
+
+  match test {
+    Some((operator, Value::String(value))) => format!("{} {} {}", variable, operator, value),
+    Some((operator, value)) => format!("{} {} {}", variable, operator, value),
+    None => variable.to_string(),
+  }
+}
+
+// A transition label runs to the end of the line, where `;` would end the
+// statement early and `#` start an entity code.
+fn text(text: &str) -> String {
+  text.replace('#', "#35;").replace(';', "#59;").replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::ast::subgraph::Subgraph;
+  use crate::cloudformation::template::Template;
+  use crate::render::Format;
+
+  use super::*;
+
+  #[test]
+  fn test_state_machine_as_state_diagram() {
+    let definition = json!({
+      "StartAt": "Validate",
+      "States": {
+        "Validate": { "Type": "Task", "Resource": "arn:aws:states:::lambda:invoke", "Next": "Is Valid?", "Catch": [{ "ErrorEquals": ["States.ALL"], "Next": "Failed" }] },
+        "Is Valid?": {
+          "Type": "Choice",
+          "Choices": [
+            { "Variable": "$.valid", "BooleanEquals": true, "Next": "Fan Out" },
+            { "And": [{ "Variable": "$.retry", "IsPresent": true }, { "Variable": "$.attempts", "NumericLessThan": 3 }], "Next": "Validate" }
+          ],
+          "Default": "Failed"
+        },
+        "Fan Out": {
+          "Type": "Parallel",
+          "Next": "Each",
+          "Branches": [
+            { "StartAt": "Save", "States": { "Save": { "Type": "Task", "Resource": "arn:aws:states:::dynamodb:putItem", "End": true } } },
+            { "StartAt": "Notify", "States": { "Notify": { "Type": "Task", "Resource": "arn:aws:states:::sns:publish", "End": true } } }
+          ]
+        },
+        "Each": {
+          "Type": "Map",
+          "End": true,
+          "ItemProcessor": { "StartAt": "Ship", "States": { "Ship": { "Type": "Pass", "End": true } } }
+        },
+        "Failed": { "Type": "Fail" }
+      }
+    });
+    let template: Template = serde_json::from_value(json!({
+      "Resources": {
+        "Orders": { "Type": "AWS::StepFunctions::StateMachine", "Properties": { "Definition": definition } }
+      }
+    }))
+    .unwrap();
+    let ast = AST {
+      subgraphs: vec![Subgraph {
+        name: "app".to_string(),
+        nodes: vec![Node::from(&template.resources[0])],
+        ..Default::default()
+      }],
+      ..Default::default()
+    };
+
+    let options = RenderOptions {
+      raw: true,
+      resource: Some("Orders".to_string()),
+      ..Default::default()
+    };
+    let expected_output = concat!(
+      "stateDiagram-v2\n",
+      "[*] --> Validate\n",
+      "Validate --> Is_Valid_\n",
+      "Validate --> Failed: catch States.ALL\n",
+      "state \"Is Valid?\" as Is_Valid_\n",
+      "state Is_Valid_ <<choice>>\n",
+      "Is_Valid_ --> Fan_Out: $.valid BooleanEquals true\n",
+      "Is_Valid_ --> Validate: $.retry IsPresent true and $.attempts NumericLessThan 3\n",
+      "Is_Valid_ --> Failed: default\n",
+      "state \"Fan Out\" as Fan_Out\n",
+      "state Fan_Out {\n",
+      "  [*] --> Save\n",
+      "  Save --> [*]\n",
+      "  --\n",
+      "  [*] --> Notify\n",
+      "  Notify --> [*]\n",
+      "}\n",
+      "Fan_Out --> Each\n",
+      "state Each {\n",
+      "  [*] --> Ship\n",
+      "  Ship --> [*]\n",
+      "}\n",
+      "Each --> [*]\n",
+      "Failed --> [*]\n",
+    );
+
+    assert_eq!(Format::StateDiagram.renderer(options).render(&ast), expected_output);
+  }
+}
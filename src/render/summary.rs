@@ -0,0 +1,151 @@
+use crate::ast::analysis::Listing;
+use crate::ast::ast::AST;
+use crate::ast::diff::Diff;
+use crate::ast::edge::Edge;
+use crate::render::{Format, RenderOptions, mermaid_block};
+
+/// One markdown document for a CI job summary or a PR comment: the diagram, the
+/// changes against a base template when there is one, and the resources in a
+/// collapsed table so a long stack does not push the rest off the page.
+pub fn summary(ast: &AST, listing: &Listing, diff: Option<&Diff>, options: &RenderOptions) -> String {
+  let mut result = format!("## {}\n\n", options.title.as_deref().unwrap_or("Architecture"));
+
+  // The title is the heading, and a code block is the only way the diagram
+  // renders on GitHub.
+  let options = RenderOptions {
+    raw: false,
+    title: None,
+    ..options.clone()
+  };
+  let diagram = match diff {
+    Some(diff) => mermaid_block(&diff.to_flowchart(), &options),
+    None => Format::Mermaid.renderer(options).render(ast),
+  };
+  result.push_str(&format!("{}\n", diagram));
+
+  if let Some(diff) = diff {
+    result.push_str(&changes(diff));
+  }
+  result.push_str(&resources(listing));
+  result
+}
+
+fn changes(diff: &Diff) -> String {
+  if diff.is_empty() {
+    return "\nNo changes to the architecture.\n".to_string();
+  }
+
+  let mut result = format!(
+    "\n### Changes\n\n{} added, {} removed and {} changed resources; {} added and {} removed edges.\n\n",
+    diff.added_nodes.len(),
+    diff.removed_nodes.len(),
+    diff.changed_nodes.len(),
+    diff.added_edges.len(),
+    diff.removed_edges.len()
+  );
+  result.push_str("| Change | Resource | Type |\n|---|---|---|\n");
+
+  for (change, nodes) in [("added", &diff.added_nodes), ("removed", &diff.removed_nodes), ("changed", &diff.changed_nodes)] {
+    for node in nodes {
+      result.push_str(&format!("| {} | `{}` | {} |\n", change, node.name.0, node.typ));
+    }
+  }
+  for (change, edges) in [("added", &diff.added_edges), ("removed", &diff.removed_edges)] {
+    for edge in edges {
+      result.push_str(&format!("| {} | `{}` → `{}` | {} |\n", change, edge.from.name.0, edge.to.name.0, relationship(edge)));
+    }
+  }
+  result
+}
+
+fn resources(listing: &Listing) -> String {
+  let stacks = listing.resources.iter().any(|r| r.stack != listing.resources[0].stack);
+
+  let mut result = format!("\n<details>\n<summary>Resources ({})</summary>\n\n", listing.resources.len());
+  match stacks {
+    true => result.push_str("| Stack | Resource | Type | Name |\n|---|---|---|---|\n"),
+    false => result.push_str("| Resource | Type | Name |\n|---|---|---|\n"),
+  }
+  for r in &listing.resources {
+    let name = r.name.replace('|', "\\|");
+    match stacks {
+      true => result.push_str(&format!("| {} | `{}` | {} | {} |\n", r.stack, r.id, r.typ, name)),
+      false => result.push_str(&format!("| `{}` | {} | {} |\n", r.id, r.typ, name)),
+    }
+  }
+  result.push_str("\n</details>\n");
+  result
+}
+
+fn relationship(edge: &Edge) -> String {
+  edge.label().unwrap_or_else(|| edge.relationship.to_string()).replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::cloudformation::stack::Stack;
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  fn stack(resources: serde_json::Value) -> Stack {
+    let template: Template = serde_json::from_value(json!({ "Resources": resources })).unwrap();
+    Stack {
+      name: "jobs".to_string(),
+      template,
+    }
+  }
+
+  #[test]
+  fn test_summary_against_base() {
+    let mapping = json!({
+      "Type": "AWS::Lambda::EventSourceMapping",
+      "Properties": { "EventSourceArn": { "Fn::GetAtt": ["Queue", "Arn"] }, "FunctionName": { "Ref": "Worker" } }
+    });
+    let base = stack(json!({
+      "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+      "Mapping": mapping,
+      "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker" } }
+    }));
+    let head = stack(json!({
+      "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } },
+      "Mapping": mapping,
+      "Worker": {
+        "Type": "AWS::Lambda::Function",
+        "Properties": { "FunctionName": "worker", "Environment": { "Variables": { "TABLE": { "Ref": "Table" } } } }
+      },
+      "Table": { "Type": "AWS::DynamoDB::Table", "Properties": { "TableName": "orders" } }
+    }));
+    let (old, new) = (AST::from(base.template.clone()), AST::from(head.template.clone()));
+    let diff = Diff::new(&old, &new);
+    let listing = Listing::new(std::slice::from_ref(&head), |_| true);
+    let options = RenderOptions {
+      title: Some("jobs".to_string()),
+      ..Default::default()
+    };
+
+    let document = summary(&new, &listing, Some(&diff), &options);
+
+    assert!(document.starts_with("## jobs\n\n```mermaid\nflowchart LR\n"));
+    assert!(document.contains("1 added, 0 removed and 1 changed resources; 1 added and 0 removed edges."));
+    assert!(document.contains(concat!(
+      "| Change | Resource | Type |\n|---|---|---|\n",
+      "| added | `Table` | dynamodb |\n",
+      "| changed | `Worker` | lambda |\n",
+      "| added | `Worker` → `Table` | configures |\n",
+    )), "{}", document);
+    assert!(document.ends_with(concat!(
+      "<summary>Resources (4)</summary>\n\n",
+      "| Resource | Type | Name |\n|---|---|---|\n",
+      "| `Queue` | sqs | jobs |\n",
+      "| `Mapping` | eventsourcemapping | Mapping |\n",
+      "| `Worker` | lambda | worker |\n",
+      "| `Table` | dynamodb | orders |\n",
+      "\n</details>\n",
+    )), "{}", document);
+
+    assert!(summary(&new, &listing, Some(&Diff::new(&new, &new)), &options).contains("No changes to the architecture."));
+  }
+}
@@ -0,0 +1,216 @@
+use crate::error::{CloudmaidError, Result};
+
+/// Checks a rendered flowchart for what mermaid would refuse to parse: links
+/// missing a node, IDs it does not accept and unbalanced shapes. Mermaid
+/// rejects the whole diagram over one bad line, so it is better not written at
+/// all than written and broken. Anything that is not a flowchart is let through.
+pub fn flowchart(diagram: &str) -> Result<()> {
+  let mut statements = statements(diagram);
+  match statements.next() {
+    Some((_, first)) if first.starts_with("flowchart") => {}
+    _ => return Ok(()),
+  }
+
+  for (number, line) in statements {
+    check(line)
+      .map_err(|problem| CloudmaidError::Render(format!("mermaid would reject line {} `{}`: {}", number, line, problem)))?;
+  }
+  Ok(())
+}
+
+// The lines of the diagram itself, numbered as in the output, without the
+// fences, front-matter and comments around it.
+fn statements(diagram: &str) -> impl Iterator<Item = (usize, &str)> {
+  let mut front_matter = false;
+
+  diagram
+    .lines()
+    .enumerate()
+    .map(|(index, line)| (index + 1, line.trim()))
+    .filter(move |(_, line)| {
+      if *line == "---" {
+        front_matter = !front_matter;
+        return false;
+      }
+      !front_matter && !line.is_empty() && !line.starts_with("%%") && !line.starts_with("```")
+    })
+}
+
+fn check(line: &str) -> std::result::Result<(), String> {
+  let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+  match keyword {
+    "end" if rest.is_empty() => Ok(()),
+    "direction" | "classDef" | "class" | "linkStyle" | "style" => Ok(()),
+    "subgraph" => match skeleton(rest)?.split_whitespace().collect::<Vec<_>>()[..] {
+      [id] => node(id),
+      _ => Err("a subgraph needs a single ID, with any title in brackets".to_string()),
+    },
+    _ => links(&skeleton(line)?),
+  }
+}
+
+// A statement is nodes joined by links: `a --> b -.->|label| c`.
+fn links(skeleton: &str) -> std::result::Result<(), String> {
+  let mut previous: Option<&str> = None;
+
+  for token in skeleton.split_whitespace() {
+    match (previous, is_link(token)) {
+      (None, true) => return Err(format!("empty node statement before `{}`", token)),
+      (Some(link), true) if is_link(link) => return Err(format!("empty node statement between `{}` and `{}`", link, token)),
+      (Some(id), false) if !is_link(id) => return Err(format!("`{}` and `{}` are not joined by a link", id, token)),
+      (_, false) => node(token)?,
+      _ => {}
+    }
+    previous = Some(token);
+  }
+
+  match previous {
+    Some(link) if is_link(link) => Err(format!("empty node statement after `{}`", link)),
+    _ => Ok(()),
+  }
+}
+
+fn node(id: &str) -> std::result::Result<(), String> {
+  if id.eq_ignore_ascii_case("end") {
+    return Err(format!("node `{}` uses the reserved word `end` as its ID", id));
+  }
+  match id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+    true => Ok(()),
+    false => Err(format!("node `{}` is not a valid ID", id)),
+  }
+}
+
+fn is_link(token: &str) -> bool {
+  (token.contains('-') || token.contains('=')) && token.chars().all(|c| matches!(c, '-' | '=' | '.' | '>' | '<'))
+}
+
+// The line with every shape, quoted label and link label taken out, leaving
+// only the IDs and links between them.
+fn skeleton(line: &str) -> std::result::Result<String, String> {
+  let chars: Vec<char> = line.chars().collect();
+  let mut skeleton = String::new();
+  let mut i = 0;
+
+  while i < chars.len() {
+    let after_id = i > 0 && (chars[i - 1].is_ascii_alphanumeric() || chars[i - 1] == '_');
+    match chars[i] {
+      '"' => i = after(&chars, i, '"').ok_or("unterminated quoted label")?,
+      '|' => i = after(&chars, i, '|').ok_or("unterminated link label")?,
+      '(' | '[' | '{' => i = shape(&chars, i)?,
+      // The asymmetric shape, `id>label]`, opens with `>`.
+      '>' if after_id => i = shape(&chars, i)?,
+      // Shape data, such as an icon, follows `@`: `id@{ icon: "logos:aws-s3" }`.
+      '@' if after_id && chars.get(i + 1) == Some(&'{') => i = shape(&chars, i + 1)?,
+      c @ (')' | ']' | '}') => return Err(format!("`{}` closes nothing", c)),
+      c => {
+        skeleton.push(c);
+        i += 1;
+      }
+    }
+  }
+  Ok(skeleton)
+}
+
+// Where the shape opening at `start` ends. Mermaid only reads brackets as the
+// outline of a shape, so any inside the label have to be quoted.
+fn shape(chars: &[char], start: usize) -> std::result::Result<usize, String> {
+  let opening: String = match chars[start] {
+    '>' => ">".to_string(),
+    _ => chars[start..].iter().take(2).take_while(|c| matches!(c, '(' | '[' | '{')).collect(),
+  };
+
+  let mut i = start + opening.len();
+  loop {
+    match chars.get(i) {
+      None => return Err(format!("`{}` is never closed", opening)),
+      Some('"') => i = after(chars, i, '"').ok_or("unterminated quoted label")?,
+      Some(c @ ('(' | '[' | '{')) => return Err(format!("`{}` inside a label must be quoted", c)),
+      Some(')' | ']' | '}') => break,
+      Some(_) => i += 1,
+    }
+  }
+
+  let expected: String = opening.chars().rev().map(closing).collect();
+  let closed: String = chars[i..].iter().take(expected.len()).collect();
+  match closed == expected {
+    true => Ok(i + expected.len()),
+    false => Err(format!("`{}` is closed by `{}`", opening, closed)),
+  }
+}
+
+// The index after the next `delimiter`, skipping the one at `start`.
+fn after(chars: &[char], start: usize, delimiter: char) -> Option<usize> {
+  chars[start + 1..].iter().position(|&c| c == delimiter).map(|offset| start + offset + 2)
+}
+
+fn closing(opening: char) -> char {
+  match opening {
+    '(' => ')',
+    '{' => '}',
+    _ => ']',
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ast::ast::AST;
+  use crate::ast::edge::Edge;
+  use crate::ast::node::Node;
+  use crate::ast::subgraph::Subgraph;
+  use crate::cloudformation::property::Property;
+  use crate::cloudformation::resource::{Name, ResourceType};
+  use crate::render::{Format, RenderOptions};
+  use clap::ValueEnum;
+
+  fn node(name: &str, typ: ResourceType) -> Node {
+    Node {
+      name: Name::from(name),
+      typ,
+      properties: Property::Other(serde_json::Value::Null).into(),
+    }
+  }
+
+  #[test]
+  fn test_every_shape_is_accepted() {
+    // Untyped resources have no shape and are left out of the statement; see
+    // the test below.
+    let edges = ResourceType::value_variants()
+      .iter()
+      .filter(|typ| **typ != ResourceType::Other)
+      .enumerate()
+      .map(|(index, typ)| Edge::new(node(&format!("Node{}", index), typ.clone()), node("my-queue.fifo", ResourceType::Sqs)))
+      .collect();
+    let ast = AST { edges, ..Default::default() };
+    let options = RenderOptions {
+      title: Some("stack".to_string()),
+      header: Some("Generated".to_string()),
+      ..Default::default()
+    };
+
+    assert!(flowchart(&Format::Mermaid.renderer(options).render(&ast)).is_ok());
+    assert!(flowchart("@startuml\n@enduml").is_ok());
+  }
+
+  #[test]
+  fn test_broken_statements_are_rejected() {
+    let ast = AST {
+      edges: vec![Edge::new(node("Role", ResourceType::Other), node("Worker", ResourceType::Lambda))],
+      ..Default::default()
+    };
+    let error = flowchart(&ast.to_mermaid()).unwrap_err().to_string();
+    assert_eq!(error, "mermaid would reject line 3 `--> Worker([Worker])`: empty node statement before `-->`");
+
+    let ast = AST {
+      subgraphs: vec![Subgraph {
+        name: "Api (v2)".to_string(),
+        ..Default::default()
+      }],
+      ..Default::default()
+    };
+    assert!(flowchart(&ast.to_flowchart()).unwrap_err().to_string().contains("`(` inside a label must be quoted"));
+
+    assert!(flowchart("flowchart LR\nend --> b").unwrap_err().to_string().contains("reserved word"));
+    assert!(flowchart("flowchart LR\na --> b(\"open").unwrap_err().to_string().contains("unterminated"));
+  }
+}
@@ -0,0 +1,286 @@
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+// `terraform show -json` output for a plan (`planned_values`) or a state
+// (`values`), rewritten as a CloudFormation-shaped document whose logical IDs
+// are the Terraform resource addresses.
+pub fn is_plan(value: &Value) -> bool {
+  value.get("format_version").is_some() && (value.get("planned_values").is_some() || value.get("values").is_some())
+}
+
+pub fn to_template_value(plan: &Value) -> Value {
+  let root = plan
+    .pointer("/planned_values/root_module")
+    .or_else(|| plan.pointer("/values/root_module"))
+    .cloned()
+    .unwrap_or(Value::Null);
+
+  let mut resources = Vec::new();
+  collect_resources(&root, &mut resources);
+
+  let mut references = HashMap::new();
+  if let Some(configuration) = plan.pointer("/configuration/root_module") {
+    collect_references(configuration, "", &mut references);
+  }
+
+  let known = known_values(&resources);
+  let addresses: Vec<String> = resources.iter().map(|r| r.address.clone()).collect();
+  let resolver = Resolver {
+    references: &references,
+    known: &known,
+    addresses: &addresses,
+  };
+
+  let mut cloudformation = Map::new();
+  for resource in &resources {
+    cloudformation.insert(resource.address.clone(), resolver.convert(resource));
+  }
+
+  json!({ "Resources": cloudformation })
+}
+
+struct PlanResource {
+  address: String,
+  typ: String,
+  values: Value,
+}
+
+struct Resolver<'a> {
+  references: &'a HashMap<(String, String), Vec<String>>,
+  known: &'a HashMap<String, String>,
+  addresses: &'a [String],
+}
+
+impl Resolver<'_> {
+  fn convert(&self, resource: &PlanResource) -> Value {
+    let values = &resource.values;
+
+    match resource.typ.as_str() {
+      "aws_lambda_function" => json!({
+        "Type": "AWS::Lambda::Function",
+        "Properties": {
+          "FunctionName": values.get("function_name").filter(|n| n.is_string()).cloned().unwrap_or(json!(resource.address)),
+          "Architectures": values.get("architectures").filter(|a| a.is_array()).cloned().unwrap_or(json!(["x86_64"])),
+        }
+      }),
+      "aws_sqs_queue" => json!({
+        "Type": "AWS::SQS::Queue",
+        "Properties": { "QueueName": values.get("name").filter(|n| n.is_string()).cloned().unwrap_or(json!(resource.address)) }
+      }),
+      "aws_dynamodb_table" => json!({
+        "Type": "AWS::DynamoDB::Table",
+        "Properties": { "TableName": values.get("name").filter(|n| n.is_string()).cloned().unwrap_or(json!(resource.address)) }
+      }),
+      "aws_api_gateway_integration" => json!({
+        "Type": "AWS::ApiGateway::Method",
+        "Properties": {
+          "HttpMethod": values.get("http_method").cloned().unwrap_or(json!("ANY")),
+          "Integration": { "Uri": self.get_att(resource, "uri", values) },
+        }
+      }),
+      "aws_lambda_event_source_mapping" => json!({
+        "Type": "AWS::Lambda::EventSourceMapping",
+        "Properties": {
+          "EventSourceArn": self.get_att(resource, "event_source_arn", values),
+          "FunctionName": self.reference(resource, "function_name", values),
+        }
+      }),
+      other => json!({
+        "Type": format!("Terraform::{}", other),
+        "Properties": values,
+      }),
+    }
+  }
+
+  fn reference(&self, resource: &PlanResource, attribute: &str, values: &Value) -> Value {
+    match self.target(resource, attribute, values) {
+      Some(address) => json!({ "Ref": address }),
+      None => values.get(attribute).cloned().unwrap_or(Value::Null),
+    }
+  }
+
+  fn get_att(&self, resource: &PlanResource, attribute: &str, values: &Value) -> Value {
+    match self.target(resource, attribute, values) {
+      Some(address) => json!({ "Fn::GetAtt": [address, "Arn"] }),
+      None => values.get(attribute).cloned().unwrap_or(Value::Null),
+    }
+  }
+
+  // Plans carry explicit expression references in `configuration`; states only
+  // have literal values, which are matched against other resources' ARNs/names.
+  fn target(&self, resource: &PlanResource, attribute: &str, values: &Value) -> Option<String> {
+    let configured = self
+      .references
+      .get(&(resource.address.clone(), attribute.to_string()))
+      .and_then(|references| references.iter().find(|r| self.addresses.contains(r)).cloned());
+
+    configured.or_else(|| {
+      let literal = values.get(attribute)?.as_str()?;
+      self.known.get(literal).cloned()
+    })
+  }
+}
+
+fn collect_resources(module: &Value, resources: &mut Vec<PlanResource>) {
+  for resource in module.get("resources").and_then(Value::as_array).into_iter().flatten() {
+    if resource.get("mode").and_then(Value::as_str) == Some("data") {
+      continue;
+    }
+
+    let (Some(address), Some(typ)) = (
+      resource.get("address").and_then(Value::as_str),
+      resource.get("type").and_then(Value::as_str),
+    ) else {
+      continue;
+    };
+
+    resources.push(PlanResource {
+      address: address.to_string(),
+      typ: typ.to_string(),
+      values: resource.get("values").cloned().unwrap_or(json!({})),
+    });
+  }
+
+  for child in module.get("child_modules").and_then(Value::as_array).into_iter().flatten() {
+    collect_resources(child, resources);
+  }
+}
+
+fn collect_references(module: &Value, prefix: &str, references: &mut HashMap<(String, String), Vec<String>>) {
+  for resource in module.get("resources").and_then(Value::as_array).into_iter().flatten() {
+    let Some(address) = resource.get("address").and_then(Value::as_str) else {
+      continue;
+    };
+    let Some(expressions) = resource.get("expressions").and_then(Value::as_object) else {
+      continue;
+    };
+
+    for (attribute, expression) in expressions {
+      let targets = expression
+        .get("references")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(|r| format!("{}{}", prefix, r))
+        .collect();
+
+      references.insert((format!("{}{}", prefix, address), attribute.clone()), targets);
+    }
+  }
+
+  for (name, call) in module.get("module_calls").and_then(Value::as_object).into_iter().flatten() {
+    if let Some(child) = call.get("module") {
+      collect_references(child, &format!("{}module.{}.", prefix, name), references);
+    }
+  }
+}
+
+fn known_values(resources: &[PlanResource]) -> HashMap<String, String> {
+  let mut known = HashMap::new();
+
+  for resource in resources {
+    let attributes: &[&str] = match resource.typ.as_str() {
+      "aws_lambda_function" => &["arn", "invoke_arn", "qualified_arn", "function_name"],
+      _ => &["arn"],
+    };
+
+    for attribute in attributes {
+      if let Some(value) = resource.values.get(*attribute).and_then(Value::as_str) {
+        known.insert(value.to_string(), resource.address.clone());
+      }
+    }
+  }
+
+  known
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ast::ast::AST;
+  use crate::cloudformation::template::Template;
+
+  use super::*;
+
+  #[test]
+  fn test_plan_to_template() {
+    let plan = json!({
+      "format_version": "1.2",
+      "planned_values": {
+        "root_module": {
+          "resources": [
+            { "address": "aws_sqs_queue.jobs", "mode": "managed", "type": "aws_sqs_queue", "values": { "name": "jobs" } },
+            {
+              "address": "aws_lambda_function.worker",
+              "mode": "managed",
+              "type": "aws_lambda_function",
+              "values": { "function_name": "worker", "architectures": ["arm64"] }
+            },
+            {
+              "address": "aws_lambda_event_source_mapping.jobs",
+              "mode": "managed",
+              "type": "aws_lambda_event_source_mapping",
+              "values": { "function_name": "worker" }
+            }
+          ]
+        }
+      },
+      "configuration": {
+        "root_module": {
+          "resources": [
+            {
+              "address": "aws_lambda_event_source_mapping.jobs",
+              "expressions": {
+                "event_source_arn": { "references": ["aws_sqs_queue.jobs.arn", "aws_sqs_queue.jobs"] },
+                "function_name": { "references": ["aws_lambda_function.worker.arn", "aws_lambda_function.worker"] }
+              }
+            }
+          ]
+        }
+      }
+    });
+
+    assert!(is_plan(&plan));
+
+    let template: Template = serde_json::from_value(to_template_value(&plan)).unwrap();
+    let expected_output = "```mermaid\nflowchart LR\naws_sqs_queue_jobs((\"jobs\")) -.->|triggers| aws_lambda_function_worker([\"worker\"])\n```";
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+
+  #[test]
+  fn test_state_resolves_literal_arns() {
+    let state = json!({
+      "format_version": "1.0",
+      "values": {
+        "root_module": {
+          "resources": [
+            {
+              "address": "aws_sqs_queue.jobs",
+              "mode": "managed",
+              "type": "aws_sqs_queue",
+              "values": { "name": "jobs", "arn": "arn:aws:sqs:eu-west-2:123456789012:jobs" }
+            },
+            {
+              "address": "aws_lambda_function.worker",
+              "mode": "managed",
+              "type": "aws_lambda_function",
+              "values": { "function_name": "worker", "architectures": null }
+            },
+            {
+              "address": "aws_lambda_event_source_mapping.jobs",
+              "mode": "managed",
+              "type": "aws_lambda_event_source_mapping",
+              "values": { "event_source_arn": "arn:aws:sqs:eu-west-2:123456789012:jobs", "function_name": "worker" }
+            }
+          ]
+        }
+      }
+    });
+
+    let template: Template = serde_json::from_value(to_template_value(&state)).unwrap();
+    let expected_output = "```mermaid\nflowchart LR\naws_sqs_queue_jobs((\"jobs\")) -.->|triggers| aws_lambda_function_worker([\"worker\"])\n```";
+
+    assert_eq!(AST::from(template).to_mermaid(), expected_output);
+  }
+}
@@ -0,0 +1,115 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const PRODUCER: &str = r#"{
+  "Resources": {
+    "Queue": { "Type": "AWS::SQS::Queue", "Properties": { "QueueName": "jobs" } }
+  },
+  "Outputs": {
+    "QueueArn": {
+      "Value": { "Fn::GetAtt": ["Queue", "Arn"] },
+      "Export": { "Name": { "Fn::Sub": "${AWS::StackName}:QueueArn" } }
+    }
+  }
+}"#;
+
+const CONSUMER: &str = r#"{
+  "Resources": {
+    "Worker": { "Type": "AWS::Lambda::Function", "Properties": { "FunctionName": "worker" } },
+    "Mapping": {
+      "Type": "AWS::Lambda::EventSourceMapping",
+      "Properties": { "EventSourceArn": { "Fn::ImportValue": "producer:QueueArn" }, "FunctionName": { "Ref": "Worker" } }
+    }
+  }
+}"#;
+
+const YAML_CONSUMER: &str = "
+Resources:
+  Worker:
+    Type: AWS::Lambda::Function
+    Properties:
+      FunctionName: worker
+  Mapping:
+    Type: AWS::Lambda::EventSourceMapping
+    Properties:
+      EventSourceArn: !ImportValue producer:QueueArn
+      FunctionName: !Ref Worker
+";
+
+// A directory of its own per test, as the tests run in parallel.
+fn templates(test: &str, files: &[(&str, &str)]) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("cloudmaid-cli-{}-{}", test, std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  for (name, content) in files {
+    fs::write(dir.join(name), content).unwrap();
+  }
+  dir
+}
+
+fn cloudmaid(dir: &Path, args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_cloudmaid"))
+    .current_dir(dir)
+    .arg("--no-config")
+    .args(args)
+    .output()
+    .unwrap()
+}
+
+#[test]
+fn test_exports_are_linked_across_templates() {
+  let dir = templates("linked", &[("producer.json", PRODUCER), ("consumer.json", CONSUMER)]);
+
+  let output = cloudmaid(&dir, &["--input-file", "producer.json", "consumer.json"]);
+
+  assert!(output.status.success());
+  let expected = concat!(
+    "```mermaid\nflowchart LR\n",
+    "subgraph producer\nQueue((\"jobs\"))\nend\n",
+    "subgraph consumer\nWorker([\"worker\"])\nend\n",
+    "Queue((\"jobs\")) ==>|triggers| Worker([\"worker\"])\n",
+    "```\n",
+  );
+  assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+#[test]
+fn test_yaml_imports_are_linked() {
+  let dir = templates("yaml", &[("producer.json", PRODUCER), ("consumer.yaml", YAML_CONSUMER)]);
+
+  let output = cloudmaid(&dir, &["--input-file", "producer.json", "consumer.yaml"]);
+
+  assert!(output.status.success());
+  assert!(String::from_utf8_lossy(&output.stdout).contains("Queue((\"jobs\")) ==>|triggers| Worker([\"worker\"])"));
+}
+
+#[test]
+fn test_unmatched_imports_are_not_linked() {
+  let renamed = PRODUCER.replace(":QueueArn", ":OtherArn");
+  let dir = templates("unmatched", &[("producer.json", &renamed), ("consumer.json", CONSUMER)]);
+
+  let output = cloudmaid(&dir, &["--input-file", "producer.json", "consumer.json"]);
+
+  assert!(output.status.success());
+  assert!(!String::from_utf8_lossy(&output.stdout).contains("==>"));
+}
+
+#[test]
+fn test_missing_template_fails() {
+  let dir = templates("missing", &[("producer.json", PRODUCER)]);
+
+  let output = cloudmaid(&dir, &["--input-file", "producer.json", "consumer.json"]);
+
+  assert_eq!(output.status.code(), Some(66));
+  assert!(String::from_utf8_lossy(&output.stderr).contains("consumer.json"));
+}
+
+#[test]
+fn test_subcommands_need_an_input() {
+  let dir = templates("noinput", &[]);
+
+  let output = cloudmaid(&dir, &["stats"]);
+
+  assert_eq!(output.status.code(), Some(64));
+  assert!(String::from_utf8_lossy(&output.stderr).contains("no input given"));
+}